@@ -1,24 +1,71 @@
+use crate::lex::source::{SourceMap, Span};
 use super::symbols as S;
 
-pub fn codegen(program: &S::Program) -> String {
+// Line tables are emitted (with `-g`) when given the source map of the program
+pub fn codegen(program: &S::Program, source: Option<&SourceMap>) -> String {
     let mut gen = String::new();
+    let mut lines = source.map(|source| LineTable { source, files: vec![] });
 
-    // FIXME: Allow more than one function
-    assert_eq!(program.len(), 1);
-    let program = &program[0];
+    for construct in program {
+        match construct {
+            S::Construct::Function(f) => gen += gen_function(f, lines.as_mut()).as_str(),
+            S::Construct::StaticVariable(v) => gen += gen_static_variable(v).as_str(),
+            S::Construct::StaticConstant(c) => gen += gen_static_constant(c).as_str()
+        }
+    }
+
+    gen
+}
 
-    // FIXME: fix this too
+// The assembler builds the DWARF line table from `.loc` directives, which refer
+// to files by the number a `.file` directive gives them before their first use
+struct LineTable<'a> {
+    source: &'a SourceMap,
+    files: Vec<String>
+}
+
+impl LineTable<'_> {
+    fn gen_location(&mut self, span: Span) -> String {
+        let location = self.source.locate(span.start);
+        let mut gen = String::new();
+
+        let number = match self.files.iter().position(|file| file == location.file) {
+            Some(index) => index + 1,
+            None => {
+                self.files.push(location.file.to_string());
+                let escaped = location.file.replace('\\', "\\\\").replace('"', "\\\"");
+                gen += format!("\t.file\t{} \"{}\"\n", self.files.len(), escaped).as_str();
+                self.files.len()
+            }
+        };
+
+        gen += format!("\t.loc\t{} {} {}\n", number, location.line, location.column).as_str();
+        gen
+    }
+}
 
+fn gen_function(function: &S::Function, mut lines: Option<&mut LineTable>) -> String {
+    let mut gen = String::new();
 
     // Function definition header
-    gen += "\t.globl _";
-    gen += program.identifier.as_str();
-    gen += "\n_";
-    gen += program.identifier.as_str();
+    if function.global {
+        gen += "\t.globl _";
+        gen += function.identifier.as_str();
+        gen += "\n";
+    }
+    gen += "\t.text\n_";
+    gen += function.identifier.as_str();
     gen += ":\n\tpushq\t%rbp\n\tmovq\t%rsp, %rbp\n";
 
 
-    for i in &program.instructions {
+    for i in &function.instructions {
+        if let S::Instruction::Location(span) = i {
+            if let Some(lines) = lines.as_mut() {
+                gen += lines.gen_location(*span).as_str();
+            }
+            continue;
+        }
+
         gen += "\t";
         gen += gen_instruction(i).as_str();
         gen += "\n";
@@ -27,45 +74,249 @@ pub fn codegen(program: &S::Program) -> String {
     gen
 }
 
+fn gen_static_variable(variable: &S::StaticVariable) -> String {
+    let mut gen = String::new();
+
+    if variable.global {
+        gen += "\t.globl _";
+        gen += variable.identifier.as_str();
+        gen += "\n";
+    }
+
+    // Zero-initialized objects go in .bss, which takes no space in the object file
+    if variable.init.iter().all(|init| matches!(init, S::StaticInit::Zero(_))) {
+        gen += "\t.bss\n";
+    } else {
+        gen += "\t.data\n";
+    }
+    gen += format!("\t.balign {}\n_", variable.alignment).as_str();
+    gen += variable.identifier.as_str();
+    gen += ":\n";
+
+    gen += gen_static_init(&variable.init).as_str();
+
+    gen
+}
+
+// Constants of 4, 8 or 16 bytes go in the literal sections of that size
+// (`__TEXT,__literal8` etc.), where the linker merges duplicates
+fn gen_static_constant(constant: &S::StaticConstant) -> String {
+    let mut gen = String::new();
+
+    let size: i32 = constant.init.iter().map(|init| match init {
+        S::StaticInit::Value(t, _) => t.size(),
+        S::StaticInit::Zero(bytes) => *bytes,
+        S::StaticInit::Address(..) => 8
+    }).sum();
+    gen += match size {
+        4 => "\t.literal4\n",
+        8 => "\t.literal8\n",
+        16 => "\t.literal16\n",
+        _ => "\t.const\n"
+    };
+    gen += format!("\t.balign {}\n_", constant.alignment).as_str();
+    gen += constant.identifier.as_str();
+    gen += ":\n";
+    gen += gen_static_init(&constant.init).as_str();
+
+    gen
+}
+
+// Floating values are given by their bit pattern
+fn gen_static_init(init: &[S::StaticInit]) -> String {
+    let mut gen = String::new();
+
+    for init in init {
+        match init {
+            S::StaticInit::Value(t, value) => {
+                let directive = match t {
+                    S::AsmType::Byte => ".byte",
+                    S::AsmType::Word => ".short",
+                    S::AsmType::Longword | S::AsmType::Single => ".long",
+                    S::AsmType::Quadword | S::AsmType::Double => ".quad"
+                };
+                gen += format!("\t{} {}\n", directive, value).as_str();
+            },
+            S::StaticInit::Zero(bytes) => {
+                gen += format!("\t.zero {}\n", bytes).as_str();
+            },
+            S::StaticInit::Address(symbol, 0) => {
+                gen += format!("\t.quad _{}\n", symbol).as_str();
+            },
+            S::StaticInit::Address(symbol, offset) => {
+                gen += format!("\t.quad _{}{:+}\n", symbol, offset).as_str();
+            }
+        }
+    }
+
+    gen
+}
+
 fn gen_instruction(i: &S::Instruction) -> String {
     match i {
-        S::Instruction::Mov(src, dst) => {
-            let mut instruction = String::new();
-            instruction += "movl\t";
-            instruction += gen_op(src).as_str();
-            instruction += ", ";
-            instruction += gen_op(dst).as_str();
+        S::Instruction::Mov(t, src, dst) => {
+            format!("mov{}\t{}, {}", t.suffix(), gen_op(src, *t), gen_op(dst, *t))
+        },
+
+        S::Instruction::Movsx(src_t, dst_t, src, dst) => {
+            format!("movs{}{}\t{}, {}", src_t.suffix(), dst_t.suffix(), gen_op(src, *src_t), gen_op(dst, *dst_t))
+        },
 
-            instruction
+        S::Instruction::MovZeroExtend(src_t, dst_t, src, dst) => {
+            format!("movz{}{}\t{}, {}", src_t.suffix(), dst_t.suffix(), gen_op(src, *src_t), gen_op(dst, *dst_t))
         },
 
+        S::Instruction::IntToFloat(src_t, dst_t, src, dst) => {
+            format!("cvtsi2{}{}\t{}, {}", dst_t.suffix(), src_t.suffix(), gen_op(src, *src_t), gen_op(dst, *dst_t))
+        },
+
+        S::Instruction::FloatToInt(src_t, dst_t, src, dst) => {
+            format!("cvtt{}2si{}\t{}, {}", src_t.suffix(), dst_t.suffix(), gen_op(src, *src_t), gen_op(dst, *dst_t))
+        },
+
+        S::Instruction::FloatConvert(src_t, dst_t, src, dst) => {
+            format!("cvt{}2{}\t{}, {}", src_t.suffix(), dst_t.suffix(), gen_op(src, *src_t), gen_op(dst, *dst_t))
+        },
+
+        S::Instruction::Lea(src, dst) => {
+            format!("leaq\t{}, {}", gen_op(src, S::AsmType::Quadword), gen_op(dst, S::AsmType::Quadword))
+        },
+
+        S::Instruction::Location(_) => panic!("Internal codegen error -- location emitted as an instruction"),
+
         S::Instruction::Ret => {
             "movq\t%rbp, %rsp\n\tpopq\t%rbp\n\tret".to_string()
         },
 
-        S::Instruction::Unary(op, dst) => {
-            let mut instruction = String::new();
+        S::Instruction::Unary(op, t, dst) => {
+            let instruction = match op {
+                S::UnaryOp::Neg => "neg",
+                S::UnaryOp::Not => "not"
+            };
+
+            format!("{}{}\t{}", instruction, t.suffix(), gen_op(dst, *t))
+        },
+
+        // Bitwise operations on XMM registers work on all of their
+        // 128 bits, which is what the `pd` and `ps` suffixes denote
+        S::Instruction::Binary(S::BinaryOp::Xor, t, src, dst) if t.is_floating() => {
+            let suffix = if *t == S::AsmType::Double { "pd" } else { "ps" };
+            format!("xor{}\t{}, {}", suffix, gen_op(src, *t), gen_op(dst, *t))
+        },
+
+        S::Instruction::Binary(op, t, src, dst) => {
+            let instruction = match op {
+                S::BinaryOp::Add => "add",
+                S::BinaryOp::Sub => "sub",
+                S::BinaryOp::Mult if t.is_floating() => "mul",
+                S::BinaryOp::Mult => "imul",
+                S::BinaryOp::DivFloat => "div",
+                S::BinaryOp::And => "and",
+                S::BinaryOp::Or => "or",
+                S::BinaryOp::Xor => "xor",
+                S::BinaryOp::Sal => "sal",
+                S::BinaryOp::Sar => "sar",
+                S::BinaryOp::Shr => "shr"
+            };
 
-            instruction += match op {
-                S::UnaryOp::Neg => "negl\t",
-                S::UnaryOp::Not => "notl\t"
+            // The count of a shift is always given by %cl
+            let src = match (op, src) {
+                (S::BinaryOp::Sal | S::BinaryOp::Sar | S::BinaryOp::Shr, S::Opd::Reg(r)) => r.operand(S::AsmType::Byte),
+                _ => gen_op(src, *t)
             };
 
-            instruction += gen_op(dst).as_str();
-            instruction
+            format!("{}{}\t{}, {}", instruction, t.suffix(), src, gen_op(dst, *t))
+        },
+
+        // Floating comparisons set the flags like unsigned ones, and PF if unordered
+        S::Instruction::Cmp(t, src, dst) if t.is_floating() => {
+            format!("comi{}\t{}, {}", t.suffix(), gen_op(src, *t), gen_op(dst, *t))
+        },
+
+        S::Instruction::Cmp(t, src, dst) => {
+            format!("cmp{}\t{}, {}", t.suffix(), gen_op(src, *t), gen_op(dst, *t))
+        },
+
+        S::Instruction::Idiv(t, src) => {
+            format!("idiv{}\t{}", t.suffix(), gen_op(src, *t))
+        },
+
+        S::Instruction::Div(t, src) => {
+            format!("div{}\t{}", t.suffix(), gen_op(src, *t))
+        },
+
+        S::Instruction::Cdq(t) => {
+            match t {
+                S::AsmType::Quadword => "cqto".to_string(),
+                _ => "cltd".to_string()
+            }
+        },
+
+        S::Instruction::SetCC(cc, dst) => {
+            format!("set{}\t{}", gen_cond(*cc), gen_op(dst, S::AsmType::Byte))
+        },
+
+        S::Instruction::Jmp(label) => {
+            format!("jmp\t.L{}", label)
+        },
+
+        S::Instruction::JmpCC(cc, label) => {
+            format!("j{}\t.L{}", gen_cond(*cc), label)
+        },
+
+        S::Instruction::Label(label) => {
+            format!(".L{}:", label)
         },
 
         S::Instruction::AllocateStack(size) => {
             "subq\t$".to_string() + &size.to_string() + ", %rsp"
+        },
+
+        S::Instruction::DeallocateStack(size) => {
+            "addq\t$".to_string() + &size.to_string() + ", %rsp"
+        },
+
+        S::Instruction::Push(opd) => {
+            "pushq\t".to_string() + &gen_op(opd, S::AsmType::Quadword)
+        },
+
+        S::Instruction::Call(name) => {
+            "call\t_".to_string() + name
+        },
+        S::Instruction::CallIndirect(target) => {
+            format!("call\t*{}", gen_op(target, S::AsmType::Quadword))
         }
     }
 }
 
-fn gen_op(v: &S::Opd) -> String {
+fn gen_cond(cc: S::CondCode) -> &'static str {
+    match cc {
+        S::CondCode::E => "e",
+        S::CondCode::NE => "ne",
+        S::CondCode::L => "l",
+        S::CondCode::LE => "le",
+        S::CondCode::G => "g",
+        S::CondCode::GE => "ge",
+        S::CondCode::B => "b",
+        S::CondCode::BE => "be",
+        S::CondCode::A => "a",
+        S::CondCode::AE => "ae",
+        S::CondCode::P => "p",
+        S::CondCode::NP => "np"
+    }
+}
+
+fn gen_op(v: &S::Opd, t: S::AsmType) -> String {
     match v {
-        S::Opd::Reg(r) => { r.operand() },
+        S::Opd::Reg(r) => { r.operand(t) },
         S::Opd::Imm(imm) => { "$".to_string() + imm.as_str() },
-        S::Opd::Stack(offset) => { offset.to_string() + "(%rbp)" }
+        S::Opd::Stack(offset) => { offset.to_string() + "(%rbp)" },
+        S::Opd::Data(name, 0) => { "_".to_string() + name + "(%rip)" },
+        S::Opd::Data(name, offset) => { format!("_{}+{}(%rip)", name, offset) },
+        S::Opd::Memory(r, offset) => { format!("{}({})", offset, r.operand(S::AsmType::Quadword)) }
     }
 }
 
+#[cfg(test)]
+#[path = "./test.rs"]
+mod agen_test;
@@ -1,20 +1,143 @@
-
 use crate::zil::symbols as Z;
+use crate::ast::symbols::{Span, Type};
+
+pub type Program = Vec<Construct>;
 
-pub type Program = Vec<Function>;
+#[derive(Debug)]
+pub enum Construct {
+    Function(Function),
+    StaticVariable(StaticVariable),
+    StaticConstant(StaticConstant)
+}
 
 #[derive(Debug)]
 pub struct Function {
     pub identifier: String,
+    pub global: bool,
     pub instructions: Vec<Instruction>,
 }
 
+#[derive(Debug)]
+pub struct StaticVariable {
+    pub identifier: String,
+    pub global: bool,
+    pub alignment: i32,
+    pub init: Vec<StaticInit>
+}
+
+// Read-only constants, such as floating literals, which can't be immediates
+#[derive(Debug, Clone, PartialEq)]
+pub struct StaticConstant {
+    pub identifier: String,
+    pub alignment: i32,
+    pub init: Vec<StaticInit>
+}
+
+// Initial value of a static object, in the order it is laid out in memory
+#[derive(Debug, Clone, PartialEq)]
+pub enum StaticInit {
+    Value(AsmType, String),
+    Zero(i32),
+    Address(String, i64)
+}
+
+// Operand size of an instruction, which determines its suffix
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AsmType {
+    Byte,
+    Word,
+    Longword,
+    Quadword,
+    // Floating values, held in XMM registers
+    Single,
+    Double
+}
+
+impl AsmType {
+    pub fn size(&self) -> i32 {
+        match self {
+            AsmType::Byte => 1,
+            AsmType::Word => 2,
+            AsmType::Longword | AsmType::Single => 4,
+            AsmType::Quadword | AsmType::Double => 8
+        }
+    }
+
+    pub fn is_floating(&self) -> bool {
+        matches!(self, AsmType::Single | AsmType::Double)
+    }
+
+    // Integer type of the same size, to move the bits of a floating value around
+    pub fn bits(&self) -> AsmType {
+        match self {
+            AsmType::Single => AsmType::Longword,
+            AsmType::Double => AsmType::Quadword,
+            _ => *self
+        }
+    }
+
+    pub fn suffix(&self) -> &'static str {
+        match self {
+            AsmType::Byte => "b",
+            AsmType::Word => "w",
+            AsmType::Longword => "l",
+            AsmType::Quadword => "q",
+            AsmType::Single => "ss",
+            AsmType::Double => "sd"
+        }
+    }
+}
+
+impl From<&Type> for AsmType {
+    fn from(ty: &Type) -> Self {
+        match ty.unqualified() {
+            Type::Float => return AsmType::Single,
+            Type::Double => return AsmType::Double,
+            _ => {}
+        }
+
+        match ty.size() {
+            1 => AsmType::Byte,
+            2 => AsmType::Word,
+            4 => AsmType::Longword,
+            _ => AsmType::Quadword
+        }
+    }
+}
+
 #[derive(Debug)]
 pub enum Instruction {
-    Mov(Opd, Opd),
-    Unary(UnaryOp, Opd),
-    AllocateStack(i8),
-    Ret
+    Mov(AsmType, Opd, Opd),
+    // Sign and zero extension, from the first type to the second
+    Movsx(AsmType, AsmType, Opd, Opd),
+    MovZeroExtend(AsmType, AsmType, Opd, Opd),
+    // Conversions between signed integers and floating values (cvtsi2sd and
+    // cvttsd2si, which truncates), and between floating types (cvtss2sd, cvtsd2ss)
+    IntToFloat(AsmType, AsmType, Opd, Opd),
+    FloatToInt(AsmType, AsmType, Opd, Opd),
+    FloatConvert(AsmType, AsmType, Opd, Opd),
+    // Loads the address of a memory operand
+    Lea(Opd, Opd),
+    Unary(UnaryOp, AsmType, Opd),
+    Binary(BinaryOp, AsmType, Opd, Opd),
+    Cmp(AsmType, Opd, Opd),
+    Idiv(AsmType, Opd),
+    Div(AsmType, Opd),
+    // Sign extends %eax into %edx (or %rax into %rdx) before a division
+    Cdq(AsmType),
+    SetCC(CondCode, Opd),
+    Jmp(String),
+    JmpCC(CondCode, String),
+    Label(String),
+    AllocateStack(i32),
+    DeallocateStack(i32),
+    Push(Opd),
+    Call(String),
+    // Calls the function an operand points to
+    CallIndirect(Opd),
+    Ret,
+    // Marks where the code of a statement starts, emitted as a `.loc` directive with `-g`
+    Location(Span)
 }
 
 #[derive(Debug)]
@@ -32,25 +155,130 @@ impl From<Z::UnaryInstructionOperator> for UnaryOp {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BinaryOp {
+    Add,
+    Sub,
+    Mult,
+    And,
+    Or,
+    Xor,
+    Sal,
+    // Arithmetic and logical right shifts, for signed and unsigned operands
+    Sar,
+    Shr,
+    // Floating division; integers are divided with idiv and div
+    DivFloat
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum CondCode {
+    E,
+    NE,
+    // Signed comparisons
+    L,
+    LE,
+    G,
+    GE,
+    // Unsigned comparisons
+    B,
+    BE,
+    A,
+    AE,
+    // Set when a floating comparison is unordered, i.e. involves a NaN
+    P,
+    NP
+}
+
 #[derive(Debug, Clone)]
 pub enum Opd { // Operand
     Imm(String),
     Reg(Reg),
-    Stack(i8),
+    Stack(i32),
+    Data(String, i32), // RIP-relative reference to (an offset within) an object with static storage
+    Memory(Reg, i32) // Object at an offset from the address held in a register
 }
 
-#[derive(Debug, Clone)]
+impl Opd {
+    pub fn is_memory(&self) -> bool {
+        matches!(self, Opd::Stack(_) | Opd::Data(_, _) | Opd::Memory(_, _))
+    }
+
+    // The memory operand `bytes` further into the same object
+    pub fn offset(&self, bytes: i32) -> Opd {
+        match self {
+            Opd::Stack(offset) => Opd::Stack(offset + bytes),
+            Opd::Data(name, offset) => Opd::Data(name.clone(), offset + bytes),
+            Opd::Memory(r, offset) => Opd::Memory(r.clone(), offset + bytes),
+            _ => panic!("Internal assembly error -- offset of a non-memory operand")
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum Reg {
     AX,
-    R10D
+    CX,
+    DX,
+    DI,
+    SI,
+    R8,
+    R9,
+    R10,
+    R11,
+    SP,
+    XMM0,
+    XMM1,
+    XMM2,
+    XMM3,
+    XMM4,
+    XMM5,
+    XMM6,
+    XMM7,
+    // Scratch registers for rewriting floating instructions
+    XMM14,
+    XMM15
 }
 
 impl Reg {
-    pub fn operand(&self) -> String {
-        match self {
-            Reg::AX => "%eax".into(),
-            Reg::R10D => "%r10d".into(),
+    // Registers used to pass the first six integer arguments
+    pub const ARGUMENTS: [Reg; 6] = [Reg::DI, Reg::SI, Reg::DX, Reg::CX, Reg::R8, Reg::R9];
+    // And the first eight floating arguments
+    pub const FLOAT_ARGUMENTS: [Reg; 8] = [Reg::XMM0, Reg::XMM1, Reg::XMM2, Reg::XMM3, Reg::XMM4, Reg::XMM5, Reg::XMM6, Reg::XMM7];
+
+    pub fn is_xmm(&self) -> bool {
+        matches!(self, Reg::XMM0 | Reg::XMM1 | Reg::XMM2 | Reg::XMM3 | Reg::XMM4 | Reg::XMM5 | Reg::XMM6 | Reg::XMM7 | Reg::XMM14 | Reg::XMM15)
+    }
+
+    // Name of the register when accessed with the given operand size;
+    // XMM registers have the same name for any size
+    pub fn operand(&self, asm_type: AsmType) -> String {
+        let (b, w, l, q) = match self {
+            Reg::AX => ("%al", "%ax", "%eax", "%rax"),
+            Reg::CX => ("%cl", "%cx", "%ecx", "%rcx"),
+            Reg::DX => ("%dl", "%dx", "%edx", "%rdx"),
+            Reg::DI => ("%dil", "%di", "%edi", "%rdi"),
+            Reg::SI => ("%sil", "%si", "%esi", "%rsi"),
+            Reg::R8 => ("%r8b", "%r8w", "%r8d", "%r8"),
+            Reg::R9 => ("%r9b", "%r9w", "%r9d", "%r9"),
+            Reg::R10 => ("%r10b", "%r10w", "%r10d", "%r10"),
+            Reg::R11 => ("%r11b", "%r11w", "%r11d", "%r11"),
+            Reg::SP => ("%spl", "%sp", "%esp", "%rsp"),
+            xmm => {
+                let index = match xmm {
+                    Reg::XMM14 => 14,
+                    Reg::XMM15 => 15,
+                    _ => Reg::FLOAT_ARGUMENTS.iter().position(|r| r == xmm).expect("XMM register")
+                };
+                return format!("%xmm{}", index);
+            }
+        };
+
+        match asm_type.bits() {
+            AsmType::Byte => b.into(),
+            AsmType::Word => w.into(),
+            AsmType::Longword => l.into(),
+            _ => q.into()
         }
     }
 }
-
@@ -0,0 +1,217 @@
+mod assembly_tests {
+    use crate::assembly::{agen, transpile::STranspiler};
+    use crate::ast::parser::ASTParser;
+    use crate::lexer::{Lexer, Tag};
+
+    // Compiles a source string through every stage, down to the assembly emitted for it
+    fn compile(src: &str) -> String {
+        let mut lexer = Lexer::load_test_str(src);
+        let mut tokens = vec![];
+        loop {
+            let token = lexer.next();
+            if token.tag == Tag::Eof {
+                break;
+            }
+            tokens.push(token);
+        }
+
+        let program = ASTParser::new(src.to_string(), tokens).parse().expect("Program should parse");
+        let (program, mut symbols, _) = crate::ast::resolve::resolve(program).expect("Program should resolve");
+        let (program, _) = crate::ast::typecheck::typecheck(program, &symbols).expect("Program should typecheck");
+        let intermediate = crate::zil::transpile::parse(program, &mut symbols);
+        let assembled = STranspiler::new(symbols).parse(intermediate);
+        return agen::codegen(&assembled, None);
+    }
+
+    // The instructions and local labels of function `name`, up to the next symbol or
+    // section, with their operands separated by a space, as in `movl $0, %eax`
+    fn function(asm: &str, name: &str) -> Vec<String> {
+        let label = format!("_{}:", name);
+        return asm.lines()
+            .skip_while(|line| *line != label)
+            .skip(1)
+            .take_while(|line| !line.starts_with('_') && (!line.starts_with("\t.") || line.starts_with("\t.L")))
+            .map(|line| line.trim().replace('\t', " "))
+            .collect();
+    }
+
+    // Index of the first instruction starting with `prefix`, e.g. `comisd` or `movl $1, %eax`
+    fn find(instructions: &[String], prefix: &str) -> Option<usize> {
+        return instructions.iter().position(|i| i.starts_with(prefix));
+    }
+
+    #[test]
+    fn test_floating_arithmetic() {
+        let asm = compile("double f(double a, double b) { return a * b + a / b - 1.5; }\ndouble g(long l) { return l; }\nlong h(double d) { return (long)d; }");
+
+        // Arguments and return values are passed in XMM registers
+        let f = function(&asm, "f");
+        assert!(find(&f, "movsd %xmm0, ").is_some());
+        assert!(find(&f, "movsd %xmm1, ").is_some());
+        for op in ["mulsd", "divsd", "addsd", "subsd"] {
+            assert!(find(&f, op).is_some(), "{} missing from {:?}", op, f);
+        }
+        assert!(f.iter().any(|i| i.starts_with("subsd _double.") && i.contains("(%rip), %xmm")));
+        let ret = find(&f, "ret").expect("`f` should return");
+        assert!(f[ret - 3].starts_with("movsd") && f[ret - 3].ends_with(", %xmm0"));
+
+        assert!(find(&function(&asm, "g"), "cvtsi2sdq").is_some());
+        assert!(find(&function(&asm, "h"), "cvttsd2siq").is_some());
+    }
+
+    #[test]
+    fn test_floating_comparisons() {
+        let asm = compile(
+            "int lt(double a, double b) { return a < b; }\nint eq(double a, double b) { return a == b; }\nint ne(double a, double b) { return a != b; }"
+        );
+
+        // `a < b` is tested as `b > a`, as `seta` is false when either operand is NaN,
+        // which sets the carry flag, unlike `setb`
+        let lt = function(&asm, "lt");
+        let compare = find(&lt, "comisd").expect("`<` should use comisd");
+        assert!(lt[compare].starts_with("comisd -8(%rbp)") || lt[compare - 1].starts_with("movsd -16(%rbp)"));
+        assert!(find(&lt, "seta").is_some());
+        assert!(find(&lt, "setb").is_none());
+
+        // Unordered operands set the parity flag, and the zero flag along with it
+        let eq = function(&asm, "eq");
+        let (sete, setnp, and) = (find(&eq, "sete"), find(&eq, "setnp"), find(&eq, "andb"));
+        assert!(find(&eq, "comisd") < sete && sete < setnp && setnp < and, "{:?}", eq);
+        let ne = function(&asm, "ne");
+        let (setne, setp, or) = (find(&ne, "setne"), find(&ne, "setp "), find(&ne, "orb"));
+        assert!(find(&ne, "comisd") < setne && setne < setp && setp < or, "{:?}", ne);
+    }
+
+    #[test]
+    fn test_floating_arguments() {
+        let asm = compile(
+            "double g(double x, int n, float y);\ndouble f(void) { return g(1.5, 2, 2.5f); }\nint printf(const char *format, ...);\nint p(double d) { return printf(\"%f\", d); }"
+        );
+
+        // Floating arguments take the XMM registers in order, apart from the integer ones
+        let f = function(&asm, "f");
+        let call = find(&f, "call _g").expect("`f` should call `g`");
+        let x = f.iter().position(|i| i.starts_with("movsd _double.") && i.ends_with(", %xmm0")).expect("`x` should be in %xmm0");
+        let n = find(&f, "movl $2, %edi").expect("`n` should be in %edi");
+        let y = f.iter().position(|i| i.starts_with("movss _float.") && i.ends_with(", %xmm1")).expect("`y` should be in %xmm1");
+        assert!(x < call && n < call && y < call);
+        assert!(find(&f, "movl $1, %eax").is_none());
+
+        // Calls to variadic functions tell how many vector registers hold arguments
+        let p = function(&asm, "p");
+        let call = find(&p, "call _printf").expect("`p` should call `printf`");
+        assert!(find(&p, "movsd -8(%rbp), %xmm0") < Some(call));
+        assert_eq!(p[call - 1], "movl $1, %eax");
+    }
+
+    #[test]
+    fn test_constant_pools() {
+        let asm = compile("float f(void) { return 2.5f; }\ndouble g(double x) { return -x + 1.5; }\ndouble h(void) { return 1.5; }");
+        let lines: Vec<&str> = asm.lines().collect();
+        let section = |label: &str| {
+            let index = lines.iter().position(|line| line.starts_with(label)).expect("Constant should be emitted");
+            lines[index - 2]
+        };
+
+        // Constants go in the literal section of their size, where the linker merges
+        // duplicates, and each value is only emitted once
+        assert_eq!(section("_float."), "\t.literal4");
+        let double = lines.iter().position(|line| *line == "\t.quad 4609434218613702656").expect("1.5 should be emitted");
+        assert_eq!(lines[double - 3], "\t.literal8");
+        assert_eq!(lines.iter().filter(|line| **line == "\t.quad 4609434218613702656").count(), 1);
+
+        // The sign mask of a negation is read 16 bytes at a time, so it is padded to that size
+        let xorpd = function(&asm, "g").into_iter().find(|i| i.starts_with("xorpd")).expect("Negation should use xorpd");
+        let mask = xorpd.split(' ').nth(1).and_then(|operand| operand.strip_suffix("(%rip),")).expect("Mask should be a constant");
+        let index = lines.iter().position(|line| *line == format!("{}:", mask)).expect("Mask should be emitted");
+        assert_eq!(&lines[index - 2..index + 3], &["\t.literal16", "\t.balign 16", lines[index], "\t.quad 9223372036854775808", "\t.zero 8"]);
+        assert!(!asm.contains(".rodata"));
+    }
+
+    #[test]
+    fn test_integer_codegen() {
+        let asm = compile(
+            "long w(char c, unsigned short s) { return c + s; }\nunsigned u(unsigned a, unsigned b) { return a / b >> 1; }\nint s(int a, int b) { return a / b >> 1; }\nint a(unsigned x, unsigned y) { return x > y; }"
+        );
+
+        // Operands are sign or zero extended by their own type, to the size of the operation
+        let w = function(&asm, "w");
+        assert!(find(&w, "movb %dil, ").is_some() && find(&w, "movw %si, ").is_some());
+        assert!(find(&w, "movsbl").is_some() && find(&w, "movzwl").is_some());
+        assert!(find(&w, "addl").is_some() && find(&w, "movslq").is_some());
+
+        // Unsigned division clears %edx and shifts are logical; signed ones extend the sign
+        let u = function(&asm, "u");
+        assert!(find(&u, "movl $0, %edx") < find(&u, "divl") && find(&u, "shrl").is_some());
+        assert!(find(&u, "idivl").is_none());
+        let s = function(&asm, "s");
+        assert!(find(&s, "cltd") < find(&s, "idivl") && find(&s, "sarl").is_some());
+        assert!(find(&function(&asm, "a"), "seta").is_some());
+    }
+
+    #[test]
+    fn test_structure_codegen() {
+        let asm = compile(concat!(
+            "struct big { long a, b, c; };\nstruct pair { long x; double y; };\n",
+            "long m(struct big *p) { return p->c; }\n",
+            "struct pair r(long x) { struct pair p = { x, 2.5 }; return p; }\n",
+            "struct big c(struct big b) { return b; }"
+        ));
+
+        // Members are found at their offset
+        assert!(find(&function(&asm, "m"), "addq $16, ").is_some());
+
+        // A structure of an INTEGER and an SSE eightbyte is returned in %rax and %xmm0
+        let r = function(&asm, "r");
+        let ret = find(&r, "ret").expect("`r` should return");
+        assert!(r[..ret].iter().any(|i| i.starts_with("movq") && i.ends_with(", %rax")));
+        assert!(r[..ret].iter().any(|i| i.starts_with("movsd") && i.ends_with(", %xmm0")));
+
+        // Larger structures are MEMORY: they are returned through the pointer passed in
+        // %rdi, which is also returned in %rax, and copied eightbyte by eightbyte
+        let c = function(&asm, "c");
+        assert_eq!(&c[..4], &["pushq %rbp", "movq %rsp, %rbp", "subq $32, %rsp", "movq %rdi, -8(%rbp)"]);
+        let ret = find(&c, "ret").expect("`c` should return");
+        let copied: Vec<&String> = c[..ret].iter().filter(|i| i.starts_with("movq") && i.ends_with("(%rax)")).collect();
+        assert_eq!(copied.len(), 3);
+        assert!(c[..ret].iter().any(|i| i == "movq -8(%rbp), %rax"));
+    }
+
+    #[test]
+    fn test_variadic_codegen() {
+        let asm = compile(
+            "long f(int n, ...) { __builtin_va_list ap; __builtin_va_start(ap, n); long x = __builtin_va_arg(ap, long); __builtin_va_end(ap); return x; }"
+        );
+
+        // Every argument register is saved in the register save area of the prologue
+        let f = function(&asm, "f");
+        for reg in ["%rdi", "%rsi", "%rdx", "%rcx", "%r8", "%r9"] {
+            assert!(find(&f, &format!("movq {}, ", reg)).is_some(), "{} should be saved", reg);
+        }
+        for xmm in 0..8 {
+            assert!(find(&f, &format!("movsd %xmm{}, ", xmm)).is_some(), "%xmm{} should be saved", xmm);
+        }
+
+        // One named integer parameter was passed, so `gp_offset` starts at 8 and
+        // `fp_offset` at 48, and a `long` comes from the stack once past 40
+        assert!(find(&f, "movl $8, 0(%rax)") < find(&f, "movl $48, 4(%rax)"));
+        assert!(find(&f, "cmpl $40, 0(%rax)").is_some());
+        assert!(f.iter().any(|i| i.starts_with(".Lva_arg.stack")));
+    }
+
+    #[test]
+    fn test_variable_length_array_codegen() {
+        let asm = compile("int f(int n) { { int a[n]; a[0] = 1; } return 0; }");
+
+        // The array is allocated below the frame, keeping the stack aligned, and
+        // freed by restoring the stack pointer saved when its block was entered
+        let f = function(&asm, "f");
+        let save = f.iter().position(|i| i.starts_with("movq %rsp, ") && i.ends_with("(%rbp)")).expect("%rsp should be saved");
+        let saved = f[save].trim_start_matches("movq %rsp, ").to_string();
+        let allocate = f.iter().position(|i| i.starts_with("subq") && i.ends_with(", %rsp") && !i.starts_with("subq $")).expect("The array should be allocated");
+        assert_eq!(f[allocate + 1], "andq $-16, %rsp");
+        let restore = f.iter().position(|i| *i == format!("movq {}, %rsp", saved)).expect("%rsp should be restored");
+        assert!(save < allocate && allocate < restore);
+        assert!(f[restore..].iter().any(|i| i == "movq %rbp, %rsp"));
+    }
+}
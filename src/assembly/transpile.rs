@@ -1,18 +1,49 @@
 use std::collections::HashMap;
 use crate::zil;
 use zil::symbols as Z;
-use crate::assembly::symbols::Opd;
+use crate::ast::symbols::Type;
+use crate::ast::table::{SymbolTable, StaticInit};
+use crate::assembly::symbols::{Opd, AsmType};
 use crate::assembly::symbols::Opd::Reg;
 use super::symbols as S;
 
+// Classes of the System V calling convention, given to each eightbyte of
+// an argument or return value. Structures larger than 16 bytes are passed
+// in memory, and eightbytes of smaller ones in XMM registers if they only
+// hold floating values, and in general-purpose registers otherwise
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Class {
+    Integer,
+    Sse,
+    Memory
+}
+
+// Where a variadic function keeps what `va_start` needs: the registers arguments may
+// have been passed in, saved in the layout of the ABI's register save area (six
+// general-purpose registers, then eight XMM registers of 16 bytes each), how much of
+// it the named parameters use, and where the arguments passed on the stack start
+struct VaFrame {
+    save_area: i32,
+    gp_offset: usize,
+    fp_offset: usize,
+    overflow_area: i32
+}
+
 pub struct STranspiler {
-    stack_map: HashMap<String, i8>,
-    curr_offset: i8
+    stack_map: HashMap<String, i32>,
+    curr_offset: i32,
+    // Where the caller asked for a structure returned in memory to be written
+    return_pointer: Option<Opd>,
+    va_frame: Option<VaFrame>,
+    // Floating constants, and the number of labels made so far
+    constants: Vec<S::StaticConstant>,
+    label_count: usize,
+    symbols: SymbolTable
 }
 
 impl STranspiler {
-    pub fn new() -> STranspiler {
-        STranspiler { stack_map: HashMap::new(), curr_offset: 0 }
+    pub fn new(symbols: SymbolTable) -> STranspiler {
+        STranspiler { stack_map: HashMap::new(), curr_offset: 0, return_pointer: None, va_frame: None, constants: vec![], label_count: 0, symbols }
     }
 
     pub fn parse(&mut self, program: zil::symbols::Program) -> S::Program {
@@ -23,72 +54,1178 @@ impl STranspiler {
         for c in program {
             match c {
                 Z::Construct::Function(def) => {
-                    let mut parsed = self.parse_instructions(def.instructions);
+                    // Every function gets its own stack frame
+                    self.stack_map.clear();
+                    self.curr_offset = 0;
+
+                    let (return_type, variadic) = match self.symbols.type_of(&def.identifier) {
+                        Type::Function(f) => (*f.ret.clone(), f.variadic),
+                        _ => panic!("Internal assembly error -- function `{}` without function type", def.identifier)
+                    };
+
+                    let mut parsed = self.parse_parameters(def.params, &return_type, variadic);
+                    parsed.extend(self.parse_instructions(def.instructions));
 
-                    // FIXME: insert stack allocation in the correct place
-                    parsed.insert(0, S::Instruction::AllocateStack(self.curr_offset * -1));
+                    // Second pass - rewrite instructions with invalid operands
+                    let mut parsed = Self::fixup_instructions(parsed);
 
-                    new.push(S::Function {
+                    // The stack is kept 16-byte aligned, as required by the calling convention
+                    let stack_size = (-self.curr_offset + 15) / 16 * 16;
+                    parsed.insert(0, S::Instruction::AllocateStack(stack_size));
+
+                    new.push(S::Construct::Function(S::Function {
                         identifier: def.identifier,
+                        global: def.global,
                         instructions: parsed
-                    });
+                    }));
+                },
+
+                Z::Construct::StaticVariable(def) => {
+                    let init = def.init.into_iter().map(|init| match init {
+                        StaticInit::Value(ty, value) if ty.is_floating() => {
+                            let t = AsmType::from(&ty);
+                            S::StaticInit::Value(t, Self::float_bits(value.to_float(), t))
+                        },
+                        StaticInit::Value(ty, value) => S::StaticInit::Value(AsmType::from(&ty), value.to_string()),
+                        StaticInit::Zero(bytes) => S::StaticInit::Zero(bytes as i32),
+                        StaticInit::Address(symbol, offset) => S::StaticInit::Address(symbol, offset)
+                    }).collect();
+
+                    new.push(S::Construct::StaticVariable(S::StaticVariable {
+                        identifier: def.identifier,
+                        global: def.global,
+                        alignment: self.symbols.alignment_of(&def.ty) as i32,
+                        init
+                    }));
                 }
             }
         }
 
+        new.extend(self.constants.drain(..).map(S::Construct::StaticConstant));
 
         return new;
     }
 
+    // Copies the parameters out of the registers and stack slots where
+    // the caller placed them (see `parse_function_call`)
+    fn parse_parameters(&mut self, params: Vec<String>, return_type: &Type, variadic: bool) -> Vec<S::Instruction> {
+        let mut tp: Vec<S::Instruction> = vec![];
+        let mut next_register = 0;
+        let mut next_float = 0;
+
+        // Variable arguments may be in any argument register, so they are all saved
+        // before the parameters are copied out of them
+        let save_area = match variadic {
+            true => {
+                let Opd::Stack(save_area) = self.allocate_stack(176, 16) else { unreachable!() };
+                for (idx, register) in S::Reg::ARGUMENTS.iter().enumerate() {
+                    tp.push(S::Instruction::Mov(AsmType::Quadword, Reg(register.clone()), Opd::Stack(save_area + 8 * idx as i32)));
+                }
+                for (idx, register) in S::Reg::FLOAT_ARGUMENTS.iter().enumerate() {
+                    tp.push(S::Instruction::Mov(AsmType::Double, Reg(register.clone()), Opd::Stack(save_area + 48 + 16 * idx as i32)));
+                }
+                Some(save_area)
+            },
+            false => None
+        };
+
+        // The address to return a structure in memory to is passed as a hidden first argument
+        self.return_pointer = None;
+        if self.classify(return_type)[0] == Class::Memory {
+            let slot = self.allocate_stack(8, 8);
+            tp.push(S::Instruction::Mov(AsmType::Quadword, Reg(S::Reg::DI), slot.clone()));
+            self.return_pointer = Some(slot);
+            next_register = 1;
+        }
+
+        // Past the saved %rbp and the return address
+        let mut stack_offset = 16;
+        for param in params {
+            let param = Z::Value::Variable(param);
+            let ty = self.value_type(&param);
+            let classes = self.classify(&ty);
+            let registers = Self::argument_registers(&classes, &mut next_register, &mut next_float);
+
+            if let Type::Structure(_, _) = ty {
+                let size = self.symbols.size_of(&ty);
+                let dst = self.parse_value(param);
+
+                match registers {
+                    Some(registers) => {
+                        for (idx, register) in registers.into_iter().enumerate() {
+                            let (offset, bytes) = (8 * idx, (size - 8 * idx).min(8));
+                            if register.is_xmm() {
+                                tp.push(S::Instruction::Mov(Self::eightbyte_type(bytes), Reg(register), dst.offset(offset as i32)));
+                            } else {
+                                Self::store_eightbyte(&mut tp, register, &dst, offset, bytes);
+                            }
+                        }
+                    },
+                    None => {
+                        Self::copy_bytes(&mut tp, &Opd::Stack(stack_offset), &dst, size);
+                        stack_offset += 8 * classes.len() as i32;
+                    }
+                }
+                continue;
+            }
+
+            let t = self.asm_type(&param);
+            let dst = self.parse_value(param);
+            match registers {
+                Some(registers) => tp.push(S::Instruction::Mov(t, Reg(registers[0].clone()), dst)),
+                None => {
+                    tp.push(S::Instruction::Mov(t, Opd::Stack(stack_offset), dst));
+                    stack_offset += 8;
+                }
+            }
+        }
+
+        self.va_frame = save_area.map(|save_area| VaFrame {
+            save_area,
+            gp_offset: 8 * next_register,
+            fp_offset: 48 + 16 * next_float,
+            overflow_area: stack_offset
+        });
+
+        tp
+    }
+
     fn parse_instructions(&mut self, instructions: Vec<Z::Instruction>) -> Vec<S::Instruction> {
         let mut tp: Vec<S::Instruction> = vec![];
 
         for i in instructions {
             match i {
-                Z::Instruction::Return(val) => {
+                // Small structures are returned in %rax and %rdx, or %xmm0 and %xmm1, and larger
+                // ones are copied to the memory provided by the caller, whose address is returned
+                // in %rax. Floating values are returned in %xmm0
+                Z::Instruction::Return(Some(val)) if self.is_structure(&val) => {
+                    let ty = self.value_type(&val);
+                    let size = self.symbols.size_of(&ty);
+                    let val = self.parse_value(val);
+
+                    match self.return_pointer.clone() {
+                        Some(pointer) => {
+                            tp.push(S::Instruction::Mov(AsmType::Quadword, pointer, Reg(S::Reg::AX)));
+                            Self::copy_bytes(&mut tp, &val, &Opd::Memory(S::Reg::AX, 0), size);
+                        },
+                        None => {
+                            for (idx, register) in Self::return_registers(&self.classify(&ty)).into_iter().enumerate() {
+                                let (offset, bytes) = (8 * idx, (size - 8 * idx).min(8));
+                                if register.is_xmm() {
+                                    tp.push(S::Instruction::Mov(Self::eightbyte_type(bytes), val.offset(offset as i32), Reg(register)));
+                                } else {
+                                    Self::load_eightbyte(&mut tp, &val, offset, bytes, register);
+                                }
+                            }
+                        }
+                    }
+                    tp.push(S::Instruction::Ret);
+                },
+                Z::Instruction::Return(Some(val)) => {
+                    let t = self.asm_type(&val);
                     let val = self.parse_value(val);
 
-                    tp.push(S::Instruction::Mov(val, Opd::Reg(S::Reg::AX)));
+                    let register = if t.is_floating() { S::Reg::XMM0 } else { S::Reg::AX };
+                    tp.push(S::Instruction::Mov(t, val, Opd::Reg(register)));
+                    tp.push(S::Instruction::Ret);
+                },
+                Z::Instruction::Return(None) => {
                     tp.push(S::Instruction::Ret);
                 },
 
                 Z::Instruction::Unary(def) => {
-                    let src= self.parse_value(def.source);
+                    let t = self.asm_type(&def.source);
+                    let src = self.parse_value(def.source);
+                    let dst = self.parse_value(def.destination);
+
+                    tp.push(S::Instruction::Mov(t, src, dst.clone()));
+                    if t.is_floating() {
+                        // Negating a floating value flips its sign bit
+                        let sign = self.float_constant(-0.0, t, 16);
+                        tp.push(S::Instruction::Binary(S::BinaryOp::Xor, t, sign, dst));
+                    } else {
+                        tp.push(S::Instruction::Unary(def.operator.into(), t, dst));
+                    }
+                },
+
+                Z::Instruction::Binary(def) => {
+                    tp.extend(self.parse_binary(def));
+                },
+
+                Z::Instruction::Copy(def) => {
+                    let ty = self.value_type(&def.source);
+                    let src = self.parse_value(def.source);
                     let dst = self.parse_value(def.destination);
 
-                    // FIXME: move this to another compiler pass
-                    if let Opd::Stack(_) = &src {
-                        if let Opd::Stack(_) = &dst {
-                            // When you encounter an invalid mov instruction, rewrite it to first
-                            // copy from the source address into R10D and then copy from R10D to
-                            // the destination
+                    self.copy_object(&mut tp, &ty, src, dst);
+                },
 
-                            tp.push(S::Instruction::Mov(src, Reg(S::Reg::R10D)));
-                            tp.push(S::Instruction::Mov(Reg(S::Reg::R10D), dst.clone()));
-                        }
+                Z::Instruction::CopyToOffset(def) => {
+                    let ty = self.value_type(&def.source);
+                    let src = self.parse_value(def.source);
+                    let dst = self.parse_value(Z::Value::Variable(def.destination)).offset(def.offset as i32);
+
+                    self.copy_object(&mut tp, &ty, src, dst);
+                },
+
+                Z::Instruction::CopyFromOffset(def) => {
+                    let ty = self.value_type(&def.destination);
+                    let src = self.parse_value(Z::Value::Variable(def.source)).offset(def.offset as i32);
+                    let dst = self.parse_value(def.destination);
+
+                    self.copy_object(&mut tp, &ty, src, dst);
+                },
+
+                Z::Instruction::SignExtend(def) => {
+                    let (src_t, dst_t) = (self.asm_type(&def.source), self.asm_type(&def.destination));
+                    let src = self.parse_value(def.source);
+                    let dst = self.parse_value(def.destination);
+
+                    tp.push(S::Instruction::Movsx(src_t, dst_t, src, dst));
+                },
+
+                Z::Instruction::ZeroExtend(def) => {
+                    let (src_t, dst_t) = (self.asm_type(&def.source), self.asm_type(&def.destination));
+                    let src = self.parse_value(def.source);
+                    let dst = self.parse_value(def.destination);
+
+                    tp.push(S::Instruction::MovZeroExtend(src_t, dst_t, src, dst));
+                },
+
+                // Truncating is just copying the low bytes of the source
+                Z::Instruction::Truncate(def) => {
+                    let t = self.asm_type(&def.destination);
+                    let src = self.parse_value(def.source);
+                    let dst = self.parse_value(def.destination);
+
+                    tp.push(S::Instruction::Mov(t, src, dst));
+                },
+
+                Z::Instruction::IntToFloat(def) => {
+                    let src_type = self.value_type(&def.source);
+                    let dst_t = self.asm_type(&def.destination);
+                    let src = self.parse_value(def.source);
+                    let dst = self.parse_value(def.destination);
+
+                    self.int_to_float(&mut tp, &src_type, dst_t, src, dst);
+                },
+
+                Z::Instruction::FloatToInt(def) => {
+                    let src_t = self.asm_type(&def.source);
+                    let dst_type = self.value_type(&def.destination);
+                    let src = self.parse_value(def.source);
+                    let dst = self.parse_value(def.destination);
+
+                    self.float_to_int(&mut tp, src_t, &dst_type, src, dst);
+                },
+
+                Z::Instruction::FloatToFloat(def) => {
+                    let (src_t, dst_t) = (self.asm_type(&def.source), self.asm_type(&def.destination));
+                    let src = self.parse_value(def.source);
+                    let dst = self.parse_value(def.destination);
+
+                    tp.push(S::Instruction::FloatConvert(src_t, dst_t, src, dst));
+                },
+
+                Z::Instruction::FunctionCall(def) => {
+                    tp.extend(self.parse_function_call(def));
+                },
+
+                // `va_start` sets every field of the `va_list`, which is kept in %rax
+                Z::Instruction::VaStart(def) => {
+                    let frame = self.va_frame.as_ref().expect("Internal assembly error -- `va_start` in a function with fixed arguments");
+                    let (save_area, gp_offset, fp_offset, overflow_area) = (frame.save_area, frame.gp_offset, frame.fp_offset, frame.overflow_area);
+                    let va_list = self.parse_value(def.va_list);
+
+                    tp.push(S::Instruction::Mov(AsmType::Quadword, va_list, Reg(S::Reg::AX)));
+                    tp.push(S::Instruction::Mov(AsmType::Longword, Opd::Imm(gp_offset.to_string()), Opd::Memory(S::Reg::AX, 0)));
+                    tp.push(S::Instruction::Mov(AsmType::Longword, Opd::Imm(fp_offset.to_string()), Opd::Memory(S::Reg::AX, 4)));
+                    tp.push(S::Instruction::Lea(Opd::Stack(overflow_area), Reg(S::Reg::DX)));
+                    tp.push(S::Instruction::Mov(AsmType::Quadword, Reg(S::Reg::DX), Opd::Memory(S::Reg::AX, 8)));
+                    tp.push(S::Instruction::Lea(Opd::Stack(save_area), Reg(S::Reg::DX)));
+                    tp.push(S::Instruction::Mov(AsmType::Quadword, Reg(S::Reg::DX), Opd::Memory(S::Reg::AX, 16)));
+                },
+                Z::Instruction::VaArg(def) => {
+                    tp.extend(self.parse_va_arg(def));
+                },
+
+                // Variable-length arrays are allocated below the fixed part of the frame, which
+                // is still addressed through %rbp, and the epilogue restores %rsp from %rbp
+                Z::Instruction::Allocate(def) => {
+                    let size = self.parse_value(def.size);
+                    let dst = self.parse_value(def.destination);
+
+                    tp.push(S::Instruction::Binary(S::BinaryOp::Sub, AsmType::Quadword, size, Reg(S::Reg::SP)));
+                    tp.push(S::Instruction::Binary(S::BinaryOp::And, AsmType::Quadword, Opd::Imm("-16".to_string()), Reg(S::Reg::SP)));
+                    tp.push(S::Instruction::Mov(AsmType::Quadword, Reg(S::Reg::SP), dst));
+                },
+                Z::Instruction::Location(span) => tp.push(S::Instruction::Location(span)),
+                Z::Instruction::SaveStack(value) => {
+                    let dst = self.parse_value(value);
+                    tp.push(S::Instruction::Mov(AsmType::Quadword, Reg(S::Reg::SP), dst));
+                },
+                Z::Instruction::RestoreStack(value) => {
+                    let src = self.parse_value(value);
+                    tp.push(S::Instruction::Mov(AsmType::Quadword, src, Reg(S::Reg::SP)));
+                },
+
+                Z::Instruction::GetAddress(def) => {
+                    let src = self.parse_value(def.source);
+                    let dst = self.parse_value(def.destination);
+
+                    tp.push(S::Instruction::Lea(src, dst));
+                },
+
+                // Loads and stores go through the pointer, kept in %rax
+                Z::Instruction::Load(def) => {
+                    let ty = self.value_type(&def.destination);
+                    let ptr = self.parse_value(def.source_pointer);
+                    let dst = self.parse_value(def.destination);
+
+                    tp.push(S::Instruction::Mov(AsmType::Quadword, ptr, Reg(S::Reg::AX)));
+                    self.copy_object(&mut tp, &ty, Opd::Memory(S::Reg::AX, 0), dst);
+                },
+
+                Z::Instruction::Store(def) => {
+                    let ty = self.value_type(&def.source);
+                    let src = self.parse_value(def.source);
+                    let ptr = self.parse_value(def.destination_pointer);
+
+                    tp.push(S::Instruction::Mov(AsmType::Quadword, ptr, Reg(S::Reg::AX)));
+                    self.copy_object(&mut tp, &ty, src, Opd::Memory(S::Reg::AX, 0));
+                }
+            }
+        }
+
+        tp
+    }
+
+    fn parse_binary(&mut self, def: Z::BinaryInstructionDefinition) -> Vec<S::Instruction> {
+        use Z::BinaryInstructionOperator as O;
+
+        let mut tp: Vec<S::Instruction> = vec![];
+
+        // The operands have the same type; its signedness selects the instructions
+        let operand_type = self.value_type(&def.left);
+        let signed = operand_type.is_signed();
+        let t = AsmType::from(&operand_type);
+        let right_t = self.asm_type(&def.right);
+
+        let left = self.parse_value(def.left);
+        let right = self.parse_value(def.right);
+        let dst = self.parse_value(def.destination);
+
+        if t.is_floating() {
+            return Self::parse_float_binary(def.operator, t, left, right, dst);
+        }
+
+        match def.operator {
+            // Division leaves the quotient in %eax, and the remainder in %edx
+            O::Divide | O::Remainder => {
+                tp.push(S::Instruction::Mov(t, left, Reg(S::Reg::AX)));
+                if signed {
+                    tp.push(S::Instruction::Cdq(t));
+                    tp.push(S::Instruction::Idiv(t, right));
+                } else {
+                    tp.push(S::Instruction::Mov(t, Opd::Imm("0".to_string()), Reg(S::Reg::DX)));
+                    tp.push(S::Instruction::Div(t, right));
+                }
+
+                let result = if matches!(def.operator, O::Divide) { S::Reg::AX } else { S::Reg::DX };
+                tp.push(S::Instruction::Mov(t, Reg(result), dst));
+            },
+
+            O::Equal | O::NotEqual | O::Less | O::LessEqual | O::Greater | O::GreaterEqual => {
+                let cc = match (def.operator, signed) {
+                    (O::Equal, _) => S::CondCode::E,
+                    (O::NotEqual, _) => S::CondCode::NE,
+                    (O::Less, true) => S::CondCode::L,
+                    (O::LessEqual, true) => S::CondCode::LE,
+                    (O::Greater, true) => S::CondCode::G,
+                    (O::GreaterEqual, true) => S::CondCode::GE,
+                    (O::Less, false) => S::CondCode::B,
+                    (O::LessEqual, false) => S::CondCode::BE,
+                    (O::Greater, false) => S::CondCode::A,
+                    (_, false) => S::CondCode::AE,
+                    _ => unreachable!()
+                };
+
+                // The result is an int, of which setcc only sets the lowest byte
+                tp.push(S::Instruction::Cmp(t, right, left));
+                tp.push(S::Instruction::Mov(AsmType::Longword, Opd::Imm("0".to_string()), dst.clone()));
+                tp.push(S::Instruction::SetCC(cc, dst));
+            },
+
+            // Variable shift counts must be in %cl
+            O::ShiftLeft | O::ShiftRight => {
+                let op = match def.operator {
+                    O::ShiftLeft => S::BinaryOp::Sal,
+                    _ if signed => S::BinaryOp::Sar,
+                    _ => S::BinaryOp::Shr
+                };
+
+                tp.push(S::Instruction::Mov(t, left, dst.clone()));
+                let count = match right {
+                    Opd::Imm(_) => right,
+                    _ => {
+                        tp.push(S::Instruction::Mov(right_t, right, Reg(S::Reg::CX)));
+                        Reg(S::Reg::CX)
+                    }
+                };
+                tp.push(S::Instruction::Binary(op, t, count, dst));
+            },
+
+            _ => {
+                let op = match def.operator {
+                    O::Add => S::BinaryOp::Add,
+                    O::Subtract => S::BinaryOp::Sub,
+                    O::Multiply => S::BinaryOp::Mult,
+                    O::BitAnd => S::BinaryOp::And,
+                    O::BitOr => S::BinaryOp::Or,
+                    _ => S::BinaryOp::Xor
+                };
+
+                tp.push(S::Instruction::Mov(t, left, dst.clone()));
+                tp.push(S::Instruction::Binary(op, t, right, dst));
+            }
+        }
+
+        tp
+    }
+
+    // Floating comparisons set the flags like unsigned integer comparisons, except that
+    // an unordered comparison (with a NaN) sets ZF, PF and CF at once. So `<` and `<=`
+    // are tested as `>` and `>=` with swapped operands, which are false when unordered,
+    // and equality also checks the parity flag
+    fn parse_float_binary(op: Z::BinaryInstructionOperator, t: AsmType, left: Opd, right: Opd, dst: Opd) -> Vec<S::Instruction> {
+        use Z::BinaryInstructionOperator as O;
+
+        let mut tp: Vec<S::Instruction> = vec![];
+        let r11 = || Reg(S::Reg::R11);
+
+        match op {
+            O::Equal | O::NotEqual => {
+                let (cc, parity, combine) = match op {
+                    O::Equal => (S::CondCode::E, S::CondCode::NP, S::BinaryOp::And),
+                    _ => (S::CondCode::NE, S::CondCode::P, S::BinaryOp::Or)
+                };
+
+                tp.push(S::Instruction::Cmp(t, right, left));
+                tp.push(S::Instruction::Mov(AsmType::Longword, Opd::Imm("0".to_string()), dst.clone()));
+                tp.push(S::Instruction::SetCC(cc, dst.clone()));
+                tp.push(S::Instruction::SetCC(parity, r11()));
+                tp.push(S::Instruction::Binary(combine, AsmType::Byte, r11(), dst));
+            },
+
+            O::Less | O::LessEqual | O::Greater | O::GreaterEqual => {
+                let (cc, left, right) = match op {
+                    O::Greater => (S::CondCode::A, left, right),
+                    O::GreaterEqual => (S::CondCode::AE, left, right),
+                    O::Less => (S::CondCode::A, right, left),
+                    _ => (S::CondCode::AE, right, left)
+                };
+
+                tp.push(S::Instruction::Cmp(t, right, left));
+                tp.push(S::Instruction::Mov(AsmType::Longword, Opd::Imm("0".to_string()), dst.clone()));
+                tp.push(S::Instruction::SetCC(cc, dst));
+            },
+
+            _ => {
+                let op = match op {
+                    O::Add => S::BinaryOp::Add,
+                    O::Subtract => S::BinaryOp::Sub,
+                    O::Multiply => S::BinaryOp::Mult,
+                    O::Divide => S::BinaryOp::DivFloat,
+                    _ => panic!("Internal assembly error -- integer operator on floating operands")
+                };
+
+                tp.push(S::Instruction::Mov(t, left, dst.clone()));
+                tp.push(S::Instruction::Binary(op, t, right, dst));
+            }
+        }
+
+        tp
+    }
+
+    // cvtsi2sd only converts signed longwords and quadwords, so narrower integers are
+    // extended first, and unsigned longwords are zero-extended to a quadword. Unsigned
+    // quadwords with the top bit set are halved (keeping the lowest bit, so they still
+    // round correctly), converted, and doubled
+    fn int_to_float(&mut self, tp: &mut Vec<S::Instruction>, src_type: &Type, dst_t: AsmType, src: Opd, dst: Opd) {
+        let src_t = AsmType::from(src_type);
+        let r10 = || Reg(S::Reg::R10);
+        let r11 = || Reg(S::Reg::R11);
+
+        match (src_type.is_signed(), src_t) {
+            (true, AsmType::Longword | AsmType::Quadword) => tp.push(S::Instruction::IntToFloat(src_t, dst_t, src, dst)),
+            (true, _) => {
+                tp.push(S::Instruction::Movsx(src_t, AsmType::Longword, src, r10()));
+                tp.push(S::Instruction::IntToFloat(AsmType::Longword, dst_t, r10(), dst));
+            },
+            (false, AsmType::Quadword) => {
+                let (large, end) = (self.make_label("large"), self.make_label("end"));
+
+                tp.push(S::Instruction::Cmp(AsmType::Quadword, Opd::Imm("0".to_string()), src.clone()));
+                tp.push(S::Instruction::JmpCC(S::CondCode::L, large.clone()));
+                tp.push(S::Instruction::IntToFloat(AsmType::Quadword, dst_t, src.clone(), dst.clone()));
+                tp.push(S::Instruction::Jmp(end.clone()));
+
+                tp.push(S::Instruction::Label(large));
+                tp.push(S::Instruction::Mov(AsmType::Quadword, src, r10()));
+                tp.push(S::Instruction::Mov(AsmType::Quadword, r10(), r11()));
+                tp.push(S::Instruction::Binary(S::BinaryOp::Shr, AsmType::Quadword, Opd::Imm("1".to_string()), r11()));
+                tp.push(S::Instruction::Binary(S::BinaryOp::And, AsmType::Quadword, Opd::Imm("1".to_string()), r10()));
+                tp.push(S::Instruction::Binary(S::BinaryOp::Or, AsmType::Quadword, r10(), r11()));
+                tp.push(S::Instruction::IntToFloat(AsmType::Quadword, dst_t, r11(), dst.clone()));
+                tp.push(S::Instruction::Binary(S::BinaryOp::Add, dst_t, dst.clone(), dst));
+                tp.push(S::Instruction::Label(end));
+            },
+            (false, _) => {
+                tp.push(S::Instruction::MovZeroExtend(src_t, AsmType::Quadword, src, r10()));
+                tp.push(S::Instruction::IntToFloat(AsmType::Quadword, dst_t, r10(), dst));
+            }
+        }
+    }
+
+    // cvttsd2si only produces signed longwords and quadwords: narrower integers and
+    // unsigned longwords are truncated from the next larger of those. Values of unsigned
+    // quadwords past the signed range are converted after subtracting 2^63, which is
+    // then added back
+    fn float_to_int(&mut self, tp: &mut Vec<S::Instruction>, src_t: AsmType, dst_type: &Type, src: Opd, dst: Opd) {
+        let dst_t = AsmType::from(dst_type);
+        let r10 = || Reg(S::Reg::R10);
+
+        match (dst_type.is_signed(), dst_t) {
+            (true, AsmType::Longword | AsmType::Quadword) => tp.push(S::Instruction::FloatToInt(src_t, dst_t, src, dst)),
+            (false, AsmType::Quadword) => {
+                let upper = self.float_constant(9223372036854775808.0, src_t, src_t.size());
+                let (large, end) = (self.make_label("large"), self.make_label("end"));
+
+                tp.push(S::Instruction::Cmp(src_t, upper.clone(), src.clone()));
+                tp.push(S::Instruction::JmpCC(S::CondCode::AE, large.clone()));
+                tp.push(S::Instruction::FloatToInt(src_t, AsmType::Quadword, src.clone(), dst.clone()));
+                tp.push(S::Instruction::Jmp(end.clone()));
+
+                tp.push(S::Instruction::Label(large));
+                tp.push(S::Instruction::Mov(src_t, src, Reg(S::Reg::XMM14)));
+                tp.push(S::Instruction::Binary(S::BinaryOp::Sub, src_t, upper, Reg(S::Reg::XMM14)));
+                tp.push(S::Instruction::FloatToInt(src_t, AsmType::Quadword, Reg(S::Reg::XMM14), dst.clone()));
+                tp.push(S::Instruction::Binary(S::BinaryOp::Add, AsmType::Quadword, Opd::Imm(i64::MIN.to_string()), dst));
+                tp.push(S::Instruction::Label(end));
+            },
+            (false, AsmType::Longword) => {
+                tp.push(S::Instruction::FloatToInt(src_t, AsmType::Quadword, src, r10()));
+                tp.push(S::Instruction::Mov(dst_t, r10(), dst));
+            },
+            _ => {
+                tp.push(S::Instruction::FloatToInt(src_t, AsmType::Longword, src, r10()));
+                tp.push(S::Instruction::Mov(dst_t, r10(), dst));
+            }
+        }
+    }
+
+    // Fetches the next variable argument, from the register save area if all of its
+    // eightbytes were passed in registers, and from the stack otherwise, advancing the
+    // offsets of the `va_list` (kept in %rax) past it. Each eightbyte is read through %rcx
+    fn parse_va_arg(&mut self, def: Z::VaArgInstructionDefinition) -> Vec<S::Instruction> {
+        let mut tp: Vec<S::Instruction> = vec![];
+        let ty = self.value_type(&def.destination);
+        let size = self.symbols.size_of(&ty);
+        let classes = self.classify(&ty);
+        let va_list = self.parse_value(def.va_list);
+        let dst = self.parse_value(def.destination);
+        let (stack, end) = (self.make_label("va_arg.stack"), self.make_label("va_arg.end"));
+
+        tp.push(S::Instruction::Mov(AsmType::Quadword, va_list, Reg(S::Reg::AX)));
+
+        if classes[0] != Class::Memory {
+            let integers = classes.iter().filter(|c| **c == Class::Integer).count();
+            let floats = classes.len() - integers;
+            if integers > 0 {
+                tp.push(S::Instruction::Cmp(AsmType::Longword, Opd::Imm((48 - 8 * integers).to_string()), Opd::Memory(S::Reg::AX, 0)));
+                tp.push(S::Instruction::JmpCC(S::CondCode::A, stack.clone()));
+            }
+            if floats > 0 {
+                tp.push(S::Instruction::Cmp(AsmType::Longword, Opd::Imm((176 - 16 * floats).to_string()), Opd::Memory(S::Reg::AX, 4)));
+                tp.push(S::Instruction::JmpCC(S::CondCode::A, stack.clone()));
+            }
+
+            for (idx, class) in classes.iter().enumerate() {
+                let (field, step) = if *class == Class::Sse { (4, 16) } else { (0, 8) };
+                tp.push(S::Instruction::Mov(AsmType::Longword, Opd::Memory(S::Reg::AX, field), Reg(S::Reg::CX)));
+                tp.push(S::Instruction::Binary(S::BinaryOp::Add, AsmType::Quadword, Opd::Memory(S::Reg::AX, 16), Reg(S::Reg::CX)));
+                match ty {
+                    Type::Structure(_, _) => Self::copy_bytes(&mut tp, &Opd::Memory(S::Reg::CX, 0), &dst.offset(8 * idx as i32), (size - 8 * idx).min(8)),
+                    _ => self.copy_object(&mut tp, &ty, Opd::Memory(S::Reg::CX, 0), dst.clone())
+                }
+                tp.push(S::Instruction::Binary(S::BinaryOp::Add, AsmType::Longword, Opd::Imm(step.to_string()), Opd::Memory(S::Reg::AX, field)));
+            }
+            tp.push(S::Instruction::Jmp(end.clone()));
+        }
+
+        tp.push(S::Instruction::Label(stack));
+        tp.push(S::Instruction::Mov(AsmType::Quadword, Opd::Memory(S::Reg::AX, 8), Reg(S::Reg::CX)));
+        self.copy_object(&mut tp, &ty, Opd::Memory(S::Reg::CX, 0), dst);
+        tp.push(S::Instruction::Binary(S::BinaryOp::Add, AsmType::Quadword, Opd::Imm((8 * size.div_ceil(8)).to_string()), Opd::Memory(S::Reg::AX, 8)));
+        tp.push(S::Instruction::Label(end));
+
+        tp
+    }
+
+    // Lowers a call following the System V calling convention: arguments are passed
+    // in the six integer and eight floating argument registers while they last, with
+    // structures taking one register per eightbyte, and the rest are pushed onto the
+    // stack in reverse order, keeping %rsp 16-byte aligned at the call
+    fn parse_function_call(&mut self, def: Z::FunctionCallDefinition) -> Vec<S::Instruction> {
+        let mut tp: Vec<S::Instruction> = vec![];
+
+        // Structures returned in memory are written wherever the hidden first argument points to
+        let return_in_memory = match &def.destination {
+            Some(dst) => self.classify(&self.value_type(dst))[0] == Class::Memory,
+            None => false
+        };
+        let mut next_register = if return_in_memory { 1 } else { 0 };
+        let mut next_float = 0;
+
+        // A structure that doesn't fit in the remaining registers is passed on the stack as a whole
+        let mut register_arguments = vec![];
+        let mut stack_arguments = vec![];
+        for arg in def.arguments {
+            let classes = self.classify(&self.value_type(&arg));
+            match Self::argument_registers(&classes, &mut next_register, &mut next_float) {
+                Some(registers) => register_arguments.push((arg, registers)),
+                None => stack_arguments.push(arg)
+            }
+        }
+
+        let stack_bytes: i32 = stack_arguments.iter().map(|arg| 8 * self.classify(&self.value_type(arg)).len() as i32).sum();
+        let padding = if stack_bytes % 16 != 0 { 8 } else { 0 };
+        if padding != 0 {
+            tp.push(S::Instruction::AllocateStack(padding));
+        }
+
+        if return_in_memory {
+            let dst = self.parse_value(def.destination.clone().expect("Structure returned in memory has a destination"));
+            tp.push(S::Instruction::Lea(dst, Reg(S::Reg::DI)));
+        }
+
+        for (arg, registers) in register_arguments {
+            if self.is_structure(&arg) {
+                let size = self.symbols.size_of(&self.value_type(&arg));
+                let arg = self.parse_value(arg);
+                for (idx, register) in registers.into_iter().enumerate() {
+                    let (offset, bytes) = (8 * idx, (size - 8 * idx).min(8));
+                    if register.is_xmm() {
+                        tp.push(S::Instruction::Mov(Self::eightbyte_type(bytes), arg.offset(offset as i32), Reg(register)));
                     } else {
-                        tp.push(S::Instruction::Mov(src, dst.clone()));
+                        Self::load_eightbyte(&mut tp, &arg, offset, bytes, register);
                     }
+                }
+                continue;
+            }
+
+            let t = self.asm_type(&arg);
+            let arg = self.parse_value(arg);
+            tp.push(S::Instruction::Mov(t, arg, Reg(registers[0].clone())));
+        }
+
+        for arg in stack_arguments.into_iter().rev() {
+            if self.is_structure(&arg) {
+                let size = self.symbols.size_of(&self.value_type(&arg));
+                let arg = self.parse_value(arg);
+                tp.push(S::Instruction::AllocateStack(8 * size.div_ceil(8) as i32));
+                Self::copy_bytes(&mut tp, &arg, &Opd::Memory(S::Reg::SP, 0), size);
+                continue;
+            }
+
+            // Floating values are pushed as integers of the same size
+            let t = self.asm_type(&arg).bits();
+            let arg = self.parse_value(arg);
+            match arg {
+                Opd::Imm(_) | Opd::Reg(_) => tp.push(S::Instruction::Push(arg)),
+                _ if t == AsmType::Quadword => tp.push(S::Instruction::Push(arg)),
+                _ => {
+                    // Pushing a narrower memory operand would read past it, so go through %rax
+                    tp.push(S::Instruction::Mov(t, arg, Reg(S::Reg::AX)));
+                    tp.push(S::Instruction::Push(Reg(S::Reg::AX)));
+                }
+            }
+        }
+
+        // Variadic functions expect the number of vector registers used in %al. It is set
+        // last, since pushing arguments may go through %rax
+        let callee_type = match &def.function {
+            Z::Callee::Direct(name) => Some(self.symbols.type_of(name).clone()),
+            Z::Callee::Indirect(pointer) => self.value_type(pointer).pointee().cloned()
+        };
+        if let Some(Type::Function(f)) = callee_type {
+            if f.variadic {
+                tp.push(S::Instruction::Mov(AsmType::Longword, Opd::Imm(next_float.to_string()), Reg(S::Reg::AX)));
+            }
+        }
+
+        // %r11 isn't used to pass arguments, so it can hold the function pointer
+        match def.function {
+            Z::Callee::Direct(name) => tp.push(S::Instruction::Call(name)),
+            Z::Callee::Indirect(pointer) => {
+                let pointer = self.parse_value(pointer);
+                tp.push(S::Instruction::Mov(AsmType::Quadword, pointer, Reg(S::Reg::R11)));
+                tp.push(S::Instruction::CallIndirect(Reg(S::Reg::R11)));
+            }
+        }
+
+        let bytes_to_remove = stack_bytes + padding;
+        if bytes_to_remove != 0 {
+            tp.push(S::Instruction::DeallocateStack(bytes_to_remove));
+        }
+
+        match def.destination {
+            Some(_) if return_in_memory => {},
+            Some(dst) if self.is_structure(&dst) => {
+                let ty = self.value_type(&dst);
+                let size = self.symbols.size_of(&ty);
+                let dst = self.parse_value(dst);
+
+                for (idx, register) in Self::return_registers(&self.classify(&ty)).into_iter().enumerate() {
+                    let (offset, bytes) = (8 * idx, (size - 8 * idx).min(8));
+                    if register.is_xmm() {
+                        tp.push(S::Instruction::Mov(Self::eightbyte_type(bytes), Reg(register), dst.offset(offset as i32)));
+                    } else {
+                        Self::store_eightbyte(&mut tp, register, &dst, offset, bytes);
+                    }
+                }
+            },
+            Some(dst) => {
+                let t = self.asm_type(&dst);
+                let dst = self.parse_value(dst);
+                let register = if t.is_floating() { S::Reg::XMM0 } else { S::Reg::AX };
+                tp.push(S::Instruction::Mov(t, Reg(register), dst));
+            },
+            None => {}
+        }
+
+        tp
+    }
+
+    fn classify(&self, ty: &Type) -> Vec<Class> {
+        match ty {
+            Type::Structure(_, _) => {
+                let size = self.symbols.size_of(ty);
+                if size > 16 {
+                    return vec![Class::Memory; size.div_ceil(8)];
+                }
+
+                let mut scalars = vec![];
+                self.flatten(ty, 0, &mut scalars);
+                (0..size.div_ceil(8)).map(|eightbyte| {
+                    let floating = scalars.iter().filter(|(offset, _)| offset / 8 == eightbyte).all(|(_, ty)| ty.is_floating());
+                    if floating { Class::Sse } else { Class::Integer }
+                }).collect()
+            },
+            _ if ty.is_floating() => vec![Class::Sse],
+            _ => vec![Class::Integer]
+        }
+    }
+
+    // Scalars making up an object, with their offsets within it
+    fn flatten(&self, ty: &Type, offset: usize, scalars: &mut Vec<(usize, Type)>) {
+        match ty.unqualified() {
+            Type::Structure(_, tag) => {
+                let definition = self.symbols.get_struct(tag).expect("Classified structures are complete");
+                for member in &definition.members {
+                    self.flatten(&member.ty, offset + member.offset, scalars);
+                }
+            },
+            Type::Array(element, count) => {
+                let size = self.symbols.size_of(element);
+                for i in 0..*count {
+                    self.flatten(element, offset + i * size, scalars);
+                }
+            },
+            _ => scalars.push((offset, ty.clone()))
+        }
+    }
+
+    // Registers for each eightbyte of an argument, taken from the next free ones,
+    // or None if it is passed on the stack because there aren't enough of them
+    fn argument_registers(classes: &[Class], next_register: &mut usize, next_float: &mut usize) -> Option<Vec<S::Reg>> {
+        let integers = classes.iter().filter(|c| **c == Class::Integer).count();
+        let floats = classes.iter().filter(|c| **c == Class::Sse).count();
+        if classes[0] == Class::Memory || *next_register + integers > S::Reg::ARGUMENTS.len() || *next_float + floats > S::Reg::FLOAT_ARGUMENTS.len() {
+            return None;
+        }
+
+        let registers = classes.iter().map(|class| match class {
+            Class::Sse => {
+                *next_float += 1;
+                S::Reg::FLOAT_ARGUMENTS[*next_float - 1].clone()
+            },
+            _ => {
+                *next_register += 1;
+                S::Reg::ARGUMENTS[*next_register - 1].clone()
+            }
+        }).collect();
+
+        Some(registers)
+    }
+
+    // Registers for each eightbyte of a value returned in registers
+    fn return_registers(classes: &[Class]) -> Vec<S::Reg> {
+        let mut integer = [S::Reg::AX, S::Reg::DX].into_iter();
+        let mut sse = [S::Reg::XMM0, S::Reg::XMM1].into_iter();
+
+        classes.iter().map(|class| match class {
+            Class::Sse => sse.next(),
+            _ => integer.next()
+        }.expect("Values returned in registers have at most two eightbytes")).collect()
+    }
+
+    // Floating eightbytes hold either a double, or one or two floats
+    fn eightbyte_type(bytes: usize) -> AsmType {
+        if bytes == 4 { AsmType::Single } else { AsmType::Double }
+    }
+
+    // There are no floating immediates, so floating values are kept as read-only
+    // constants, each emitted once. Bitwise operations read all 16 bytes of their
+    // memory operand, so constants aligned for them are padded to that size
+    fn float_constant(&mut self, value: f64, t: AsmType, alignment: i32) -> Opd {
+        let mut init = vec![S::StaticInit::Value(t, Self::float_bits(value, t))];
+        if alignment > t.size() {
+            init.push(S::StaticInit::Zero(alignment - t.size()));
+        }
+
+        if let Some(constant) = self.constants.iter().find(|c| c.init == init && c.alignment == alignment) {
+            return Opd::Data(constant.identifier.clone(), 0);
+        }
+
+        let name = if t == AsmType::Single { "float" } else { "double" };
+        let identifier = format!("{}.{}", name, self.constants.len());
+        self.constants.push(S::StaticConstant { identifier: identifier.clone(), alignment, init });
+
+        Opd::Data(identifier, 0)
+    }
+
+    fn float_bits(value: f64, t: AsmType) -> String {
+        match t {
+            AsmType::Single => (value as f32).to_bits().to_string(),
+            _ => value.to_bits().to_string()
+        }
+    }
+
+    fn make_label(&mut self, name: &str) -> String {
+        self.label_count += 1;
+        format!("{}.{}", name, self.label_count)
+    }
+
+    // Copies a value between two operands; structures are copied piecewise
+    fn copy_object(&self, tp: &mut Vec<S::Instruction>, ty: &Type, src: Opd, dst: Opd) {
+        match ty {
+            Type::Structure(_, _) => Self::copy_bytes(tp, &src, &dst, self.symbols.size_of(ty)),
+            _ => tp.push(S::Instruction::Mov(AsmType::from(ty), src, dst))
+        }
+    }
+
+    // Copies `size` bytes between two memory operands, in the largest moves that fit
+    fn copy_bytes(tp: &mut Vec<S::Instruction>, src: &Opd, dst: &Opd, size: usize) {
+        let mut offset = 0;
+        while offset < size {
+            let t = match size - offset {
+                8.. => AsmType::Quadword,
+                4..=7 => AsmType::Longword,
+                2 | 3 => AsmType::Word,
+                _ => AsmType::Byte
+            };
+
+            tp.push(S::Instruction::Mov(t, src.offset(offset as i32), dst.offset(offset as i32)));
+            offset += t.size() as usize;
+        }
+    }
+
+    // Loads the `bytes` bytes at `offset` into a register; eightbytes that are only
+    // partially part of the object are assembled byte by byte, so nothing past its
+    // end is read
+    fn load_eightbyte(tp: &mut Vec<S::Instruction>, src: &Opd, offset: usize, bytes: usize, register: S::Reg) {
+        let src = src.offset(offset as i32);
+
+        match bytes {
+            8 => tp.push(S::Instruction::Mov(AsmType::Quadword, src, Reg(register))),
+            4 => tp.push(S::Instruction::Mov(AsmType::Longword, src, Reg(register))),
+            _ => {
+                tp.push(S::Instruction::Mov(AsmType::Quadword, Opd::Imm("0".to_string()), Reg(register.clone())));
+                for i in (0..bytes).rev() {
+                    tp.push(S::Instruction::Binary(S::BinaryOp::Sal, AsmType::Quadword, Opd::Imm("8".to_string()), Reg(register.clone())));
+                    tp.push(S::Instruction::Mov(AsmType::Byte, src.offset(i as i32), Reg(register.clone())));
+                }
+            }
+        }
+    }
+
+    // Stores the low `bytes` bytes of a register at `offset`, clobbering the register
+    fn store_eightbyte(tp: &mut Vec<S::Instruction>, register: S::Reg, dst: &Opd, offset: usize, bytes: usize) {
+        let dst = dst.offset(offset as i32);
 
-                    tp.push(S::Instruction::Unary(def.operator.into(), dst));
+        match bytes {
+            8 => tp.push(S::Instruction::Mov(AsmType::Quadword, Reg(register), dst)),
+            4 => tp.push(S::Instruction::Mov(AsmType::Longword, Reg(register), dst)),
+            _ => {
+                for i in 0..bytes {
+                    tp.push(S::Instruction::Mov(AsmType::Byte, Reg(register.clone()), dst.offset(i as i32)));
+                    tp.push(S::Instruction::Binary(S::BinaryOp::Shr, AsmType::Quadword, Opd::Imm("8".to_string()), Reg(register.clone())));
                 }
             }
         }
+    }
+
+    fn fixup_instructions(instructions: Vec<S::Instruction>) -> Vec<S::Instruction> {
+        use S::Instruction as I;
+
+        let mut tp: Vec<S::Instruction> = vec![];
+        let r10 = || Reg(S::Reg::R10);
+        let r11 = || Reg(S::Reg::R11);
+
+        let xmm14 = || Reg(S::Reg::XMM14);
+        let xmm15 = || Reg(S::Reg::XMM15);
+        let is_register = |opd: &Opd| matches!(opd, Opd::Reg(_));
+
+        for i in instructions {
+            match i {
+                // Floating instructions other than mov can't write to memory, and the
+                // right operand of comisd must be a register as well, so they operate
+                // on %xmm15 instead. Memory to memory moves go through %xmm14
+                I::Mov(t, src, dst) if t.is_floating() && src.is_memory() && dst.is_memory() => {
+                    tp.push(I::Mov(t, src, xmm14()));
+                    tp.push(I::Mov(t, xmm14(), dst));
+                },
+                I::Binary(op, t, src, dst) if t.is_floating() && !is_register(&dst) => {
+                    tp.push(I::Mov(t, dst.clone(), xmm15()));
+                    tp.push(I::Binary(op, t, src, xmm15()));
+                    tp.push(I::Mov(t, xmm15(), dst));
+                },
+                I::Cmp(t, src, dst) if t.is_floating() && !is_register(&dst) => {
+                    tp.push(I::Mov(t, dst, xmm15()));
+                    tp.push(I::Cmp(t, src, xmm15()));
+                },
+
+                // Conversions must write to a register, and can't convert an immediate
+                I::IntToFloat(src_t, dst_t, src, dst) => {
+                    let src = match src {
+                        Opd::Imm(_) => {
+                            tp.push(I::Mov(src_t, src, r10()));
+                            r10()
+                        },
+                        _ => src
+                    };
+
+                    if is_register(&dst) {
+                        tp.push(I::IntToFloat(src_t, dst_t, src, dst));
+                    } else {
+                        tp.push(I::IntToFloat(src_t, dst_t, src, xmm15()));
+                        tp.push(I::Mov(dst_t, xmm15(), dst));
+                    }
+                },
+                I::FloatToInt(src_t, dst_t, src, dst) if !is_register(&dst) => {
+                    tp.push(I::FloatToInt(src_t, dst_t, src, r11()));
+                    tp.push(I::Mov(dst_t, r11(), dst));
+                },
+                I::FloatConvert(src_t, dst_t, src, dst) if !is_register(&dst) => {
+                    tp.push(I::FloatConvert(src_t, dst_t, src, xmm15()));
+                    tp.push(I::Mov(dst_t, xmm15(), dst));
+                },
+
+                // Immediates are truncated to the size of the operation, and 64-bit
+                // immediates that don't fit in 32 bits can only be moved into a register
+                I::Mov(t, Opd::Imm(imm), dst) if t != AsmType::Quadword => {
+                    tp.push(I::Mov(t, Opd::Imm(Self::truncate_immediate(&imm, t)), dst));
+                },
+                I::Mov(t, src, dst) if Self::is_large_immediate(&src) && dst.is_memory() => {
+                    tp.push(I::Mov(t, src, r10()));
+                    tp.push(I::Mov(t, r10(), dst));
+                },
+
+                // When you encounter an invalid mov instruction, rewrite it to first
+                // copy from the source address into R10 and then copy from R10 to
+                // the destination
+                I::Mov(t, src, dst) if src.is_memory() && dst.is_memory() => {
+                    tp.push(I::Mov(t, src, r10()));
+                    tp.push(I::Mov(t, r10(), dst));
+                },
+
+                // lea must write to a register
+                I::Lea(src, dst) if dst.is_memory() => {
+                    tp.push(I::Lea(src, r11()));
+                    tp.push(I::Mov(AsmType::Quadword, r11(), dst));
+                },
+
+                // Extensions can't take an immediate source, and must write to a register
+                I::Movsx(src_t, dst_t, src, dst) => {
+                    let src = match src {
+                        Opd::Imm(_) => {
+                            tp.push(I::Mov(src_t, src, r10()));
+                            r10()
+                        },
+                        _ => src
+                    };
+
+                    if dst.is_memory() {
+                        tp.push(I::Movsx(src_t, dst_t, src, r11()));
+                        tp.push(I::Mov(dst_t, r11(), dst));
+                    } else {
+                        tp.push(I::Movsx(src_t, dst_t, src, dst));
+                    }
+                },
+
+                // Writing to a 32-bit register clears its upper half, so there is no
+                // instruction to zero-extend a longword: a plain movl does the job
+                I::MovZeroExtend(AsmType::Longword, dst_t, src, dst) => {
+                    if dst.is_memory() {
+                        tp.push(I::Mov(AsmType::Longword, src, r11()));
+                        tp.push(I::Mov(dst_t, r11(), dst));
+                    } else {
+                        tp.push(I::Mov(AsmType::Longword, src, dst));
+                    }
+                },
+                I::MovZeroExtend(src_t, dst_t, src, dst) => {
+                    let src = match src {
+                        Opd::Imm(_) => {
+                            tp.push(I::Mov(src_t, src, r10()));
+                            r10()
+                        },
+                        _ => src
+                    };
+
+                    if dst.is_memory() {
+                        tp.push(I::MovZeroExtend(src_t, dst_t, src, r11()));
+                        tp.push(I::Mov(dst_t, r11(), dst));
+                    } else {
+                        tp.push(I::MovZeroExtend(src_t, dst_t, src, dst));
+                    }
+                },
+
+                // imul can't write to memory
+                I::Binary(S::BinaryOp::Mult, t, src, dst) if dst.is_memory() => {
+                    let src = Self::fixup_large_immediate(&mut tp, t, src);
+                    tp.push(I::Mov(t, dst.clone(), r11()));
+                    tp.push(I::Binary(S::BinaryOp::Mult, t, src, r11()));
+                    tp.push(I::Mov(t, r11(), dst));
+                },
+                I::Binary(op, t, src, dst) => {
+                    let src = Self::fixup_large_immediate(&mut tp, t, src);
+                    if src.is_memory() && dst.is_memory() {
+                        tp.push(I::Mov(t, src, r10()));
+                        tp.push(I::Binary(op, t, r10(), dst));
+                    } else {
+                        tp.push(I::Binary(op, t, src, dst));
+                    }
+                },
+
+                // The second operand of cmp can't be an immediate
+                I::Cmp(t, src, dst) => {
+                    let src = Self::fixup_large_immediate(&mut tp, t, src);
+                    let src = if src.is_memory() && dst.is_memory() {
+                        tp.push(I::Mov(t, src, r10()));
+                        r10()
+                    } else {
+                        src
+                    };
+
+                    if let Opd::Imm(_) = dst {
+                        tp.push(I::Mov(t, dst, r11()));
+                        tp.push(I::Cmp(t, src, r11()));
+                    } else {
+                        tp.push(I::Cmp(t, src, dst));
+                    }
+                },
+
+                // Neither can the divisor
+                I::Idiv(t, Opd::Imm(imm)) => {
+                    tp.push(I::Mov(t, Opd::Imm(imm), r10()));
+                    tp.push(I::Idiv(t, r10()));
+                },
+                I::Div(t, Opd::Imm(imm)) => {
+                    tp.push(I::Mov(t, Opd::Imm(imm), r10()));
+                    tp.push(I::Div(t, r10()));
+                },
+
+                I::Push(src) if Self::is_large_immediate(&src) => {
+                    tp.push(I::Mov(AsmType::Quadword, src, r10()));
+                    tp.push(I::Push(r10()));
+                },
+
+                _ => tp.push(i)
+            }
+        }
 
         tp
     }
 
+    // Only immediates that fit in 32 bits can be used by most instructions
+    fn is_large_immediate(opd: &Opd) -> bool {
+        match opd {
+            Opd::Imm(imm) => imm.parse::<i128>().is_ok_and(|v| v < i32::MIN as i128 || v > i32::MAX as i128),
+            _ => false
+        }
+    }
+
+    fn fixup_large_immediate(tp: &mut Vec<S::Instruction>, t: AsmType, src: Opd) -> Opd {
+        if t == AsmType::Quadword && Self::is_large_immediate(&src) {
+            tp.push(S::Instruction::Mov(t, src, Reg(S::Reg::R10)));
+            return Reg(S::Reg::R10);
+        }
+
+        src
+    }
+
+    fn truncate_immediate(imm: &str, t: AsmType) -> String {
+        match imm.parse::<i128>() {
+            Ok(value) => {
+                let bits = 8 * t.size() as u32;
+                let value = value.rem_euclid(1 << bits);
+                let value = if value >= 1 << (bits - 1) { value - (1 << bits) } else { value };
+                value.to_string()
+            },
+            Err(_) => imm.to_string()
+        }
+    }
+
+    fn value_type(&self, value: &Z::Value) -> Type {
+        match value {
+            Z::Value::Constant(c) => c.ty(),
+            Z::Value::Variable(id) => self.symbols.type_of(id).unqualified().clone()
+        }
+    }
+
+    fn is_structure(&self, value: &Z::Value) -> bool {
+        matches!(self.value_type(value), Type::Structure(_, _))
+    }
+
+    fn asm_type(&self, value: &Z::Value) -> AsmType {
+        AsmType::from(&self.value_type(value))
+    }
+
     fn parse_value(&mut self, value: Z::Value) -> S::Opd {
+        let ty = self.value_type(&value);
+
         match value {
-            Z::Value::Constant(s) => S::Opd::Imm(s),
+            Z::Value::Constant(c) if c.is_floating() => {
+                let t = AsmType::from(&ty);
+                self.float_constant(c.to_float(), t, t.size())
+            },
+            Z::Value::Constant(c) => S::Opd::Imm(c.to_string()),
             Z::Value::Variable(id) => {
+                // Functions are only ever operands of `lea`, which takes their address
+                if self.symbols.is_static(&id) || matches!(ty, Type::Function(_)) {
+                    return S::Opd::Data(id, 0);
+                }
+
                 let offset = self.stack_map.get(&id);
                 match offset {
                     None => {
-                        self.curr_offset -= 4;
-                        self.stack_map.insert(id.clone(), self.curr_offset);
+                        let slot = self.allocate_stack(self.symbols.size_of(&ty), self.symbols.alignment_of(&ty));
+                        if let S::Opd::Stack(offset) = slot {
+                            self.stack_map.insert(id.clone(), offset);
+                        }
 
-                        S::Opd::Stack(self.curr_offset)
+                        slot
                     }
                     Some(offset) => {
                         S::Opd::Stack(*offset)
@@ -97,4 +1234,12 @@ impl STranspiler {
             }
         }
     }
+
+    // Each slot is aligned to the alignment of its type
+    fn allocate_stack(&mut self, size: usize, alignment: usize) -> S::Opd {
+        self.curr_offset -= size as i32;
+        self.curr_offset -= self.curr_offset.rem_euclid(alignment as i32);
+
+        S::Opd::Stack(self.curr_offset)
+    }
 }
@@ -0,0 +1,145 @@
+// Constant expression evaluation //
+// Evaluates constant expressions at compile time, following the
+// same conversion rules the generated code follows at runtime.
+use super::symbols::*;
+
+// Returns the value and type of a constant expression, or None if the
+// expression is not constant (or its evaluation is undefined)
+pub fn evaluate(e: &Expression) -> Option<(i128, Type)> {
+    match &e.kind {
+        ExpressionKind::Constant(c) if !c.is_floating() => {
+            let ty = c.ty();
+            let value = c.value()?;
+            Some((ty.wrap(value), ty))
+        },
+
+        ExpressionKind::Unary(op, inner) => {
+            let (value, ty) = evaluate(inner)?;
+            let ty = ty.promote();
+            let value = match op {
+                UnaryExpressionType::Negation => -value,
+                UnaryExpressionType::Complement => !value
+            };
+
+            Some((ty.wrap(value), ty))
+        },
+
+        ExpressionKind::Binary(op, left, right) => {
+            let (left, left_type) = evaluate(left)?;
+            let (right, right_type) = evaluate(right)?;
+
+            // The type of a shift is that of its promoted left operand
+            if op.is_shift() {
+                let ty = left_type.promote();
+                if right < 0 || right >= 8 * ty.size() as i128 {
+                    return None;
+                }
+
+                let value = match op {
+                    BinaryExpressionType::ShiftLeft => left << right,
+                    _ => left >> right
+                };
+                return Some((ty.wrap(value), ty));
+            }
+
+            let ty = Type::common(&left_type, &right_type);
+            let (a, b) = (ty.wrap(left), ty.wrap(right));
+
+            use BinaryExpressionType as B;
+            let value = match op {
+                B::Add => a + b,
+                B::Subtract => a - b,
+                B::Multiply => a * b,
+                B::Divide | B::Remainder if b == 0 => return None,
+                B::Divide => a / b,
+                B::Remainder => a % b,
+                B::BitAnd => a & b,
+                B::BitOr => a | b,
+                B::BitXor => a ^ b,
+                B::Equal => return Some(((a == b) as i128, Type::Int)),
+                B::NotEqual => return Some(((a != b) as i128, Type::Int)),
+                B::Less => return Some(((a < b) as i128, Type::Int)),
+                B::LessEqual => return Some(((a <= b) as i128, Type::Int)),
+                B::Greater => return Some(((a > b) as i128, Type::Int)),
+                B::GreaterEqual => return Some(((a >= b) as i128, Type::Int)),
+                B::ShiftLeft | B::ShiftRight => unreachable!()
+            };
+
+            Some((ty.wrap(value), ty))
+        },
+
+        // Floating constants may be cast to integers, as in `(int)2.5`
+        ExpressionKind::Cast(ty, inner) => {
+            if !ty.is_integer() {
+                return None;
+            }
+
+            let value = evaluate_arithmetic(inner)?.integer_value(ty)?;
+            Some((value, ty.clone()))
+        },
+
+        _ => None
+    }
+}
+
+// An integer constant expression with the value 0, or such an expression
+// cast to `void *`, as `NULL` is usually defined
+pub fn is_null_pointer_constant(e: &Expression) -> bool {
+    match &e.kind {
+        ExpressionKind::Cast(Type::Pointer(pointee), inner) if **pointee == Type::Void => is_null_pointer_constant(inner),
+        _ => matches!(evaluate(e), Some((0, ty)) if ty.is_integer())
+    }
+}
+
+// Evaluates an arithmetic constant expression, which unlike an integer
+// constant expression may also involve floating constants, as in the
+// initializers of static objects
+pub fn evaluate_arithmetic(e: &Expression) -> Option<ConstantValue> {
+    // Types narrower than `int` have no constants, but their values are the same once promoted
+    if let Some((value, ty)) = evaluate(e) {
+        return ConstantValue::from_value(value, &ty).or_else(|| ConstantValue::from_value(value, &ty.promote()));
+    }
+
+    match &e.kind {
+        ExpressionKind::Constant(c) => Some(c.clone()),
+
+        ExpressionKind::Unary(UnaryExpressionType::Negation, inner) => {
+            let c = evaluate_arithmetic(inner)?;
+            if !c.is_floating() {
+                return None;
+            }
+
+            ConstantValue::from_float(-c.to_float(), &c.ty())
+        },
+
+        ExpressionKind::Binary(op, left, right) => {
+            let (left, right) = (evaluate_arithmetic(left)?, evaluate_arithmetic(right)?);
+            let ty = Type::common(&left.ty(), &right.ty());
+            if !ty.is_floating() {
+                return None;
+            }
+            let (a, b) = (left.convert(&ty)?.to_float(), right.convert(&ty)?.to_float());
+
+            use BinaryExpressionType as B;
+            let value = match op {
+                B::Add => a + b,
+                B::Subtract => a - b,
+                B::Multiply => a * b,
+                B::Divide => a / b,
+                B::Equal => return ConstantValue::from_value((a == b) as i128, &Type::Int),
+                B::NotEqual => return ConstantValue::from_value((a != b) as i128, &Type::Int),
+                B::Less => return ConstantValue::from_value((a < b) as i128, &Type::Int),
+                B::LessEqual => return ConstantValue::from_value((a <= b) as i128, &Type::Int),
+                B::Greater => return ConstantValue::from_value((a > b) as i128, &Type::Int),
+                B::GreaterEqual => return ConstantValue::from_value((a >= b) as i128, &Type::Int),
+                _ => return None
+            };
+
+            ConstantValue::from_float(value, &ty)
+        },
+
+        ExpressionKind::Cast(ty, inner) => evaluate_arithmetic(inner)?.convert(ty),
+
+        _ => None
+    }
+}
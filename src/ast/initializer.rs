@@ -0,0 +1,307 @@
+// Semantic analysis: initializer lists //
+// Rewrites initializer lists as written, which may designate the element they
+// initialize and elide the braces around nested aggregates, into lists with one
+// initializer per element or member, in order (C11 6.7.9). For example, with
+// `struct point { int x, y; }`, the initializer of `struct point p[3]` in
+//
+//     { 1, 2, [2].y = 4 }
+//
+// becomes `{ {1, 2}, {}, {0, 4} }`, where `{}` zero-initializes `p[1]`.
+use super::symbols::*;
+use super::parser::ASTError;
+use super::table::{SymbolTable, Member};
+use super::{consteval, typecheck};
+
+
+// Rewrites the (resolved) initializer of an object of type `ty`. Lists are trimmed
+// after their last initialized element, as elements without an initializer are
+// zero-initialized anyway, and gaps before it are filled with zero initializers,
+// which take the span of the declaration, as do lists around elided braces
+pub fn normalize(init: Initializer, ty: &Type, span: Span, symbols: &SymbolTable) -> Result<Initializer, ASTError> {
+    let normalizer = Normalizer { symbols, span };
+    return normalizer.normalize(init, ty);
+}
+
+// Size of an array declared without one, as in `int a[] = {1, 2}`,
+// given its normalized initializer
+pub fn array_length(init: &Initializer) -> Option<usize> {
+    match init {
+        Initializer::Compound(list, _) => Some(list.len()),
+        Initializer::Single(Expression { kind: ExpressionKind::String(bytes), .. }) => Some(bytes.len() + 1),
+        _ => None
+    }
+}
+
+pub fn is_character_array(ty: &Type) -> bool {
+    matches!(ty.unqualified(), Type::Array(element, _) if matches!(element.unqualified(), Type::Char | Type::SChar | Type::UChar))
+}
+
+
+// The initialized subobjects of an aggregate, with one node per element or member
+enum Node {
+    Empty,
+    Leaf(Initializer),
+    Aggregate(Vec<Node>, Span)
+}
+
+struct Normalizer<'a> {
+    symbols: &'a SymbolTable,
+    span: Span
+}
+
+impl<'a> Normalizer<'a> {
+    fn normalize(&self, init: Initializer, ty: &Type) -> Result<Initializer, ASTError> {
+        match init {
+            // A string literal initializing a character array may be enclosed in braces
+            Initializer::Compound(mut list, _) if is_character_array(ty) && list.len() == 1 && Self::is_string(&list[0]) => {
+                Ok(list.remove(0))
+            },
+            Initializer::Compound(list, span) if Self::is_aggregate(ty) => {
+                let node = self.fill(ty, list, span)?;
+                Ok(self.finish(node, ty))
+            },
+            // So may the initializer of a scalar
+            Initializer::Compound(mut list, _) if list.len() == 1 && !matches!(list[0], Initializer::Designated(_, _)) => {
+                self.normalize(list.remove(0), ty)
+            },
+            Initializer::Designated(_, _) => panic!("Internal resolver error -- designated initializer outside of a list"),
+            // Anything else is diagnosed by the passes that evaluate the initializer
+            init => Ok(init)
+        }
+    }
+
+    fn is_aggregate(ty: &Type) -> bool {
+        matches!(ty.unqualified(), Type::Array(_, _) | Type::Structure(_, _))
+    }
+
+    fn is_string(init: &Initializer) -> bool {
+        matches!(init, Initializer::Single(Expression { kind: ExpressionKind::String(_), .. }))
+    }
+
+    fn members(&self, tag: &str, ty: &Type) -> Result<&[Member], ASTError> {
+        match self.symbols.get_struct(tag) {
+            Some(definition) => Ok(&definition.members),
+            None => {
+                semantic_error!(INCOMPLETE_TYPE, "Initializer for incomplete type `{}`", ty);
+            }
+        }
+    }
+
+    // Number of elements or members of an aggregate, or None for arrays
+    // declared without a size, which grow with their initializer
+    fn bound(&self, ty: &Type) -> Result<Option<usize>, ASTError> {
+        match ty.unqualified() {
+            Type::Array(_, 0) => Ok(None),
+            Type::Array(_, count) => Ok(Some(*count)),
+            Type::Structure(_, tag) => Ok(Some(self.members(tag, ty)?.len())),
+            _ => panic!("Internal resolver error -- `{}` is not an aggregate", ty)
+        }
+    }
+
+    fn child_type(&self, ty: &Type, index: usize) -> Result<Type, ASTError> {
+        match ty.unqualified() {
+            Type::Array(element, _) => Ok(*element.clone()),
+            Type::Structure(_, tag) => Ok(self.members(tag, ty)?[index].ty.clone()),
+            _ => panic!("Internal resolver error -- `{}` is not an aggregate", ty)
+        }
+    }
+
+    fn type_at(&self, ty: &Type, path: &[usize]) -> Result<Type, ASTError> {
+        let mut ty = ty.clone();
+        for index in path {
+            ty = self.child_type(&ty, *index)?;
+        }
+
+        return Ok(ty);
+    }
+
+    // Initializes the elements of an aggregate from a brace-enclosed list: each
+    // initializer applies to the designated subobject, or to the one after the
+    // subobject initialized last, as in `{ [1].x = 1, 2 }`, where `2` initializes `[1].y`
+    fn fill(&self, ty: &Type, list: Vec<Initializer>, span: Span) -> Result<Node, ASTError> {
+        let mut root = Node::Empty;
+        let mut current: Vec<usize> = vec![];
+
+        for init in list {
+            let (path, init) = match init {
+                Initializer::Designated(designators, init) => (self.designate(ty, designators)?, *init),
+                init => match self.next(ty, &current)? {
+                    Some(path) => (path, init),
+                    None => {
+                        semantic_error!(INVALID_INITIALIZER at init.span(), "Excess elements in initializer of `{}`", ty);
+                    }
+                }
+            };
+            current = self.assign(&mut root, ty, path, init)?;
+        }
+
+        return match root {
+            Node::Aggregate(children, _) => Ok(Node::Aggregate(children, span)),
+            _ => Ok(Node::Aggregate(vec![], span))
+        };
+    }
+
+    // Path to the subobject named by a list of designators
+    fn designate(&self, ty: &Type, designators: Vec<Designator>) -> Result<Vec<usize>, ASTError> {
+        let mut path = vec![];
+        let mut current = ty.clone();
+
+        for designator in designators {
+            let span = designator.span();
+            let index = match (current.unqualified(), designator) {
+                (Type::Array(_, count), Designator::Index(e)) => match consteval::evaluate(&e) {
+                    Some((index, ty)) if ty.is_integer() && index >= 0 && (*count == 0 || index < *count as i128) => index as usize,
+                    Some((_, ty)) if ty.is_integer() => {
+                        semantic_error!(INVALID_INITIALIZER at span, "Array designator index exceeds the bounds of `{}`", current);
+                    },
+                    _ => {
+                        semantic_error!(INVALID_CONSTANT at span, "Array designator is not an integer constant expression");
+                    }
+                },
+                (Type::Structure(_, tag), Designator::Member(name, _)) => {
+                    match self.members(tag, &current)?.iter().position(|m| m.name == name) {
+                        Some(index) => index,
+                        None => {
+                            semantic_error!(UNKNOWN_MEMBER at span, "Field designator `{}` does not refer to any member of `{}`", name, current);
+                        }
+                    }
+                },
+                (_, Designator::Index(_)) => {
+                    semantic_error!(INVALID_INITIALIZER at span, "Array designator cannot initialize non-array type `{}`", current);
+                },
+                (_, Designator::Member(name, _)) => {
+                    semantic_error!(INVALID_INITIALIZER at span, "Field designator `{}` cannot initialize non-structure type `{}`", name, current);
+                }
+            };
+
+            current = self.child_type(&current, index)?;
+            path.push(index);
+        }
+
+        return Ok(path);
+    }
+
+    // Path to the subobject after the one at `path`, which is the next element or member
+    // of the innermost aggregate that has one left. Only one member of a union is initialized
+    fn next(&self, ty: &Type, path: &[usize]) -> Result<Option<Vec<usize>>, ASTError> {
+        if path.is_empty() {
+            return Ok(match self.bound(ty)? {
+                Some(0) => None,
+                _ => Some(vec![0])
+            });
+        }
+
+        let mut path = path.to_vec();
+        while let Some(index) = path.pop() {
+            let parent = self.type_at(ty, &path)?;
+            if let Type::Structure(StructKind::Union, _) = parent.unqualified() {
+                continue;
+            }
+
+            match self.bound(&parent)? {
+                Some(count) if index + 1 >= count => continue,
+                _ => {
+                    path.push(index + 1);
+                    return Ok(Some(path));
+                }
+            }
+        }
+
+        return Ok(None);
+    }
+
+    // Initializes the subobject at `path`, returning the path to the subobject that was
+    // initialized last. Without braces, an aggregate that can't be initialized by the
+    // expression as a whole has its first scalar initialized instead, and the elements
+    // after it by the initializers that follow
+    fn assign(&self, root: &mut Node, ty: &Type, path: Vec<usize>, init: Initializer) -> Result<Vec<usize>, ASTError> {
+        let target = self.type_at(ty, &path)?;
+
+        let node = match init {
+            Initializer::Single(e) if Self::is_aggregate(&target) && !self.initializes_whole(&e, &target)? => {
+                let mut path = path;
+                path.push(0);
+                return self.assign(root, ty, path, Initializer::Single(e));
+            },
+            Initializer::Single(e) => Node::Leaf(Initializer::Single(e)),
+            Initializer::Compound(list, span) if Self::is_aggregate(&target) && !(is_character_array(&target) && list.len() == 1 && Self::is_string(&list[0])) => {
+                self.fill(&target, list, span)?
+            },
+            init => Node::Leaf(self.normalize(init, &target)?)
+        };
+
+        *self.node_at(root, ty, &path)? = node;
+        return Ok(path);
+    }
+
+    // Whether an expression initializes an aggregate as a whole, like a structure
+    // of the same type, or a string literal initializing a character array
+    fn initializes_whole(&self, e: &Expression, ty: &Type) -> Result<bool, ASTError> {
+        if let ExpressionKind::String(_) = e.kind {
+            return Ok(is_character_array(ty));
+        }
+        if let Type::Structure(_, _) = ty.unqualified() {
+            return Ok(typecheck::type_of(e.clone(), self.symbols)?.unqualified() == ty.unqualified());
+        }
+
+        return Ok(false);
+    }
+
+    // The node of the subobject at `path`, creating the nodes of the aggregates
+    // that contain it. Initializing a member of a union discards any other
+    fn node_at<'n>(&self, root: &'n mut Node, ty: &Type, path: &[usize]) -> Result<&'n mut Node, ASTError> {
+        let mut node = root;
+        let mut current = ty.clone();
+
+        for index in path {
+            if !matches!(node, Node::Aggregate(_, _)) {
+                let count = self.bound(&current)?.unwrap_or_default();
+                *node = Node::Aggregate((0..count).map(|_| Node::Empty).collect(), self.span);
+            }
+            let Node::Aggregate(children, _) = node else { unreachable!() };
+
+            if let Type::Structure(StructKind::Union, _) = current.unqualified() {
+                children.iter_mut().for_each(|child| *child = Node::Empty);
+            }
+            while children.len() <= *index {
+                children.push(Node::Empty);
+            }
+
+            current = self.child_type(&current, *index)?;
+            node = &mut children[*index];
+        }
+
+        return Ok(node);
+    }
+
+    fn finish(&self, node: Node, ty: &Type) -> Initializer {
+        let (children, span) = match node {
+            Node::Empty if Self::is_aggregate(ty) => return Initializer::Compound(vec![], self.span),
+            Node::Empty => {
+                let zero = ConstantValue::Int(0);
+                return Initializer::Single(Expression::new(ExpressionKind::Constant(zero), self.span));
+            },
+            Node::Leaf(init) => return init,
+            Node::Aggregate(children, span) => (children, span)
+        };
+
+        // Unions keep the designator of the member they are initialized through
+        if let Type::Structure(StructKind::Union, tag) = ty.unqualified() {
+            let members = &self.symbols.get_struct(tag).expect("Union is complete").members;
+            return match children.into_iter().enumerate().find(|(_, child)| !matches!(child, Node::Empty)) {
+                Some((0, child)) => Initializer::Compound(vec![self.finish(child, &members[0].ty)], span),
+                Some((index, child)) => {
+                    let init = self.finish(child, &members[index].ty);
+                    Initializer::Compound(vec![Initializer::Designated(vec![Designator::Member(members[index].name.clone(), span)], Box::new(init))], span)
+                },
+                None => Initializer::Compound(vec![], span)
+            };
+        }
+
+        let length = children.iter().rposition(|child| !matches!(child, Node::Empty)).map_or(0, |last| last + 1);
+        let list = children.into_iter().take(length).enumerate()
+            .map(|(index, child)| self.finish(child, &self.child_type(ty, index).expect("Aggregate is complete")))
+            .collect();
+        return Initializer::Compound(list, span);
+    }
+}
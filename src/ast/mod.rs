@@ -1,2 +1,23 @@
+// Semantic errors are pointed at the given span, or otherwise at the innermost
+// expression or statement they are found in (see `ASTError::within`)
+macro_rules! semantic_error {
+    ($code:ident at $span:expr, $($arg:tt)*) => {
+        return Err($crate::ast::parser::ASTError::SemanticError(
+            $crate::diagnostics::Diagnostic::error(format!($($arg)*)).with_code($crate::diagnostics::$code).with_label($span, "")
+        ));
+    };
+    ($code:ident, $($arg:tt)*) => {
+        return Err($crate::ast::parser::ASTError::SemanticError(
+            $crate::diagnostics::Diagnostic::error(format!($($arg)*)).with_code($crate::diagnostics::$code)
+        ));
+    };
+}
+
+pub mod consteval;
+pub mod initializer;
 pub mod parser;
+pub mod resolve;
 pub mod symbols;
+pub mod table;
+pub mod typecheck;
+pub mod types;
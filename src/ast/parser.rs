@@ -1,241 +1,1700 @@
+use std::collections::{HashMap, HashSet};
 use super::symbols::*;
+use super::consteval;
+use crate::diagnostics::{self, Diagnostic};
 use crate::lexer::{Tag, Token};
 
+// `syntax_error!(CODE at span, ...)` points the error at the given span (or token
+// range), and gives it one of the codes of `diagnostics`
 macro_rules! syntax_error {
-    ($msg:expr) => {
-        return Err(ASTError::SyntaxError($msg.to_string()));
-    };
-    ($msg:expr, $($arg:tt)*) => {
-        return Err(ASTError::SyntaxError(format!($msg, $($arg)*)));
+    ($code:ident at $span:expr, $($arg:tt)*) => {
+        return Err(ASTError::SyntaxError(Diagnostic::error(format!($($arg)*)).with_code(crate::diagnostics::$code).with_label(Span::from($span), "")));
     };
 }
 
 
-enum ASTParserState<'a> {
-    Start,
-    DeclarationKind(Tag),
-    Declaration(Tag, &'a str)
+// Specifiers that precede a declarator, e.g. `static int`, along with the structures
+// and enumerations they define, e.g. `struct s { int x; }` in `struct s { int x; } v;`
+struct DeclarationSpecifiers {
+    var_type: Type,
+    storage_class: Option<StorageClass>,
+    // `typedef` is syntactically a storage class, but declares a type name instead of an object
+    is_typedef: bool,
+    // `inline`, the only function specifier
+    is_inline: bool,
+    definitions: Vec<Declaration>
+}
+
+// What the parser needs to know about the identifiers declared in a scope:
+// an identifier may name a type, in which case it starts a declaration or a
+// cast (as in `T * x;` or `(T)(x)`), and enumerators are replaced by their
+// value wherever they are used, which makes them usable in constant expressions
+#[derive(Default)]
+struct Scope {
+    identifiers: HashMap<String, Binding>,
+    enum_tags: HashSet<String>
+}
+
+enum Binding {
+    Typedef(Type),
+    Enumerator(ConstantValue),
+    // Objects and functions, which hide enumerators of enclosing scopes
+    Ordinary
+}
+
+// Declarators derive the type of a declaration from its specifiers, e.g.
+// in `int *f(long a)`, `f` is a function returning a pointer to an int
+enum Declarator {
+    Identifier(String, Span),
+    // The qualifiers of the pointer itself, as in `int *const p`
+    Pointer(Qualifiers, Box<Declarator>),
+    // The size may only be omitted for parameters, e.g. `char *argv[]`
+    Array(Box<Declarator>, Option<Expression>),
+    // Parameters, and whether they are followed by `...`
+    Function(Parameters, bool, Box<Declarator>),
+    // Type names, as in casts, have a declarator without an identifier, e.g. `int (*)[3]`
+    Abstract
+}
+
+impl Declarator {
+    // Where the declared identifier is, if the declarator has one
+    fn span(&self) -> Span {
+        match self {
+            Declarator::Identifier(_, span) => *span,
+            Declarator::Pointer(_, inner) | Declarator::Array(inner, _) | Declarator::Function(_, _, inner) => inner.span(),
+            Declarator::Abstract => Span::default()
+        }
+    }
 }
 
-enum FunctionParserState {
-    Start,
-    ArgumentListStart,
-    ArgumentListEnd,
-    Body,
-    StatementEnd,
-    End
+// The type and declarator of each parameter of a function declarator
+type Parameters = Vec<(Type, Declarator)>;
+
+// The names of the parameters of a function declarator, and where they are declared
+type ParameterNames = Vec<(String, Span)>;
+
+// Whether a declarator names what it declares: declarations do, type
+// names don't, and parameters may, as in `int f(char *, int n)`
+#[derive(Clone, Copy, PartialEq)]
+enum Naming {
+    Required,
+    Forbidden,
+    Optional
 }
 
 pub struct ASTParser {
     buffer: String,
-    tokens: Vec<Token>
+    tokens: Vec<Token>,
+    // Anonymous structures are given a tag that can't clash with any identifier
+    anonymous_count: usize,
+    // The first scope is the file scope, every other one is a block scope
+    scopes: Vec<Scope>,
+    // Syntax errors recovered from so far, and how many to report
+    // at most before giving up on the rest of the file (0 for no limit)
+    errors: Vec<ASTError>,
+    error_limit: usize,
+    // Whether an error past the limit was found, so parsing stopped before the end of the file
+    stopped_early: bool
 }
 
 #[derive(Debug)]
 pub enum ASTError {
-    SyntaxError(String)
+    SyntaxError(Diagnostic),
+    SemanticError(Diagnostic)
 }
 impl std::fmt::Display for ASTError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Self::SyntaxError(msg) => {
-                return write!(f, "Syntax error: {}", msg);
+            Self::SyntaxError(diagnostic) => {
+                return write!(f, "Syntax error: {}", diagnostic.message);
+            },
+            Self::SemanticError(diagnostic) => {
+                return write!(f, "Semantic error: {}", diagnostic.message);
             }
         }
     }
 }
+impl ASTError {
+    pub fn into_diagnostic(self) -> Diagnostic {
+        match self {
+            Self::SyntaxError(diagnostic) | Self::SemanticError(diagnostic) => diagnostic
+        }
+    }
+
+    // Points the error at the construct it was found in, unless it already
+    // points at a more precise part of it, e.g. an operand of an expression
+    pub fn within(self, span: Span) -> Self {
+        let located = |diagnostic: Diagnostic| match diagnostic.primary_span() {
+            Some(_) => diagnostic,
+            None => diagnostic.with_label(span, "")
+        };
+        match self {
+            Self::SyntaxError(diagnostic) => Self::SyntaxError(located(diagnostic)),
+            Self::SemanticError(diagnostic) => Self::SemanticError(located(diagnostic))
+        }
+    }
+}
 
+// Every `parse_*` method below receives the index of the first token of the
+// construct it parses, and returns the index of the first token after it.
 impl ASTParser {
     pub fn new(buffer: String, tokens: Vec<Token>) -> Self {
-        Self { buffer, tokens }
+        Self { buffer, tokens, anonymous_count: 0, scopes: vec![Scope::default()], errors: vec![], error_limit: 0, stopped_early: false }
+    }
+
+    pub fn set_error_limit(&mut self, limit: usize) {
+        self.error_limit = limit;
     }
 
-    pub fn parse(&mut self) -> Result<Program, ASTError> {
-        use ASTParserState as S;
+    pub fn stopped_early(&self) -> bool {
+        return self.stopped_early;
+    }
 
-        let mut state = ASTParserState::Start;
-        
+    // Parses the whole file, recovering from syntax errors to report as many as possible
+    pub fn parse(&mut self) -> Result<Program, Vec<ASTError>> {
         let mut index: usize = 0;
         let mut program: Program = vec![];
 
-        let buffer = self.buffer.clone();
-        let buffer = buffer.as_str();
-
-        loop {
-            if index >= self.tokens.len() {
-                break Ok(program);
+        while !self.at_end(index) {
+            match self.parse_declaration(index) {
+                Ok((new_index, declarations)) => {
+                    program.extend(declarations);
+                    index = new_index;
+                },
+                Err(error) => match self.recover(error, index) {
+                    Ok(new_index) => {
+                        self.scopes.truncate(1);
+                        index = new_index;
+                        // A `}` without a matching `{` doesn't end anything at file scope
+                        if self.tokens[index].tag == Tag::RBrace {
+                            index += 1;
+                        }
+                    },
+                    Err(error) => {
+                        if !self.stopped_early {
+                            self.errors.push(error);
+                        }
+                        break;
+                    }
+                }
             }
-            
+        }
+
+        if !self.errors.is_empty() {
+            return Err(std::mem::take(&mut self.errors));
+        }
+        return Ok(program);
+    }
+
+    fn at_end(&self, index: usize) -> bool {
+        self.tokens.get(index).is_none_or(|token| token.tag == Tag::Eof)
+    }
+
+    // Records a syntax error in the construct starting at `index`, and returns where parsing
+    // resumes, or returns the error if parsing must stop: when it is past the error limit,
+    // or when nothing is left to parse after it. Errors are propagated up to the file scope,
+    // where they are recorded, so each error is only recorded once
+    fn recover(&mut self, error: ASTError, index: usize) -> Result<usize, ASTError> {
+        // Errors past the limit aren't reported, but tell that some were left out
+        if self.error_limit != 0 && self.errors.len() >= self.error_limit {
+            self.stopped_early = true;
+            return Err(error);
+        }
+
+        let resume = self.synchronize(index);
+        if self.at_end(resume) {
+            return Err(error);
+        }
+
+        self.errors.push(error);
+        return Ok(resume);
+    }
+
+    // Skips the construct starting at `index` after a syntax error in it, up to a point
+    // where parsing can resume: after the next `;` or function body, before a `}` closing
+    // the enclosing block, or before a declaration starting a new line. Only tokens outside
+    // of parentheses, brackets and braces are synchronization points. Braces are counted
+    // apart from the others, so that a `}` always closes the block it is in, even when a
+    // parenthesis was left open before it, as in `{ return (3 + ; }`
+    fn synchronize(&self, index: usize) -> usize {
+        let mut index = index + 1;
+        let mut braces: usize = 0;
+        let mut groups: usize = 0;
+        // Whether the outermost braces are a function body, after which a declaration ends,
+        // unlike those of a structure or an initializer, which are followed by more of it
+        let mut body = false;
+
+        while !self.at_end(index) {
             let token = &self.tokens[index];
-            match state {
-                S::Start => match token.tag {
-                    Tag::KInt | Tag::KVoid => {
-                        state = S::DeclarationKind(token.tag.clone())
-                    },
-                    Tag::Eof => {},
-                    _ => {
-                        syntax_error!("Unexpected token at {:?}: expected new declaration, got `{:?}` instead", token.range, token.tag);
+            match token.tag {
+                Tag::LBrace => {
+                    if braces == 0 {
+                        body = self.tokens[index - 1].tag == Tag::RParen;
                     }
+                    braces += 1;
                 },
-
-                S::DeclarationKind(kind) => match token.tag {
-                    Tag::Identifier => {
-                        let name = &buffer[token.range.clone()];
-                        state = S::Declaration(kind, name);
-                    },
-                    _ => {
-                        syntax_error!("Unexpected token at {:?}: expected declaration identifier, got `{:?}` instead", token.range, token.tag);
+                Tag::RBrace if braces == 0 => return index,
+                Tag::RBrace => {
+                    braces -= 1;
+                    if braces == 0 && body {
+                        return index + 1;
                     }
                 },
+                Tag::LParen | Tag::LBracket => groups += 1,
+                Tag::RParen | Tag::RBracket => groups = groups.saturating_sub(1),
+                Tag::Semicolon if braces == 0 && groups == 0 => return index + 1,
+                _ if braces == 0 && groups == 0 && self.starts_line(index) && self.is_declaration_start(index) => return index,
+                _ => {}
+            }
+            index += 1;
+        }
 
-                S::Declaration(_kind, name) => match token.tag {
-                    // If we encounter a left parenthesis after a declaration,
-                    // this means it is a function -- parse it and add it to the program
-                    Tag::LParen => {
-                        let (new_index, statements) = self.parse_function(index)?;
-                        program.push(Declaration::Function(FunctionDefinition { name: name.to_string(), statements }));
-                        index = new_index;
+        return index;
+    }
 
-                        state = S::Start;
-                        continue;
-                    },
+    // Whether a line break separates the token at `index` from the one before it
+    fn starts_line(&self, index: usize) -> bool {
+        let start = self.tokens[index - 1].range.end;
+        let end = self.tokens[index].range.start;
+        return self.buffer.get(start..end).is_some_and(|between| between.contains('\n'));
+    }
 
-                    _ => {
-                        syntax_error!("Unexpected token at {:?}: expected `(`, got `{:?}` instead", token.range, token.tag);
-                    }
-                }
+    // Returns the token at `index`, or a syntax error if we ran out of tokens
+    fn peek(&self, index: usize) -> Result<&Token, ASTError> {
+        match self.tokens.get(index) {
+            Some(token) if token.tag != Tag::Eof => Ok(token),
+            _ => {
+                let end = self.tokens.iter().rev().find(|token| token.tag != Tag::Eof).map_or(0, |token| token.range.end);
+                let diagnostic = Diagnostic::error("Unexpected end of file")
+                    .with_code(diagnostics::UNEXPECTED_EOF)
+                    .with_label(Span { start: end, end }, "");
+                Err(ASTError::SyntaxError(diagnostic))
             }
+        }
+    }
 
-            index += 1;
+    // Consumes a token of the given kind, returning the index after it
+    fn expect(&self, index: usize, tag: Tag, context: &str) -> Result<usize, ASTError> {
+        let token = self.peek(index)?;
+        if token.tag != tag {
+            let expected = tag.spelling().map_or_else(|| format!("{:?}", tag), str::to_string);
+            let mut diagnostic = Diagnostic::error(format!("Expected `{}` {}, found `{}`", expected, context, self.text(token)))
+                .with_code(diagnostics::UNEXPECTED_TOKEN)
+                .with_label(Span::from(token.range.clone()), format!("expected `{}`", expected));
+
+            // A missing terminator most likely belongs right after the previous token, though
+            // a missing `;` only if what follows starts a new line or statement: otherwise,
+            // as with the `,` of `return a, b;`, something else is more likely wrong
+            let is_missing = match tag {
+                Tag::Semicolon => index > 0 && (self.starts_line(index) || self.is_statement_start(index)),
+                Tag::RParen | Tag::RBracket => true,
+                _ => false
+            };
+            if is_missing && index > 0 {
+                let end = self.tokens[index - 1].range.end;
+                diagnostic = diagnostic.with_fixit(Span { start: end, end }, expected.clone(), format!("insert `{}`", expected));
+            }
+            return Err(ASTError::SyntaxError(diagnostic));
         }
+
+        return Ok(index + 1);
     }
 
-    fn parse_function(&mut self, start_index: usize) -> Result<(usize, Vec<Statement>), ASTError> {
-        use FunctionParserState as F;
+    // Span of the tokens from `start` up to (but excluding) `end`
+    fn span(&self, start: usize, end: usize) -> Span {
+        let end = self.tokens[end.max(start + 1) - 1].range.end;
+        return Span { start: self.tokens[start].range.start, end };
+    }
 
-        let mut index = start_index;
-        let mut state = FunctionParserState::Start;
+    // Source text covered by a token
+    fn text(&self, token: &Token) -> String {
+        self.buffer.get(token.range.clone()).unwrap_or_default().to_string()
+    }
 
-        let mut statements: Vec<Statement> = vec![];
+    fn current_scope(&mut self) -> &mut Scope {
+        self.scopes.last_mut().expect("Parser has no scope")
+    }
 
-        loop {
-            if index >= self.tokens.len() {
-                syntax_error!("Unexpected end of file while parsing function");
+    fn declare(&mut self, name: String, binding: Binding, span: Span) -> Result<(), ASTError> {
+        // Typedef names share the namespace of ordinary identifiers, but the
+        // resolver never sees them, so conflicts are diagnosed here instead
+        match (self.current_scope().identifiers.get(&name), &binding) {
+            (Some(Binding::Typedef(prior)), Binding::Typedef(ty)) if prior != ty => {
+                syntax_error!(CONFLICTING_TYPES at span, "Typedef redefinition of `{}` with different types (`{}` vs `{}`)", name, ty, prior);
+            },
+            (Some(Binding::Typedef(_)), Binding::Ordinary | Binding::Enumerator(_)) | (Some(Binding::Ordinary | Binding::Enumerator(_)), Binding::Typedef(_)) => {
+                syntax_error!(REDECLARATION at span, "`{}` redeclared as a different kind of symbol", name);
+            },
+            _ => {}
+        }
+
+        self.current_scope().identifiers.insert(name, binding);
+        return Ok(());
+    }
+
+    fn lookup(&self, name: &str) -> Option<&Binding> {
+        self.scopes.iter().rev().find_map(|scope| scope.identifiers.get(name))
+    }
+
+    fn is_type_specifier(tag: Tag) -> bool {
+        matches!(tag, Tag::KInt | Tag::KVoid | Tag::KChar | Tag::KShort | Tag::KLong | Tag::KSigned | Tag::KUnsigned | Tag::KFloat | Tag::KDouble | Tag::KBool | Tag::KStruct | Tag::KUnion | Tag::KEnum | Tag::KBuiltinVaList)
+    }
+
+    // Type of the typedef name at `index`, if the identifier there is one
+    fn typedef_name(&self, index: usize) -> Option<&Type> {
+        let token = self.tokens.get(index)?;
+        if token.tag != Tag::Identifier {
+            return None;
+        }
+
+        match self.lookup(&self.text(token)) {
+            Some(Binding::Typedef(ty)) => Some(ty),
+            _ => None
+        }
+    }
+
+    fn is_type_qualifier(tag: Tag) -> bool {
+        matches!(tag, Tag::KConst | Tag::KVolatile | Tag::KRestrict)
+    }
+
+    // Adds the qualifier at `index` to a set of qualifiers; repeating one has no effect
+    fn add_qualifier(qualifiers: &mut Qualifiers, tag: Tag) {
+        match tag {
+            Tag::KConst => qualifiers.constant = true,
+            Tag::KVolatile => qualifiers.volatile = true,
+            _ => qualifiers.restrict = true
+        }
+    }
+
+    fn is_type_name_start(&self, index: usize) -> bool {
+        match self.tokens.get(index) {
+            Some(token) => Self::is_type_specifier(token.tag) || Self::is_type_qualifier(token.tag) || self.typedef_name(index).is_some(),
+            None => false
+        }
+    }
+
+    fn is_declaration_start(&self, index: usize) -> bool {
+        self.is_type_name_start(index) || self.tokens.get(index).is_some_and(|t| matches!(t.tag, Tag::KStatic | Tag::KExtern | Tag::KTypedef | Tag::KInline | Tag::KStaticAssert))
+    }
+
+    // Whether the token at `index` may start a statement or declaration
+    fn is_statement_start(&self, index: usize) -> bool {
+        if self.is_declaration_start(index) {
+            return true;
+        }
+        return self.tokens.get(index).is_some_and(|t| matches!(t.tag,
+            Tag::Identifier | Tag::NumberLiteral | Tag::StringLiteral | Tag::LParen | Tag::LBrace | Tag::Semicolon | Tag::KReturn
+            | Tag::KSizeof | Tag::KAlignof | Tag::KBuiltinVaStart | Tag::KBuiltinVaArg | Tag::KBuiltinVaEnd | Tag::KBuiltinVaCopy
+            | Tag::OpNegation | Tag::OpComplement | Tag::OpMultiplication | Tag::OpBitAnd));
+    }
+
+    // Determines the type named by a list of type specifiers, which may
+    // appear in any order, e.g. `unsigned long int` or `long unsigned`
+    fn parse_type(specifiers: &[Tag]) -> Option<Type> {
+        let count = |tag: Tag| specifiers.iter().filter(|t| **t == tag).count();
+        let (void, char, short, int, long) = (count(Tag::KVoid), count(Tag::KChar), count(Tag::KShort), count(Tag::KInt), count(Tag::KLong));
+        let (signed, unsigned) = (count(Tag::KSigned), count(Tag::KUnsigned));
+
+        if void > 1 || char > 1 || short > 1 || int > 1 || long > 2 || signed + unsigned > 1 {
+            return None;
+        }
+        if void == 1 {
+            return if specifiers.len() == 1 { Some(Type::Void) } else { None };
+        }
+        if specifiers.contains(&Tag::KBuiltinVaList) {
+            return if specifiers.len() == 1 { Some(Type::va_list()) } else { None };
+        }
+        if specifiers.contains(&Tag::KBool) {
+            return if specifiers.len() == 1 { Some(Type::Bool) } else { None };
+        }
+        // `long double` is not supported
+        match (count(Tag::KFloat), count(Tag::KDouble), specifiers.len()) {
+            (0, 0, _) => {},
+            (1, 0, 1) => return Some(Type::Float),
+            (0, 1, 1) => return Some(Type::Double),
+            _ => return None
+        }
+        if char == 1 {
+            if short + int + long > 0 {
+                return None;
             }
-            
-            let token = &self.tokens[index];
+            return Some(match (signed, unsigned) {
+                (1, _) => Type::SChar,
+                (_, 1) => Type::UChar,
+                _ => Type::Char
+            });
+        }
+
+        let ty = match (short, long) {
+            (1, 0) => Type::Short,
+            (0, 0) => Type::Int,
+            (0, 1) => Type::Long,
+            (0, 2) => Type::LongLong,
+            _ => return None
+        };
 
-            match state {
-                F::Start => match token.tag {
-                    Tag::LParen => {
-                        state = F::ArgumentListStart;
+        if unsigned == 1 {
+            return Some(match ty {
+                Type::Short => Type::UShort,
+                Type::Int => Type::UInt,
+                Type::Long => Type::ULong,
+                _ => Type::ULongLong
+            });
+        }
+        return Some(ty);
+    }
+
+    fn parse_specifiers(&mut self, index: usize) -> Result<(usize, DeclarationSpecifiers), ASTError> {
+        let start_index = index;
+        let mut index = index;
+        let mut types: Vec<Tag> = vec![];
+        // Structure, enumeration and typedef names name a type on their own
+        let mut named_type: Option<Type> = None;
+        let mut qualifiers = Qualifiers::default();
+        let mut storage_class: Option<StorageClass> = None;
+        let mut is_typedef = false;
+        let mut is_inline = false;
+        let mut definitions = vec![];
+
+        loop {
+            let token = self.peek(index)?;
+            match token.tag {
+                // An identifier is only a typedef name if no type was specified yet:
+                // in `typedef int T; { unsigned T; }`, the inner `T` is a variable
+                Tag::Identifier if types.is_empty() && named_type.is_none() => {
+                    match self.typedef_name(index) {
+                        Some(ty) => named_type = Some(ty.clone()),
+                        None => break
                     }
-                    _ => {
-                        syntax_error!("Expected argument list following declaration at {:?}; got `{:?}` instead", token.range, token.tag);
+                },
+
+                Tag::KStruct | Tag::KUnion | Tag::KEnum => {
+                    if named_type.is_some() {
+                        syntax_error!(INVALID_SPECIFIERS at token.range.clone(), "Invalid combination of type specifiers");
                     }
+
+                    let (new_index, ty) = match token.tag {
+                        Tag::KEnum => self.parse_enum_specifier(index, &mut definitions)?,
+                        _ => self.parse_struct_specifier(index, &mut definitions)?
+                    };
+                    named_type = Some(ty);
+                    index = new_index;
+                    continue;
                 },
 
-                F::ArgumentListStart => match token.tag {
-                    Tag::KVoid => {},
-                    Tag::RParen => {
-                        state = F::ArgumentListEnd;
+                tag if Self::is_type_specifier(tag) => {
+                    types.push(tag);
+                },
+
+                tag if Self::is_type_qualifier(tag) => Self::add_qualifier(&mut qualifiers, tag),
+
+                Tag::KStatic | Tag::KExtern | Tag::KTypedef => {
+                    if storage_class.is_some() || is_typedef {
+                        syntax_error!(INVALID_SPECIFIERS at token.range.clone(), "More than one storage class in declaration specifiers");
                     }
-                    _ => {
-                        syntax_error!("Unexpected token `{:?}` in argument list", token.tag);
+                    match token.tag {
+                        Tag::KStatic => storage_class = Some(StorageClass::Static),
+                        Tag::KExtern => storage_class = Some(StorageClass::Extern),
+                        _ => is_typedef = true
                     }
                 },
 
-                F::ArgumentListEnd => match token.tag {
-                    Tag::LBrace => {
-                        state = F::Body;
-                    },
+                // Like qualifiers, repeating `inline` has no effect
+                Tag::KInline => is_inline = true,
+
+                _ => break
+            }
+
+            index += 1;
+        }
+
+        // Named types can't be combined with any other type specifier
+        if let Some(var_type) = named_type {
+            if !types.is_empty() {
+                let token = &self.tokens[start_index];
+                syntax_error!(INVALID_SPECIFIERS at token.range.clone(), "Invalid combination of type specifiers {:?} with `{}`", types, var_type);
+            }
+            let var_type = var_type.qualified(qualifiers);
+            return Ok((index, DeclarationSpecifiers { var_type, storage_class, is_typedef, is_inline, definitions }));
+        }
+
+        if types.is_empty() {
+            let token = self.peek(index)?;
+            syntax_error!(UNEXPECTED_TOKEN at token.range.clone(), "Expected new declaration, found `{}`", self.text(token));
+        }
+
+        match Self::parse_type(&types) {
+            Some(var_type) => {
+                let var_type = var_type.qualified(qualifiers);
+                Ok((index, DeclarationSpecifiers { var_type, storage_class, is_typedef, is_inline, definitions }))
+            },
+            None => {
+                let token = &self.tokens[start_index];
+                syntax_error!(INVALID_SPECIFIERS at token.range.clone(), "Invalid combination of type specifiers {:?}", types);
+            }
+        }
+    }
+
+    // Parses `struct tag`, `struct tag { members }` or `struct { members }`, adding
+    // the definition (and those of any structure defined within it) to `definitions`
+    fn parse_struct_specifier(&mut self, index: usize, definitions: &mut Vec<Declaration>) -> Result<(usize, Type), ASTError> {
+        let start = index;
+        let kind = match self.peek(index)?.tag {
+            Tag::KUnion => StructKind::Union,
+            _ => StructKind::Struct
+        };
+        let mut index = index + 1;
+
+        let token = self.peek(index)?;
+        let tag = match token.tag {
+            Tag::Identifier => {
+                index += 1;
+                Some(self.text(token))
+            },
+            _ => None
+        };
+
+        if self.peek(index)?.tag != Tag::LBrace {
+            match tag {
+                Some(tag) => return Ok((index, Type::Structure(kind, tag))),
+                None => {
+                    let token = self.peek(index)?;
+                    syntax_error!(UNEXPECTED_TOKEN at token.range.clone(), "Expected structure tag or member list, found `{}`", self.text(token));
+                }
+            }
+        }
+
+        let tag = match tag {
+            Some(tag) => tag,
+            None => {
+                self.anonymous_count += 1;
+                format!("anonymous.{}", self.anonymous_count)
+            }
+        };
+
+        index += 1;
+        let mut members: Vec<MemberDeclaration> = vec![];
+        while self.peek(index)?.tag != Tag::RBrace {
+            let token = self.peek(index)?.clone();
+            if !self.is_type_name_start(index) {
+                syntax_error!(UNEXPECTED_TOKEN at token.range.clone(), "Unexpected token `{}` in member list: expected member declaration", self.text(&token));
+            }
+
+            let (new_index, specifiers) = self.parse_specifiers(index)?;
+            if specifiers.storage_class.is_some() || specifiers.is_typedef || specifiers.is_inline {
+                syntax_error!(INVALID_SPECIFIERS at token.range.clone(), "Unexpected storage class or `inline` in member list");
+            }
+            definitions.extend(specifiers.definitions);
+            index = new_index;
+
+            // Each member declarator of a list, as in `int a, *p, : 3;`, declares a member of its own
+            loop {
+                let member_start = index;
+                // Unnamed bit-fields have no declarator
+                let (declarator_index, name, member_type) = match self.peek(index)?.tag {
+                    Tag::Colon => (index, String::new(), specifiers.var_type.clone()),
                     _ => {
-                        syntax_error!("Unexpected token `{:?}` after argument list", token.tag);
+                        let (declarator_index, declarator) = self.parse_declarator(index)?;
+                        let span = self.span(index, declarator_index);
+                        let (name, member_type, _) = Self::process_declarator(declarator, specifiers.var_type.clone(), span)?;
+                        if let Type::Function(_) = member_type {
+                            syntax_error!(INVALID_DECLARATOR at span, "Member `{}` declared as a function", name);
+                        }
+                        (declarator_index, name, member_type)
                     }
-                },
-
-                F::Body => match token.tag {
-                    Tag::KReturn => {
-                        index += 1;
-                        let (new_index, expression) = self.parse_expression(index)?;
-                        statements.push(Statement::Return(expression));
-                        index = new_index;
+                };
 
-                        state = F::StatementEnd;
+                let (declarator_index, bit_width) = match self.peek(declarator_index)?.tag {
+                    Tag::Colon => {
+                        let (index, width) = self.parse_binary(declarator_index + 1, 2)?;
+                        match consteval::evaluate(&width) {
+                            Some((width, ty)) if ty.is_integer() && width >= 0 => (index, Some(width as usize)),
+                            _ => {
+                                syntax_error!(INVALID_CONSTANT at width.span, "Width of bit-field `{}` is not a nonnegative integer constant expression", name);
+                            }
+                        }
                     },
+                    _ => (declarator_index, None)
+                };
 
-                    Tag::RBrace => {
-                        state = F::End;
-                        continue;
-                    }
+                let span = self.span(member_start, declarator_index);
+                members.push(MemberDeclaration { name, member_type, bit_width, span });
+                let (next_index, is_last) = self.end_declarator(declarator_index, "after member declaration")?;
+                index = next_index;
+                if is_last {
+                    break;
+                }
+            }
+        }
+
+        if members.is_empty() {
+            syntax_error!(INVALID_DECLARATOR at self.span(start, index + 1), "Structure `{}` must have at least one member", tag);
+        }
 
+        let span = self.span(start, index + 1);
+        definitions.push(Declaration::Struct(StructDeclaration { tag: tag.clone(), kind, members: Some(members), span }));
+        return Ok((index + 1, Type::Structure(kind, tag)));
+    }
+
+    // Parses `enum tag`, `enum tag { enumerators }` or `enum { enumerators }`. Enumerations
+    // have type `int`, and each enumerator is one more than the previous one unless
+    // it is given a value, e.g. `enum { A, B = 5, C }` declares A = 0, B = 5 and C = 6
+    fn parse_enum_specifier(&mut self, index: usize, definitions: &mut Vec<Declaration>) -> Result<(usize, Type), ASTError> {
+        let start = index;
+        let mut index = index + 1;
+
+        let tag_token = self.peek(index)?.clone();
+        let tag = match tag_token.tag {
+            Tag::Identifier => {
+                index += 1;
+                Some(self.text(&tag_token))
+            },
+            _ => None
+        };
+
+        if self.peek(index)?.tag != Tag::LBrace {
+            let token = self.peek(index)?;
+            match tag {
+                Some(tag) if self.scopes.iter().any(|scope| scope.enum_tags.contains(&tag)) => return Ok((index, Type::Int)),
+                Some(tag) => {
+                    syntax_error!(UNDECLARED_IDENTIFIER at token.range.clone(), "Use of undeclared enumeration `enum {}`", tag);
+                },
+                None => {
+                    syntax_error!(UNEXPECTED_TOKEN at token.range.clone(), "Expected enumeration tag or enumerator list, found `{}`", self.text(token));
+                }
+            }
+        }
+
+        if let Some(tag) = tag {
+            if !self.current_scope().enum_tags.insert(tag.clone()) {
+                syntax_error!(REDECLARATION at tag_token.range, "Redefinition of `enum {}`", tag);
+            }
+        }
+
+        index += 1;
+        let mut enumerators: Vec<Enumerator> = vec![];
+        let mut next: i128 = 0;
+        loop {
+            let token = self.peek(index)?;
+            if token.tag != Tag::Identifier {
+                syntax_error!(UNEXPECTED_TOKEN at token.range.clone(), "Expected enumerator, found `{}`", self.text(token));
+            }
+            let name = self.text(token);
+            let span = Span::from(token.range.clone());
+            index += 1;
+
+            if self.peek(index)?.tag == Tag::OpAssign {
+                let (new_index, value) = self.parse_binary(index + 1, 2)?;
+                next = match consteval::evaluate(&value) {
+                    Some((value, ty)) if ty.is_integer() => value,
                     _ => {
-                        syntax_error!("Unexpected token `{:?}` in function body", token.tag);
+                        syntax_error!(INVALID_CONSTANT at value.span, "Value of enumerator `{}` is not an integer constant expression", name);
                     }
+                };
+                index = new_index;
+            }
+            if Type::Int.wrap(next) != next {
+                syntax_error!(INVALID_CONSTANT at span, "Value of enumerator `{}` is not representable as an `int`", name);
+            }
+
+            // Each enumerator is in scope right after its own definition
+            let value = ConstantValue::Int(next as i32);
+            self.declare(name.clone(), Binding::Enumerator(value.clone()), span)?;
+            enumerators.push(Enumerator { name, value, span });
+            next += 1;
+
+            // A trailing comma is allowed before the closing brace
+            let token = self.peek(index)?;
+            match token.tag {
+                Tag::Comma if self.peek(index + 1)?.tag == Tag::RBrace => break index += 2,
+                Tag::Comma => index += 1,
+                Tag::RBrace => break index += 1,
+                _ => {
+                    syntax_error!(UNEXPECTED_TOKEN at token.range.clone(), "Unexpected token `{}` in enumerator list", self.text(token));
+                }
+            }
+        }
+
+        let span = self.span(start, index);
+        definitions.push(Declaration::Enum(EnumDeclaration { enumerators, span }));
+        return Ok((index, Type::Int));
+    }
+
+    // Returns the declaration, preceded by any structures and enumerations defined in its specifiers
+    fn parse_declaration(&mut self, index: usize) -> Result<(usize, Vec<Declaration>), ASTError> {
+        if self.peek(index)?.tag == Tag::KStaticAssert {
+            let (index, assertion) = self.parse_static_assertion(index)?;
+            return Ok((index, vec![Declaration::StaticAssert(assertion)]));
+        }
+
+        let start = index;
+        let (index, specifiers) = self.parse_specifiers(index)?;
+        let mut declarations = specifiers.definitions;
+
+        // A declaration without a declarator, e.g. `struct s;`, `struct s { int x; };`
+        // or `enum { A, B };`, only declares its tag or enumerators
+        if self.peek(index)?.tag == Tag::Semicolon {
+            match &specifiers.var_type {
+                _ if specifiers.is_inline => {
+                    syntax_error!(INVALID_SPECIFIERS at self.peek(index)?.range.clone(), "`inline` can only appear on functions");
                 },
+                Type::Structure(kind, tag) if declarations.is_empty() => {
+                    let span = self.span(start, index + 1);
+                    declarations.push(Declaration::Struct(StructDeclaration { tag: tag.clone(), kind: *kind, members: None, span }));
+                    return Ok((index + 1, declarations));
+                },
+                _ if !declarations.is_empty() => return Ok((index + 1, declarations)),
+                _ => {}
+            }
+        }
+
+        // Each declarator of a list, as in `int a = 1, *p = &a, f(void);`, declares a name
+        // of its own, which is in scope right after it, e.g. in the initializers that follow
+        let mut index = index;
+        let mut declaration_start = start;
+        loop {
+            let declarator_start = index;
+            let (new_index, declarator) = self.parse_declarator(index)?;
+            let span = self.span(declarator_start, new_index);
+            let (name, declared_type, params) = Self::process_declarator(declarator, specifiers.var_type.clone(), span)?;
+            if specifiers.is_inline && (specifiers.is_typedef || !matches!(declared_type, Type::Function(_))) {
+                syntax_error!(INVALID_SPECIFIERS at span, "`inline` can only appear on functions, but `{}` is not one", name);
+            }
+
+            // Typedefs only name their type, which replaces the name wherever it is used
+            if specifiers.is_typedef {
+                self.declare(name, Binding::Typedef(declared_type), span)?;
+                let (new_index, is_last) = self.end_declarator(new_index, "after typedef")?;
+                if is_last {
+                    return Ok((new_index, declarations));
+                }
+                index = new_index;
+                continue;
+            }
+            self.declare(name.clone(), Binding::Ordinary, span)?;
 
-                F::StatementEnd => match token.tag {
-                    Tag::Semicolon => {
-                        state = F::Body;
+            // If the declarator declares a function, it may be followed by its body
+            // if it is the only one of the declaration -- parse it and return it
+            let (declaration, is_last) = if let Type::Function(_) = declared_type {
+                let has_body = declaration_start == start && self.peek(new_index)?.tag == Tag::LBrace;
+                let (new_index, statements, is_last) = match has_body {
+                    // Parameters are in scope within the body
+                    true => {
+                        let Type::Function(f) = &declared_type else { unreachable!() };
+                        if params.len() != f.params.len() || params.iter().any(|(param, _)| param.is_empty()) {
+                            syntax_error!(INVALID_DECLARATOR at span, "Parameter name omitted in definition of `{}`", name);
+                        }
+
+                        self.scopes.push(Scope::default());
+                        for (param, param_span) in &params {
+                            self.declare(param.clone(), Binding::Ordinary, *param_span)?;
+                        }
+                        let (index, statements) = self.parse_block(new_index)?;
+                        self.scopes.pop();
+
+                        (index, statements, true)
                     },
-                    _ => {
-                        syntax_error!("Expected semicolon after expression -- found `{:?} instead", token.tag);
+                    false => {
+                        let (index, is_last) = self.end_declarator(new_index, "after argument list")?;
+                        (index, vec![], is_last)
                     }
+                };
+                index = new_index;
+
+                let (params, param_spans) = params.into_iter().unzip();
+                let function = FunctionDefinition {
+                    name, params, param_spans, statements, has_body,
+                    function_type: declared_type,
+                    storage_class: specifiers.storage_class,
+                    is_inline: specifiers.is_inline,
+                    span: self.span(declaration_start, index)
+                };
+                (Declaration::Function(function), is_last)
+            } else {
+                // Otherwise, we are declaring a variable
+                if declared_type == Type::Void {
+                    syntax_error!(INVALID_DECLARATOR at span, "Variable `{}` declared void", name);
+                }
+
+                let (new_index, initializer) = match self.peek(new_index)?.tag {
+                    Tag::OpAssign => {
+                        let (index, initializer) = self.parse_initializer(new_index + 1)?;
+                        (index, Some(initializer))
+                    },
+                    _ => (new_index, None)
+                };
+
+                let (new_index, is_last) = self.end_declarator(new_index, "after declaration")?;
+                index = new_index;
+
+                let variable = VariableDeclaration {
+                    name, initializer,
+                    var_type: declared_type,
+                    storage_class: specifiers.storage_class,
+                    span: self.span(declaration_start, index)
+                };
+                (Declaration::Variable(variable), is_last)
+            };
+
+            declarations.push(declaration);
+            if is_last {
+                return Ok((index, declarations));
+            }
+            // Declarations after the first one span from their declarator on
+            declaration_start = index;
+        }
+    }
+
+    // Consumes the `,` or `;` after a declarator, returning
+    // the index after it and whether it ends the declaration
+    fn end_declarator(&self, index: usize, context: &str) -> Result<(usize, bool), ASTError> {
+        match self.peek(index)?.tag {
+            Tag::Comma => Ok((index + 1, false)),
+            _ => self.expect(index, Tag::Semicolon, context).map(|index| (index, true))
+        }
+    }
+
+    // `_Static_assert(condition, "message");`, whose condition may depend on the layout
+    // of structures, as in `sizeof(struct s) == 8`, so it is evaluated by the resolver
+    fn parse_static_assertion(&mut self, index: usize) -> Result<(usize, StaticAssertion), ASTError> {
+        let start = index;
+        let index = self.expect(index + 1, Tag::LParen, "after `_Static_assert`")?;
+        let (index, condition) = self.parse_binary(index, 2)?;
+        let index = self.expect(index, Tag::Comma, "after static assertion condition")?;
+
+        let token = self.peek(index)?;
+        if token.tag != Tag::StringLiteral {
+            syntax_error!(UNEXPECTED_TOKEN at token.range.clone(), "Expected string literal as the message of `_Static_assert`, found `{}`", self.text(token));
+        }
+        let (index, message) = match self.parse_primary(index)? {
+            (index, Expression { kind: ExpressionKind::String(bytes), .. }) => (index, bytes),
+            _ => unreachable!()
+        };
+
+        let index = self.expect(index, Tag::RParen, "to close `_Static_assert`")?;
+        let index = self.expect(index, Tag::Semicolon, "after `_Static_assert`")?;
+        let message = String::from_utf8_lossy(&message).to_string();
+        return Ok((index, StaticAssertion { condition, message, span: self.span(start, index) }));
+    }
+
+    fn parse_declarator(&mut self, index: usize) -> Result<(usize, Declarator), ASTError> {
+        return self.parse_any_declarator(index, Naming::Required);
+    }
+
+    // Parses the declarator of a type name, which has no identifier, e.g. `(*)[3]`
+    // in `int (*)[3]`, a pointer to an array of 3 ints
+    fn parse_abstract_declarator(&mut self, index: usize) -> Result<(usize, Declarator), ASTError> {
+        return self.parse_any_declarator(index, Naming::Forbidden);
+    }
+
+    // `*` applies to everything after it, parentheses group declarators, and array and
+    // function suffixes apply from left to right, so `a[2][3]` is an array of 2 arrays of
+    // 3 elements and `(*f)(int)` a pointer to a function, while `*f(int)` is a function
+    // returning a pointer
+    fn parse_any_declarator(&mut self, index: usize, naming: Naming) -> Result<(usize, Declarator), ASTError> {
+        if self.peek(index)?.tag == Tag::OpMultiplication {
+            let mut index = index + 1;
+            let mut qualifiers = Qualifiers::default();
+            while Self::is_type_qualifier(self.peek(index)?.tag) {
+                Self::add_qualifier(&mut qualifiers, self.peek(index)?.tag);
+                index += 1;
+            }
+
+            let (index, inner) = self.parse_any_declarator(index, naming)?;
+            return Ok((index, Declarator::Pointer(qualifiers, Box::new(inner))));
+        }
+
+        let token = self.peek(index)?;
+        let (mut index, mut declarator) = match token.tag {
+            Tag::Identifier if naming != Naming::Forbidden => (index + 1, Declarator::Identifier(self.text(token), token.range.clone().into())),
+            Tag::LParen if self.is_grouping(index, naming) => {
+                let (index, inner) = self.parse_any_declarator(index + 1, naming)?;
+                (self.expect(index, Tag::RParen, "to close declarator")?, inner)
+            },
+            _ if naming != Naming::Required => (index, Declarator::Abstract),
+            _ => {
+                syntax_error!(UNEXPECTED_TOKEN at token.range.clone(), "Expected declaration identifier, found `{}`", self.text(token));
+            }
+        };
+
+        loop {
+            match self.tokens.get(index).map(|t| t.tag) {
+                Some(Tag::LParen) => {
+                    let (new_index, params, variadic) = self.parse_parameters(index)?;
+                    index = new_index;
+                    declarator = Declarator::Function(params, variadic, Box::new(declarator));
                 },
+                Some(Tag::LBracket) => {
+                    // Type names may only omit the size of an array they name as a whole,
+                    // which compound literals take from their initializer, as in `(int[]){1, 2}`
+                    let (new_index, size) = match self.peek(index + 1)?.tag {
+                        Tag::RBracket if naming == Naming::Forbidden && !matches!(declarator, Declarator::Abstract) => {
+                            syntax_error!(INVALID_DECLARATOR at self.peek(index + 1)?.range.clone(), "Array size missing in type name");
+                        },
+                        Tag::RBracket => (index + 1, None),
+                        _ => {
+                            let (new_index, size) = self.parse_expression(index + 1)?;
+                            (new_index, Some(size))
+                        }
+                    };
+
+                    index = self.expect(new_index, Tag::RBracket, "to close array declarator")?;
+                    declarator = Declarator::Array(Box::new(declarator), size);
+                },
+                _ => break
+            }
+        }
+
+        return Ok((index, declarator));
+    }
 
-                F::End => {
-                    break Ok((index + 1, statements))
+    // Whether the parenthesis at `index` groups a declarator, rather than starting
+    // the parameters of a function type, as in `int ()` or `int (long)`
+    fn is_grouping(&self, index: usize, naming: Naming) -> bool {
+        match self.tokens.get(index + 1).map(|t| t.tag) {
+            _ if naming == Naming::Required => true,
+            Some(Tag::OpMultiplication | Tag::LParen | Tag::LBracket) => true,
+            Some(Tag::Identifier) => naming == Naming::Optional && self.typedef_name(index + 1).is_none(),
+            _ => false
+        }
+    }
+
+    // Parses a type name, as in casts, e.g. `unsigned long` or `struct s *`
+    fn parse_type_name(&mut self, index: usize) -> Result<(usize, Type), ASTError> {
+        let token = self.peek(index)?.clone();
+        let (index, specifiers) = self.parse_specifiers(index)?;
+        if specifiers.storage_class.is_some() || specifiers.is_typedef || specifiers.is_inline {
+            syntax_error!(INVALID_SPECIFIERS at token.range.clone(), "Unexpected storage class or `inline` in type name");
+        }
+        if !specifiers.definitions.is_empty() {
+            syntax_error!(INVALID_SPECIFIERS at token.range.clone(), "Type definitions are not allowed in type names");
+        }
+
+        let (end, declarator) = self.parse_abstract_declarator(index)?;
+        let (_, ty, _) = Self::process_declarator(declarator, specifiers.var_type, self.span(index, end))?;
+        let index = end;
+        return Ok((index, ty));
+    }
+
+    // Applies the derivations of a declarator to the type of its specifiers,
+    // returning the declared name, its type and the names of its parameters, with
+    // where they are declared. Errors point at `span`, the whole declarator
+    fn process_declarator(declarator: Declarator, base: Type, span: Span) -> Result<(String, Type, ParameterNames), ASTError> {
+        match declarator {
+            Declarator::Identifier(name, _) => {
+                Self::check_restrict(&base, span)?;
+                Ok((name, base, vec![]))
+            },
+            Declarator::Abstract => {
+                Self::check_restrict(&base, span)?;
+                Ok((String::new(), base, vec![]))
+            },
+            Declarator::Pointer(qualifiers, inner) => Self::process_declarator(*inner, Type::Pointer(Box::new(base)).qualified(qualifiers), span),
+            Declarator::Array(inner, size) => {
+                if !base.is_scalar() && !matches!(base, Type::Array(_, _) | Type::DeferredArray(_, _) | Type::Structure(_, _)) {
+                    syntax_error!(INVALID_DECLARATOR at span, "Array has invalid element type `{}`", base);
+                }
+                if let Type::Array(_, 0) = base {
+                    syntax_error!(INVALID_DECLARATOR at span, "Array has incomplete element type");
+                }
+
+                // A missing size is marked with a size of 0, and taken from the initializer or a
+                // later declaration, or the array is adjusted to a pointer if it is a parameter.
+                // Sizes the parser can't evaluate, such as `sizeof x`, are left to the resolver
+                let size = match size.map(|e| (consteval::evaluate(&e), e)) {
+                    None => 0,
+                    Some((Some((size, ty)), _)) if ty.is_integer() && size > 0 => size as usize,
+                    Some((None, e)) => return Self::process_declarator(*inner, Type::DeferredArray(Box::new(base), Box::new(e)), span),
+                    Some((_, e)) => {
+                        syntax_error!(INVALID_CONSTANT at e.span, "Array size must be a positive integer constant");
+                    }
+                };
+                Self::process_declarator(*inner, Type::Array(Box::new(base), size), span)
+            },
+            Declarator::Function(params, variadic, inner) => {
+                let mut names = vec![];
+                let mut types = vec![];
+                for (param_type, param) in params {
+                    // Unnamed parameters have no span of their own
+                    let param_span = match param.span() {
+                        param_span if param_span == Span::default() => span,
+                        param_span => param_span
+                    };
+                    let (param_name, param_type, _) = Self::process_declarator(param, param_type, param_span)?;
+                    if param_type == Type::Void {
+                        syntax_error!(INVALID_DECLARATOR at param_span, "Parameter `{}` declared void", param_name);
+                    }
+
+                    // Array parameters are adjusted to pointers to their element type,
+                    // and function parameters to pointers to the function
+                    let param_type = match param_type {
+                        Type::Array(element, _) | Type::DeferredArray(element, _) => Type::Pointer(element),
+                        ty @ Type::Function(_) => Type::Pointer(Box::new(ty)),
+                        ty => ty
+                    };
+
+                    names.push((param_name, param_span));
+                    types.push(param_type);
+                }
+
+                match base {
+                    Type::Array(_, _) | Type::DeferredArray(_, _) => {
+                        syntax_error!(INVALID_DECLARATOR at span, "Function cannot return an array type `{}`", base);
+                    },
+                    Type::Function(_) => {
+                        syntax_error!(INVALID_DECLARATOR at span, "Function cannot return a function type `{}`", base);
+                    },
+                    _ => {}
+                }
+
+                // The parameter names are those of the function being declared, not
+                // of any function type it is derived from, as in `int (*f(int x))(long)`.
+                // Functions return values, which have no qualifiers
+                let ret = Box::new(base.unqualified().clone());
+                let function_type = Type::Function(FunctionType { params: types, ret, variadic });
+                match *inner {
+                    Declarator::Identifier(name, _) => Ok((name, function_type, names)),
+                    inner => Self::process_declarator(inner, function_type, span)
                 }
             }
+        }
+    }
 
-            index += 1;
+    // Only pointers to objects may be `restrict`, wherever they appear in a type
+    fn check_restrict(ty: &Type, span: Span) -> Result<(), ASTError> {
+        match ty {
+            Type::Qualified(qualifiers, inner) => {
+                let is_object_pointer = matches!(inner.pointee(), Some(pointee) if !matches!(pointee, Type::Function(_)));
+                if qualifiers.restrict && !is_object_pointer {
+                    syntax_error!(INVALID_SPECIFIERS at span, "Restrict requires a pointer to an object type, but got `{}`", inner);
+                }
+                Self::check_restrict(inner, span)
+            },
+            Type::Pointer(inner) | Type::Array(inner, _) | Type::DeferredArray(inner, _) => Self::check_restrict(inner, span),
+            Type::Function(f) => {
+                for ty in f.params.iter().chain([&*f.ret]) {
+                    Self::check_restrict(ty, span)?;
+                }
+                Ok(())
+            },
+            _ => Ok(())
         }
     }
 
-    fn parse_expression(&mut self, index: usize) -> Result<(usize, Expression), ASTError> {
-        let mut index = index;
+    fn parse_initializer(&mut self, index: usize) -> Result<(usize, Initializer), ASTError> {
+        if self.peek(index)?.tag != Tag::LBrace {
+            let (index, expression) = self.parse_expression(index)?;
+            return Ok((index, Initializer::Single(expression)));
+        }
 
+        let start = index;
+        let mut index = index + 1;
+        let mut initializers = vec![];
         loop {
-            if index >= self.tokens.len() {
-                syntax_error!("Unexpected end of file while parsing expression");
+            let (new_index, designators) = self.parse_designation(index)?;
+            let (new_index, initializer) = self.parse_initializer(new_index)?;
+            match designators.is_empty() {
+                true => initializers.push(initializer),
+                false => initializers.push(Initializer::Designated(designators, Box::new(initializer)))
             }
-            
-            let token = &self.tokens[index];
 
+            // A trailing comma is allowed before the closing brace
+            let token = self.peek(new_index)?;
             match token.tag {
-                Tag::NumberLiteral => {
-                    let value = &self.buffer[token.range.clone()];
-                    break Ok((index, Expression::Constant(ConstantValue::Int(value.to_string()))));
+                Tag::Comma if self.peek(new_index + 1)?.tag == Tag::RBrace => {
+                    break Ok((new_index + 2, Initializer::Compound(initializers, self.span(start, new_index + 2))));
                 },
+                Tag::Comma => index = new_index + 1,
+                Tag::RBrace => break Ok((new_index + 1, Initializer::Compound(initializers, self.span(start, new_index + 1)))),
+                _ => {
+                    syntax_error!(UNEXPECTED_TOKEN at token.range.clone(), "Unexpected token `{}` in initializer list", self.text(token));
+                }
+            }
+        }
+    }
 
-                // Unary operators and their sub expressions
-                Tag::OpNegation | Tag::OpComplement => {
-                    let unary_type = match token.tag {
-                        Tag::OpNegation => UnaryExpressionType::Negation,
-                        Tag::OpComplement => UnaryExpressionType::Complement,
-                        _ => panic!("Internal parser error -- unary type undefined"),
-                    };
+    // Parses the designators before an element of an initializer list and the `=`
+    // after them, e.g. `[1].x =`, if there are any. The index of an array designator
+    // is a constant expression, which the resolver evaluates
+    fn parse_designation(&mut self, index: usize) -> Result<(usize, Vec<Designator>), ASTError> {
+        let mut index = index;
+        let mut designators = vec![];
+        loop {
+            let token = self.peek(index)?;
+            match token.tag {
+                Tag::OpDot => {
+                    let member = self.peek(index + 1)?;
+                    if member.tag != Tag::Identifier {
+                        syntax_error!(UNEXPECTED_TOKEN at member.range.clone(), "Expected member name in designator, found `{}`", self.text(member));
+                    }
+                    designators.push(Designator::Member(self.text(member), self.span(index, index + 2)));
+                    index += 2;
+                },
+                Tag::LBracket => {
+                    let (new_index, subscript) = self.parse_expression(index + 1)?;
+                    index = self.expect(new_index, Tag::RBracket, "to close array designator")?;
+                    designators.push(Designator::Index(subscript));
+                },
+                _ => break
+            }
+        }
 
-                    index += 1;
-                    let (new_index, subexpression) = self.parse_expression(index)?;
+        if !designators.is_empty() {
+            index = self.expect(index, Tag::OpAssign, "after designator")?;
+        }
+        return Ok((index, designators));
+    }
+
+    // Returns the parameters, and whether the function is variadic, i.e. they
+    // end with `...`, which must follow at least one named parameter
+    fn parse_parameters(&mut self, index: usize) -> Result<(usize, Parameters, bool), ASTError> {
+        let mut index = self.expect(index, Tag::LParen, "to start argument list")?;
+        let mut params = vec![];
+
+        // `(void)` declares a function without parameters, and `()` one without a prototype,
+        // which takes any arguments (see `FunctionType::has_prototype`)
+        if self.peek(index)?.tag == Tag::KVoid && self.peek(index + 1)?.tag == Tag::RParen {
+            return Ok((index + 2, params, false));
+        }
+        if self.peek(index)?.tag == Tag::RParen {
+            return Ok((index + 1, params, true));
+        }
+
+        loop {
+            if self.peek(index)?.tag == Tag::Ellipsis {
+                if params.is_empty() {
+                    syntax_error!(INVALID_DECLARATOR at self.peek(index)?.range.clone(), "`...` must follow a named parameter");
+                }
+                let index = self.expect(index + 1, Tag::RParen, "after `...`")?;
+                break Ok((index, params, true));
+            }
+
+            let token = self.peek(index)?.clone();
+            if !self.is_type_name_start(index) {
+                syntax_error!(UNEXPECTED_TOKEN at token.range.clone(), "Unexpected token `{}` in argument list", self.text(&token));
+            }
+            let (new_index, specifiers) = self.parse_specifiers(index)?;
+            if specifiers.storage_class.is_some() || specifiers.is_typedef || specifiers.is_inline {
+                syntax_error!(INVALID_SPECIFIERS at token.range.clone(), "Unexpected storage class or `inline` in argument list");
+            }
+            if !specifiers.definitions.is_empty() {
+                syntax_error!(INVALID_SPECIFIERS at token.range.clone(), "Type definitions are not allowed in argument lists");
+            }
+
+            let (new_index, declarator) = self.parse_any_declarator(new_index, Naming::Optional)?;
+            params.push((specifiers.var_type, declarator));
+            index = new_index;
+
+            let token = self.peek(index)?;
+            match token.tag {
+                Tag::Comma => index += 1,
+                Tag::RParen => break Ok((index + 1, params, false)),
+                _ => {
+                    syntax_error!(UNEXPECTED_TOKEN at token.range.clone(), "Unexpected token `{}` in argument list", self.text(token));
+                }
+            }
+        }
+    }
+
+    fn parse_block(&mut self, index: usize) -> Result<(usize, Vec<Statement>), ASTError> {
+        let mut index = self.expect(index, Tag::LBrace, "to start block")?;
+        let mut statements: Vec<Statement> = vec![];
+        self.scopes.push(Scope::default());
+        let depth = self.scopes.len();
+
+        loop {
+            let token = self.peek(index)?;
+            if token.tag == Tag::RBrace {
+                self.scopes.pop();
+                break Ok((index + 1, statements));
+            }
+
+            let parsed = match self.is_declaration_start(index) {
+                true => self.parse_declaration(index).and_then(|(new_index, declarations)| {
+                    for declaration in &declarations {
+                        if let Declaration::Function(f) = declaration {
+                            if f.has_body {
+                                syntax_error!(INVALID_DECLARATOR at f.span, "Function definition of `{}` is not allowed inside a block", f.name);
+                            }
+                        }
+                    }
+                    let span = self.span(index, new_index);
+                    Ok((new_index, declarations.into_iter().map(|d| Statement::new(StatementKind::Declaration(d), span)).collect()))
+                }),
+                false => self.parse_statement(index).map(|(new_index, statement)| (new_index, vec![statement]))
+            };
+
+            match parsed {
+                Ok((new_index, parsed)) => {
+                    statements.extend(parsed);
                     index = new_index;
-                    return Ok((index, Expression::Unary(unary_type, Box::new(subexpression))));
+                },
+                // Statements after an error are still parsed in this block's scope
+                Err(error) => {
+                    index = self.recover(error, index)?;
+                    self.scopes.truncate(depth);
+                }
+            }
+        }
+    }
+
+    fn parse_statement(&mut self, index: usize) -> Result<(usize, Statement), ASTError> {
+        let start = index;
+        let token = self.peek(index)?;
+
+        let (index, kind) = match token.tag {
+            Tag::KReturn => {
+                let (index, expression) = self.parse_expression(index + 1)?;
+                let index = self.expect(index, Tag::Semicolon, "after expression")?;
+                (index, StatementKind::Return(expression))
+            },
+
+            Tag::LBrace => {
+                let (index, statements) = self.parse_block(index)?;
+                (index, StatementKind::Compound(statements))
+            },
+
+            Tag::Semicolon => (index + 1, StatementKind::Null),
+
+            _ => {
+                let (index, expression) = self.parse_expression(index)?;
+                let index = self.expect(index, Tag::Semicolon, "after expression")?;
+                (index, StatementKind::Expression(expression))
+            }
+        };
+
+        return Ok((index, Statement::new(kind, self.span(start, index))));
+    }
+
+    // Precedence of each binary operator; higher binds tighter
+    fn precedence(tag: Tag) -> Option<u8> {
+        match tag {
+            Tag::OpMultiplication | Tag::OpDivision | Tag::OpRemainder => Some(50),
+            Tag::OpAddition | Tag::OpNegation => Some(45),
+            Tag::OpShiftLeft | Tag::OpShiftRight => Some(40),
+            Tag::OpLess | Tag::OpLessEqual | Tag::OpGreater | Tag::OpGreaterEqual => Some(35),
+            Tag::OpEqual | Tag::OpNotEqual => Some(30),
+            Tag::OpBitAnd => Some(25),
+            Tag::OpBitXor => Some(20),
+            Tag::OpBitOr => Some(15),
+            Tag::OpAssign => Some(1),
+            _ => None
+        }
+    }
+
+    fn binary_type(tag: Tag) -> BinaryExpressionType {
+        use BinaryExpressionType as B;
+        match tag {
+            Tag::OpAddition => B::Add,
+            Tag::OpNegation => B::Subtract,
+            Tag::OpMultiplication => B::Multiply,
+            Tag::OpDivision => B::Divide,
+            Tag::OpRemainder => B::Remainder,
+            Tag::OpBitAnd => B::BitAnd,
+            Tag::OpBitOr => B::BitOr,
+            Tag::OpBitXor => B::BitXor,
+            Tag::OpShiftLeft => B::ShiftLeft,
+            Tag::OpShiftRight => B::ShiftRight,
+            Tag::OpEqual => B::Equal,
+            Tag::OpNotEqual => B::NotEqual,
+            Tag::OpLess => B::Less,
+            Tag::OpLessEqual => B::LessEqual,
+            Tag::OpGreater => B::Greater,
+            Tag::OpGreaterEqual => B::GreaterEqual,
+            _ => panic!("Internal parser error -- binary type undefined")
+        }
+    }
+
+    fn parse_expression(&mut self, index: usize) -> Result<(usize, Expression), ASTError> {
+        self.parse_binary(index, 0)
+    }
+
+    // Precedence climbing: parses a chain of binary operators binding
+    // at least as tightly as `min_precedence`
+    fn parse_binary(&mut self, index: usize, min_precedence: u8) -> Result<(usize, Expression), ASTError> {
+        let (mut index, mut left) = self.parse_unary(index)?;
+
+        while let Some(token) = self.tokens.get(index) {
+            let tag = token.tag;
+            let precedence = match Self::precedence(tag) {
+                Some(p) if p >= min_precedence => p,
+                _ => break
+            };
+
+            // Assignments are right-associative: `a = b = 2` is `a = (b = 2)`
+            if tag == Tag::OpAssign {
+                let (new_index, right) = self.parse_binary(index + 1, precedence)?;
+                let span = left.span.to(right.span);
+                left = Expression::new(ExpressionKind::Assignment(Box::new(left), Box::new(right)), span);
+                index = new_index;
+                continue;
+            }
+
+            let (new_index, right) = self.parse_binary(index + 1, precedence + 1)?;
+            let span = left.span.to(right.span);
+            left = Expression::new(ExpressionKind::Binary(Self::binary_type(tag), Box::new(left), Box::new(right)), span);
+            index = new_index;
+        }
+
+        return Ok((index, left));
+    }
+
+    fn parse_unary(&mut self, index: usize) -> Result<(usize, Expression), ASTError> {
+        let start = index;
+        let token = self.peek(index)?;
+
+        match token.tag {
+            // Unary operators and their sub expressions
+            Tag::OpBitAnd => {
+                let (index, subexpression) = self.parse_unary(index + 1)?;
+                return Ok((index, Expression::new(ExpressionKind::AddressOf(Box::new(subexpression)), self.span(start, index))));
+            },
+            Tag::OpMultiplication => {
+                let (index, subexpression) = self.parse_unary(index + 1)?;
+                return Ok((index, Expression::new(ExpressionKind::Dereference(Box::new(subexpression)), self.span(start, index))));
+            },
+
+            Tag::OpNegation | Tag::OpComplement => {
+                let unary_type = match token.tag {
+                    Tag::OpNegation => UnaryExpressionType::Negation,
+                    Tag::OpComplement => UnaryExpressionType::Complement,
+                    _ => panic!("Internal parser error -- unary type undefined"),
+                };
+
+                let (index, subexpression) = self.parse_unary(index + 1)?;
+                return Ok((index, Expression::new(ExpressionKind::Unary(unary_type, Box::new(subexpression)), self.span(start, index))));
+            },
+
+            // `sizeof` applies to a parenthesized type name, or to a unary expression
+            // as in `sizeof x` or `sizeof (x)`, while `_Alignof` only applies to type names
+            Tag::KSizeof if self.peek(index + 1)?.tag == Tag::LParen && self.is_type_name_start(index + 2) => {
+                let (index, ty) = self.parse_type_name(index + 2)?;
+                let index = self.expect(index, Tag::RParen, "to close `sizeof`")?;
+                // Unless the type name starts a compound literal, as in `sizeof (int[]){1, 2}`
+                if self.peek(index)?.tag == Tag::LBrace {
+                    let (index, subexpression) = self.parse_compound_literal(start + 1, index, ty)?;
+                    return Ok((index, Expression::new(ExpressionKind::SizeOf(Box::new(subexpression)), self.span(start, index))));
+                }
+                Self::check_array_size(&ty, "sizeof", self.span(start, index))?;
+                return Ok((index, Self::fold_layout(ExpressionKind::SizeOfType(ty), self.span(start, index))));
+            },
+            Tag::KSizeof => {
+                let (index, subexpression) = self.parse_unary(index + 1)?;
+                return Ok((index, Expression::new(ExpressionKind::SizeOf(Box::new(subexpression)), self.span(start, index))));
+            },
+            Tag::KAlignof => {
+                let index = self.expect(index + 1, Tag::LParen, "after `_Alignof`")?;
+                let (index, ty) = self.parse_type_name(index)?;
+                let index = self.expect(index, Tag::RParen, "to close `_Alignof`")?;
+                Self::check_array_size(&ty, "_Alignof", self.span(start, index))?;
+                return Ok((index, Self::fold_layout(ExpressionKind::AlignOf(ty), self.span(start, index))));
+            },
+
+            // A parenthesized type name starts a cast, e.g. `(T)(x)` when `T` is a
+            // typedef name, or a compound literal if followed by an initializer list,
+            // and a parenthesized expression otherwise
+            Tag::LParen if self.is_type_name_start(index + 1) => {
+                let (index, ty) = self.parse_type_name(index + 1)?;
+                let index = self.expect(index, Tag::RParen, "to close cast")?;
+                if self.peek(index)?.tag == Tag::LBrace {
+                    return self.parse_compound_literal(start, index, ty);
+                }
+                let (index, subexpression) = self.parse_unary(index)?;
+                return Ok((index, Expression::new(ExpressionKind::Cast(ty, Box::new(subexpression)), self.span(start, index))));
+            },
+
+            _ => self.parse_postfix(index)
+        }
+    }
+
+    // Arrays without a size are incomplete, so they have no layout
+    fn check_array_size(ty: &Type, operator: &str, span: Span) -> Result<(), ASTError> {
+        if let Type::Array(_, 0) = ty {
+            syntax_error!(INVALID_DECLARATOR at span, "Array size missing in type name of `{}`", operator);
+        }
+        return Ok(());
+    }
+
+    // The size and alignment of scalars (and arrays of them) don't depend on the layout
+    // of any structure, so they are replaced by their value right away, which makes
+    // them usable in the constant expressions the parser evaluates, as in enumerators
+    fn fold_layout(kind: ExpressionKind, span: Span) -> Expression {
+        let value = match &kind {
+            // Arrays too large to have a size are reported by the resolver
+            ExpressionKind::SizeOfType(ty) if Self::has_scalar_layout(ty) => match ty.checked_size() {
+                Some(size) => size,
+                None => return Expression::new(kind, span)
+            },
+            ExpressionKind::AlignOf(ty) if Self::has_scalar_layout(ty) => ty.alignment(),
+            _ => return Expression::new(kind, span)
+        };
+        return Expression::new(ExpressionKind::Constant(ConstantValue::ULong(value as u64)), span);
+    }
+
+    fn has_scalar_layout(ty: &Type) -> bool {
+        match ty {
+            Type::Array(element, _) => Self::has_scalar_layout(element),
+            ty => ty.is_scalar()
+        }
+    }
+
+    // The initializer list at `index` of a compound literal of type `ty`, starting with the
+    // parenthesis at `start`, which is a postfix expression, as in `(struct point){1, 2}.x`
+    fn parse_compound_literal(&mut self, start: usize, index: usize, ty: Type) -> Result<(usize, Expression), ASTError> {
+        let (index, initializer) = self.parse_initializer(index)?;
+        let span = self.span(start, index);
+        let literal = VariableDeclaration {
+            name: String::new(),
+            var_type: ty,
+            initializer: Some(initializer),
+            storage_class: None,
+            span
+        };
+
+        return self.parse_postfix_operators(index, Expression::new(ExpressionKind::CompoundLiteral(Box::new(literal)), span));
+    }
+
+    // Subscripts, calls and member accesses apply to the primary
+    // expression before them, e.g. `a[1].b->c`, or `f(1)(2)`
+    fn parse_postfix(&mut self, index: usize) -> Result<(usize, Expression), ASTError> {
+        let (index, expression) = self.parse_primary(index)?;
+        return self.parse_postfix_operators(index, expression);
+    }
+
+    fn parse_postfix_operators(&mut self, index: usize, expression: Expression) -> Result<(usize, Expression), ASTError> {
+        let mut index = index;
+        let mut expression = expression;
+        while let Some(token) = self.tokens.get(index) {
+            match token.tag {
+                Tag::LBracket => {
+                    let (new_index, subscript) = self.parse_expression(index + 1)?;
+                    index = self.expect(new_index, Tag::RBracket, "to close subscript")?;
+                    let span = expression.span.to(self.span(index - 1, index));
+                    expression = Expression::new(ExpressionKind::Subscript(Box::new(expression), Box::new(subscript)), span);
                 },
 
                 Tag::LParen => {
-                    index += 1;
-                    let (shift, expression) = self.parse_expression(index)?;
-                    index = shift + 1;
+                    let (new_index, arguments) = self.parse_arguments(index)?;
+                    index = new_index;
+                    let span = expression.span.to(self.span(index - 1, index));
+                    expression = Expression::new(ExpressionKind::FunctionCall(Box::new(expression), arguments), span);
+                },
+
+                Tag::OpDot | Tag::OpArrow => {
+                    let arrow = token.tag == Tag::OpArrow;
+                    let member = self.peek(index + 1)?;
+                    if member.tag != Tag::Identifier {
+                        syntax_error!(UNEXPECTED_TOKEN at member.range.clone(), "Expected member name, found `{}`", self.text(member));
+                    }
+
+                    let name = self.text(member);
+                    let span = expression.span.to(member.range.clone().into());
+                    expression = match arrow {
+                        true => Expression::new(ExpressionKind::Arrow(Box::new(expression), name), span),
+                        false => Expression::new(ExpressionKind::Dot(Box::new(expression), name), span)
+                    };
+                    index += 2;
+                },
 
-                    if self.tokens[index].tag != Tag::RParen {
-                        syntax_error!("Unclosed parenthesis at {:?}", self.tokens[index].tag);
+                _ => break
+            }
+        }
+
+        return Ok((index, expression));
+    }
+
+    fn parse_primary(&mut self, index: usize) -> Result<(usize, Expression), ASTError> {
+        let start = index;
+        let token = self.peek(index)?;
+
+        match token.tag {
+            Tag::NumberLiteral => {
+                let value = self.text(token);
+                match ConstantValue::from_literal(&value) {
+                    Ok(constant) => return Ok((index + 1, Expression::new(ExpressionKind::Constant(constant), token.range.clone().into()))),
+                    Err(LiteralError::Invalid) => {
+                        syntax_error!(INVALID_LITERAL at token.range.clone(), "Invalid number literal `{}`", value);
+                    },
+                    // Decimal literals are only unsigned when suffixed with `u`
+                    Err(LiteralError::OutOfRange) => {
+                        let mut diagnostic = Diagnostic::error(format!("Number literal `{}` is too large to be represented in any of its types", value))
+                            .with_code(diagnostics::LITERAL_OUT_OF_RANGE)
+                            .with_label(token.range.clone().into(), "");
+                        if ConstantValue::from_literal(&format!("{}u", value)).is_ok() {
+                            let end = Span { start: token.range.end, end: token.range.end };
+                            diagnostic = diagnostic.with_fixit(end, "u", "add a `u` suffix to make it unsigned");
+                        }
+                        return Err(ASTError::SyntaxError(diagnostic));
                     }
+                }
+            },
+
+            // Adjacent string literals are concatenated, as in `"ab" "cd"`
+            Tag::StringLiteral => {
+                let mut index = index;
+                let mut bytes = vec![];
+                while let Some(token) = self.tokens.get(index).filter(|t| t.tag == Tag::StringLiteral) {
+                    bytes.extend(self.parse_string(token)?);
+                    index += 1;
+                }
+
+                return Ok((index, Expression::new(ExpressionKind::String(bytes), self.span(start, index))));
+            },
+
+            // The builtins of <stdarg.h>. `va_end` does nothing, and `va_copy` copies the
+            // structure a `va_list` is an array of, so both are rewritten as casts to void.
+            // The last parameter given to `va_start` is only there for compatibility
+            Tag::KBuiltinVaStart => {
+                let (index, mut arguments) = self.parse_builtin_arguments(index, 2)?;
+                let ap = arguments.remove(0);
+                return Ok((index, Expression::new(ExpressionKind::VaStart(Box::new(ap)), self.span(start, index))));
+            },
+            Tag::KBuiltinVaArg => {
+                let index = self.expect(index + 1, Tag::LParen, "after `__builtin_va_arg`")?;
+                let (index, ap) = self.parse_expression(index)?;
+                let index = self.expect(index, Tag::Comma, "after `va_list` argument")?;
+                let (index, ty) = self.parse_type_name(index)?;
+                let index = self.expect(index, Tag::RParen, "to close `__builtin_va_arg`")?;
+                return Ok((index, Expression::new(ExpressionKind::VaArg(Box::new(ap), ty), self.span(start, index))));
+            },
+            Tag::KBuiltinVaEnd => {
+                let (index, mut arguments) = self.parse_builtin_arguments(index, 1)?;
+                let ap = arguments.remove(0);
+                return Ok((index, Expression::new(ExpressionKind::Cast(Type::Void, Box::new(ap)), self.span(start, index))));
+            },
+            Tag::KBuiltinVaCopy => {
+                let (index, mut arguments) = self.parse_builtin_arguments(index, 2)?;
+                let (destination, source) = (arguments.remove(0), arguments.remove(0));
+                let span = self.span(start, index);
+                let copy = ExpressionKind::Assignment(
+                    Box::new(Expression::new(ExpressionKind::Dereference(Box::new(destination)), span)),
+                    Box::new(Expression::new(ExpressionKind::Dereference(Box::new(source)), span))
+                );
+                return Ok((index, Expression::new(ExpressionKind::Cast(Type::Void, Box::new(Expression::new(copy, span))), span)));
+            },
+
+            Tag::Identifier => {
+                let name = self.text(token);
+                let index = index + 1;
+
+                let span = token.range.clone().into();
+                if let Some(Binding::Enumerator(value)) = self.lookup(&name) {
+                    return Ok((index, Expression::new(ExpressionKind::Constant(value.clone()), span)));
+                }
+                return Ok((index, Expression::new(ExpressionKind::Variable(name), span)));
+            },
+
+            Tag::LParen => {
+                let (index, expression) = self.parse_expression(index + 1)?;
+
+                let token = self.peek(index)?;
+                if token.tag != Tag::RParen {
+                    syntax_error!(UNEXPECTED_TOKEN at token.range.clone(), "Unclosed parenthesis");
+                }
+
+                return Ok((index + 1, expression));
+            },
 
-                    return Ok((index, expression));
+            _ => {
+                syntax_error!(UNEXPECTED_TOKEN at token.range.clone(), "Unexpected token `{}` in expression", self.text(token));
+            }
+        }
+    }
+
+    // Decodes the escape sequences of a string literal, e.g. `\n`, `\x41` or `\101`
+    fn parse_string(&self, token: &Token) -> Result<Vec<u8>, ASTError> {
+        let text = self.text(token);
+        let content = &text[1..text.len() - 1];
+
+        let mut bytes = vec![];
+        let mut chars = content.chars().peekable();
+        while let Some(c) = chars.next() {
+            if c != '\\' {
+                let mut buffer = [0; 4];
+                bytes.extend(c.encode_utf8(&mut buffer).as_bytes());
+                continue;
+            }
+
+            let escape = chars.next().unwrap_or_default();
+            let byte = match escape {
+                'n' => b'\n',
+                't' => b'\t',
+                'r' => b'\r',
+                'a' => 0x07,
+                'b' => 0x08,
+                'f' => 0x0c,
+                'v' => 0x0b,
+                '\\' | '\'' | '"' | '?' => escape as u8,
+                // Octal escapes have up to three digits, and hexadecimal ones any number of them
+                '0'..='7' => {
+                    let mut value = escape.to_digit(8).unwrap_or_default();
+                    for _ in 0..2 {
+                        match chars.peek().and_then(|c| c.to_digit(8)) {
+                            Some(digit) => { value = value * 8 + digit; chars.next(); },
+                            None => break
+                        }
+                    }
+                    if value > 0xff {
+                        syntax_error!(INVALID_LITERAL at token.range.clone(), "Octal escape sequence out of range in string literal");
+                    }
+                    value as u8
+                },
+                'x' => {
+                    let mut value: Option<u32> = None;
+                    while let Some(digit) = chars.peek().and_then(|c| c.to_digit(16)) {
+                        value = Some(value.unwrap_or_default().saturating_mul(16).saturating_add(digit));
+                        chars.next();
+                    }
+                    match value {
+                        Some(value) if value <= 0xff => value as u8,
+                        Some(_) => {
+                            syntax_error!(INVALID_LITERAL at token.range.clone(), "Hex escape sequence out of range in string literal");
+                        },
+                        None => {
+                            syntax_error!(INVALID_LITERAL at token.range.clone(), "\\x used with no following hex digits in string literal");
+                        }
+                    }
+                },
+                _ => {
+                    syntax_error!(INVALID_LITERAL at token.range.clone(), "Unknown escape sequence `\\{}` in string literal", escape);
                 }
+            };
+            bytes.push(byte);
+        }
+
+        return Ok(bytes);
+    }
+
+    // Arguments of the builtin at `index`, which takes a fixed number of them
+    fn parse_builtin_arguments(&mut self, index: usize, count: usize) -> Result<(usize, Vec<Expression>), ASTError> {
+        let start = index;
+        let token = self.peek(index)?.clone();
+        let (index, arguments) = self.parse_arguments(index + 1)?;
+        if arguments.len() != count {
+            syntax_error!(ARGUMENT_COUNT at self.span(start, index), "`{}` expects {} arguments, but {} were given", self.text(&token), count, arguments.len());
+        }
+
+        return Ok((index, arguments));
+    }
 
+    fn parse_arguments(&mut self, index: usize) -> Result<(usize, Vec<Expression>), ASTError> {
+        let mut index = self.expect(index, Tag::LParen, "to start argument list")?;
+        let mut arguments: Vec<Expression> = vec![];
+
+        if self.peek(index)?.tag == Tag::RParen {
+            return Ok((index + 1, arguments));
+        }
+
+        loop {
+            let (new_index, argument) = self.parse_expression(index)?;
+            arguments.push(argument);
+
+            let token = self.peek(new_index)?;
+            match token.tag {
+                Tag::Comma => index = new_index + 1,
+                Tag::RParen => break Ok((new_index + 1, arguments)),
                 _ => {
-                    syntax_error!("Unexpected token `{:?}` in expression", token.tag);
+                    syntax_error!(UNEXPECTED_TOKEN at token.range.clone(), "Unexpected token `{}` in argument list", self.text(token));
                 }
             }
         }
@@ -0,0 +1,1366 @@
+// Semantic analysis: identifier resolution //
+// Resolves every identifier to the declaration it refers to, renaming
+// objects without linkage to a unique name, and determines the linkage
+// and storage duration of every declaration.
+use std::collections::{HashMap, HashSet};
+use super::symbols::*;
+use crate::diagnostics::{self, Diagnostic, warnings::Warning};
+use super::parser::ASTError;
+use super::table::*;
+use super::{consteval, initializer, typecheck};
+
+
+struct ScopeEntry {
+    unique_name: String,
+    has_linkage: bool,
+    // Enumerators are constants, which don't occupy any storage
+    constant: Option<ConstantValue>,
+    // Where the identifier was declared, when known, for errors about redeclaring it
+    span: Option<Span>,
+    used: bool,
+    // What to warn about if the identifier is never used, for locals and parameters
+    unused: Option<Warning>
+}
+
+// Structure tags live in their own namespace, but are scoped like identifiers
+struct TagEntry {
+    unique_tag: String,
+    kind: StructKind
+}
+
+// Returns the resolved program and its symbols, along with the warnings found in it
+pub fn resolve(program: Program) -> Result<(Program, SymbolTable, Vec<Diagnostic>), ASTError> {
+    let mut resolver = Resolver::new();
+    let mut resolved = Program::new();
+
+    for dec in program {
+        let dec = match dec {
+            Declaration::Function(def) => {
+                let span = def.span;
+                Declaration::Function(resolver.resolve_function(def).map_err(|e| e.within(span))?)
+            },
+            Declaration::Variable(var) => {
+                let span = var.span;
+                Declaration::Variable(resolver.resolve_file_variable(var).map_err(|e| e.within(span))?)
+            },
+            Declaration::Struct(dec) => {
+                let span = dec.span;
+                Declaration::Struct(resolver.resolve_struct_declaration(dec).map_err(|e| e.within(span))?)
+            },
+            Declaration::Enum(dec) => {
+                let span = dec.span;
+                Declaration::Enum(resolver.resolve_enum_declaration(dec).map_err(|e| e.within(span))?)
+            },
+            Declaration::StaticAssert(assertion) => {
+                let span = assertion.span;
+                Declaration::StaticAssert(resolver.resolve_static_assertion(assertion).map_err(|e| e.within(span))?)
+            }
+        };
+        resolved.push(dec);
+    }
+    resolver.complete_tentative_arrays();
+
+    return Ok((resolved, resolver.symbols, resolver.warnings));
+}
+
+
+struct Resolver {
+    // The first scope is the file scope, every other one is a block scope
+    scopes: Vec<HashMap<String, ScopeEntry>>,
+    tag_scopes: Vec<HashMap<String, TagEntry>>,
+    symbols: SymbolTable,
+    counter: usize,
+    // Declarations of the number of elements of variable-length arrays, which
+    // are evaluated before the statement their array type appears in
+    counts: Vec<VariableDeclaration>,
+    // The function whose inline definition is being resolved, which may
+    // neither define modifiable static objects nor refer to internal ones
+    inline_definition: Option<String>,
+    // Functions declared implicitly by calling them, as `int f()`, until they are declared
+    implicit_functions: HashSet<String>,
+    warnings: Vec<Diagnostic>
+}
+
+impl Resolver {
+    fn new() -> Self {
+        let mut resolver = Self {
+            scopes: vec![HashMap::new()],
+            tag_scopes: vec![HashMap::new()],
+            symbols: SymbolTable::new(),
+            counter: 0,
+            counts: vec![],
+            inline_definition: None,
+            implicit_functions: HashSet::new(),
+            warnings: vec![]
+        };
+
+        // The structure `va_list` is an array of (see `Type::va_list`), as laid out by the ABI
+        let member = |name: &str, ty: Type, offset: usize| Member { name: name.to_string(), ty, offset, bit_field: None };
+        resolver.symbols.define_struct(VA_LIST_TAG.to_string(), StructDefinition {
+            size: 24,
+            alignment: 8,
+            members: vec![
+                member("gp_offset", Type::UInt, 0),
+                member("fp_offset", Type::UInt, 4),
+                member("overflow_arg_area", Type::Pointer(Box::new(Type::Void)), 8),
+                member("reg_save_area", Type::Pointer(Box::new(Type::Void)), 16)
+            ]
+        });
+        resolver.tag_scopes[0].insert(VA_LIST_TAG.to_string(), TagEntry { unique_tag: VA_LIST_TAG.to_string(), kind: StructKind::Struct });
+
+        return resolver;
+    }
+
+    fn make_unique(&mut self, name: &str) -> String {
+        let unique = format!("{}.{}", name, self.counter);
+        self.counter += 1;
+        return unique;
+    }
+
+    fn at_file_scope(&self) -> bool {
+        self.scopes.len() == 1
+    }
+
+    // Objects declared in a block hide any declaration of the same name in enclosing scopes
+    fn declare(&mut self, name: String, entry: ScopeEntry) {
+        if let (false, None, Some(span)) = (entry.has_linkage, &entry.constant, entry.span) {
+            let outer = &self.scopes[..self.scopes.len() - 1];
+            if let Some(shadowed) = outer.iter().rev().find_map(|scope| scope.get(&name)) {
+                let mut warning = Diagnostic::warning(format!("Declaration of `{}` shadows a previous declaration", name))
+                    .with_warning(Warning::Shadow)
+                    .with_label(span, "");
+                if let Some(previous) = shadowed.span {
+                    warning = warning.with_secondary_label(previous, "previous declaration is here");
+                }
+                self.warnings.push(warning);
+            }
+        }
+        self.scopes.last_mut().expect("Resolver has no scope").insert(name, entry);
+    }
+
+    fn lookup(&self, name: &str) -> Option<&ScopeEntry> {
+        self.scopes.iter().rev().find_map(|scope| scope.get(name))
+    }
+
+    fn mark_used(&mut self, name: &str) {
+        if let Some(entry) = self.scopes.iter_mut().rev().find_map(|scope| scope.get_mut(name)) {
+            entry.used = true;
+        }
+    }
+
+    // An error about redeclaring `name` at `span`, which also points at its declaration in scope, if any
+    fn redeclaration(&self, code: &'static str, name: &str, span: Span, message: String) -> ASTError {
+        let mut diagnostic = Diagnostic::error(message).with_code(code).with_label(span, "");
+        if let Some(previous) = self.lookup(name).and_then(|entry| entry.span) {
+            diagnostic = diagnostic.with_secondary_label(previous, "previously declared here");
+        }
+        return ASTError::SemanticError(diagnostic);
+    }
+
+    fn push_scope(&mut self) {
+        self.scopes.push(HashMap::new());
+        self.tag_scopes.push(HashMap::new());
+    }
+
+    // Locals and parameters which were never used are reported when their scope ends
+    fn pop_scope(&mut self) {
+        let scope = self.scopes.pop().expect("Resolver has no scope");
+        self.tag_scopes.pop();
+
+        let mut unused: Vec<_> = scope.into_iter()
+            .filter_map(|(name, entry)| match (entry.used, entry.unused, entry.span) {
+                (false, Some(warning), Some(span)) => Some((name, warning, span)),
+                _ => None
+            })
+            .collect();
+        unused.sort_by_key(|(_, _, span)| span.start);
+        for (name, warning, span) in unused {
+            let message = match warning {
+                Warning::UnusedParameter => format!("Unused parameter `{}`", name),
+                _ => format!("Unused variable `{}`", name)
+            };
+            self.warnings.push(Diagnostic::warning(message).with_warning(warning).with_label(span, ""));
+        }
+    }
+
+    // Calling an undeclared function declares it as `extern int f()`, which takes any
+    // arguments, as before C99. Its declaration (if any) may still give it parameters
+    fn declare_implicit_function(&mut self, name: &str, span: Span) {
+        let message = format!("Call to undeclared function `{}`; ISO C99 and later do not support implicit function declarations", name);
+        self.warnings.push(Diagnostic::warning(message).with_warning(Warning::ImplicitFunctionDeclaration).with_label(span, ""));
+
+        if self.symbols.get(name).is_none() {
+            let ty = Type::Function(FunctionType { params: vec![], ret: Box::new(Type::Int), variadic: true });
+            self.symbols.insert(name.to_string(), Symbol {
+                ty,
+                attributes: SymbolAttributes::Function { defined: false, global: true, inline: false, inline_only: false }
+            });
+            self.implicit_functions.insert(name.to_string());
+        }
+        self.scopes[0].insert(name.to_string(), ScopeEntry { unique_name: name.to_string(), has_linkage: true, constant: None, span: None, used: true, unused: None });
+    }
+
+    // A tentative definition of an array without a size, as in `int a[];`, which no later
+    // declaration completes, defines an array of one element (C11 6.9.2)
+    fn complete_tentative_arrays(&mut self) {
+        let incomplete: Vec<(String, Symbol)> = self.symbols.iter()
+            .filter(|(_, symbol)| matches!(symbol, Symbol { ty: Type::Array(_, 0), attributes: SymbolAttributes::Static { init: InitialValue::Tentative, .. } }))
+            .map(|(name, symbol)| (name.clone(), symbol.clone()))
+            .collect();
+        for (name, symbol) in incomplete {
+            let Type::Array(element, _) = symbol.ty else {
+                panic!("Internal resolver error -- `{}` is not an array", name);
+            };
+            self.symbols.insert(name, Symbol { ty: Type::Array(element, 1), ..symbol });
+        }
+    }
+
+    // Declares the local holding the number of elements of a variable-length array,
+    // initialized with its size expression (see `resolve_block`)
+    fn declare_count(&mut self, size: Expression) -> String {
+        let name = self.make_unique("vla");
+        self.symbols.insert(name.clone(), Symbol { ty: Type::ULong, attributes: SymbolAttributes::Local });
+
+        let span = size.span;
+        let size = Expression::new(ExpressionKind::Cast(Type::ULong, Box::new(size)), span);
+        self.counts.push(VariableDeclaration {
+            name: name.clone(),
+            var_type: Type::ULong,
+            initializer: Some(Initializer::Single(size)),
+            storage_class: None,
+            span
+        });
+        return name;
+    }
+
+    fn declare_tag(&mut self, tag: &str, kind: StructKind) -> String {
+        let unique_tag = self.make_unique(tag);
+        self.tag_scopes.last_mut().expect("Resolver has no scope").insert(tag.to_string(), TagEntry { unique_tag: unique_tag.clone(), kind });
+        return unique_tag;
+    }
+
+    // Renames the structure tags within a type to their unique tag. A tag that is
+    // not in scope declares a new incomplete type, as in `struct s *p;`
+    fn resolve_type(&mut self, ty: Type) -> Result<Type, ASTError> {
+        match ty {
+            Type::Structure(kind, tag) => {
+                let entry = self.tag_scopes.iter().rev().find_map(|scope| scope.get(&tag));
+                let unique_tag = match entry {
+                    Some(entry) if entry.kind != kind => {
+                        semantic_error!(CONFLICTING_TYPES, "Use of `{}` with tag type that does not match previous declaration", Type::Structure(kind, tag));
+                    },
+                    Some(entry) => entry.unique_tag.clone(),
+                    None => self.declare_tag(&tag, kind)
+                };
+                Ok(Type::Structure(kind, unique_tag))
+            },
+            Type::Pointer(inner) => Ok(Type::Pointer(Box::new(self.resolve_type(*inner)?))),
+            Type::Array(element, count) => {
+                let element = self.resolve_type(*element)?;
+                self.check_array_size(Type::Array(Box::new(element), count))
+            },
+            // Sizes the parser couldn't evaluate are constant once their operands are resolved,
+            // unless they make a variable-length array, which can only be declared in a block
+            Type::DeferredArray(element, size) => {
+                let element = self.resolve_type(*element)?;
+                let size = self.resolve_expression(*size)?;
+                match consteval::evaluate(&size) {
+                    Some((size, ty)) if ty.is_integer() && size > 0 => self.check_array_size(Type::Array(Box::new(element), size as usize)),
+                    None if !self.at_file_scope() => {
+                        let ty = typecheck::type_of(size.clone(), &self.symbols)?;
+                        if !ty.is_integer() {
+                            semantic_error!(INVALID_ARRAY_SIZE, "Size of array has non-integer type `{}`", ty);
+                        }
+                        Ok(Type::VariableArray(Box::new(element), self.declare_count(size)))
+                    },
+                    _ => {
+                        semantic_error!(INVALID_CONSTANT at size.span, "Array size must be a positive integer constant");
+                    }
+                }
+            },
+            // Qualifiers of parameters only apply within the body of the function,
+            // so they are not part of its type: `int f(const int)` is `int f(int)`
+            Type::Function(f) => {
+                let mut params = vec![];
+                for p in f.params {
+                    let p = self.resolve_type(p)?;
+                    if p.is_variably_modified() {
+                        semantic_error!(VARIABLY_MODIFIED_TYPE, "Parameters of variably modified type `{}` are not supported", p);
+                    }
+                    params.push(p.unqualified().clone());
+                }
+                let ret = Box::new(self.resolve_type(*f.ret)?);
+                Ok(Type::Function(FunctionType { params, ret, variadic: f.variadic }))
+            },
+            Type::Qualified(qualifiers, inner) => Ok(Type::Qualified(qualifiers, Box::new(self.resolve_type(*inner)?))),
+            ty => Ok(ty)
+        }
+    }
+
+    // Arrays must be small enough for their size in bytes to be counted. The size of
+    // arrays of structures is only known once the structure is complete
+    fn check_array_size(&self, ty: Type) -> Result<Type, ASTError> {
+        if self.symbols.is_complete(&ty) && !ty.is_variable_length() && self.symbols.checked_size_of(&ty).is_none() {
+            semantic_error!(INVALID_ARRAY_SIZE, "Array type `{}` is too large", ty);
+        }
+        return Ok(ty);
+    }
+
+    // Declares a structure tag, and computes the layout of its members if it has any.
+    // Members are laid out in order, each aligned to its own alignment and bit-fields
+    // packed into storage units, while every member of a union starts at offset 0
+    fn resolve_struct_declaration(&mut self, dec: StructDeclaration) -> Result<StructDeclaration, ASTError> {
+        let prior = self.tag_scopes.last().expect("Resolver has no scope").get(&dec.tag);
+        let unique_tag = match prior {
+            Some(entry) if entry.kind != dec.kind => {
+                semantic_error!(CONFLICTING_TYPES, "Use of `{}` with tag type that does not match previous declaration", Type::Structure(dec.kind, dec.tag));
+            },
+            Some(entry) => entry.unique_tag.clone(),
+            None => self.declare_tag(&dec.tag, dec.kind)
+        };
+
+        let Some(members) = dec.members else {
+            return Ok(StructDeclaration { tag: unique_tag, ..dec });
+        };
+        if self.symbols.get_struct(&unique_tag).is_some() {
+            semantic_error!(REDECLARATION at dec.span, "Redefinition of `{}`", Type::Structure(dec.kind, dec.tag));
+        }
+
+        let mut resolved: Vec<MemberDeclaration> = vec![];
+        let mut layout = vec![];
+        // Members are placed bit by bit, as bit-fields need not start or end on a byte boundary
+        let (mut bits, mut size, mut alignment) = (0usize, 0usize, 1usize);
+        for member in members {
+            if !member.name.is_empty() && resolved.iter().any(|m| m.name == member.name) {
+                semantic_error!(REDECLARATION at member.span, "Duplicate member `{}` in `{}`", member.name, Type::Structure(dec.kind, dec.tag));
+            }
+
+            let member_type = self.resolve_type(member.member_type)?;
+            if !self.symbols.is_complete(&member_type) {
+                semantic_error!(INCOMPLETE_TYPE at member.span, "Member `{}` has incomplete type `{}`", member.name, member_type);
+            }
+            if member_type.is_variably_modified() {
+                semantic_error!(VARIABLY_MODIFIED_TYPE at member.span, "Member `{}` has variably modified type `{}`", member.name, member_type);
+            }
+
+            let member_size = self.symbols.size_of(&member_type);
+            let member_alignment = self.symbols.alignment_of(&member_type);
+            let start = match dec.kind {
+                StructKind::Struct => bits,
+                StructKind::Union => 0
+            };
+
+            let Some(width) = member.bit_width else {
+                let offset = start.div_ceil(8).next_multiple_of(member_alignment);
+                bits = bits.max((offset + member_size) * 8);
+                size = size.max(offset + member_size);
+                alignment = alignment.max(member_alignment);
+
+                layout.push(Member { name: member.name.clone(), ty: member_type.clone(), offset, bit_field: None });
+                resolved.push(MemberDeclaration { name: member.name, member_type, bit_width: None, span: member.span });
+                continue;
+            };
+
+            // As in the SysV ABI, a bit-field is stored in the unit of its type it starts in,
+            // unless it would cross into the next one. A zero-width bit-field ends the unit
+            Self::check_bit_field(&member.name, &member_type, width).map_err(|e| e.within(member.span))?;
+            let unit = member_size * 8;
+            let start = if width == 0 || start % unit + width > unit { start.next_multiple_of(unit) } else { start };
+            bits = bits.max(start + width);
+            size = size.max((start + width).div_ceil(8));
+
+            // Unnamed bit-fields only take up space; they are not members
+            if !member.name.is_empty() {
+                alignment = alignment.max(member_alignment);
+                let bit_field = BitField { offset: start % unit, width };
+                layout.push(Member { name: member.name.clone(), ty: member_type.clone(), offset: start / unit * member_size, bit_field: Some(bit_field) });
+            }
+            resolved.push(MemberDeclaration { name: member.name, member_type, bit_width: Some(width), span: member.span });
+        }
+
+        let definition = StructDefinition { size: size.next_multiple_of(alignment), alignment, members: layout };
+        self.symbols.define_struct(unique_tag.clone(), definition);
+
+        return Ok(StructDeclaration { tag: unique_tag, kind: dec.kind, members: Some(resolved), span: dec.span });
+    }
+
+    // Bit-fields have an integer type, and at most as many bits as it (one for `_Bool`)
+    fn check_bit_field(name: &str, ty: &Type, width: usize) -> Result<(), ASTError> {
+        let described = match name {
+            "" => "unnamed bit-field".to_string(),
+            name => format!("bit-field `{}`", name)
+        };
+        if !ty.is_integer() {
+            semantic_error!(INVALID_BIT_FIELD, "The {} has non-integer type `{}`", described, ty);
+        }
+
+        let limit = match ty.unqualified() {
+            Type::Bool => 1,
+            ty => ty.size() * 8
+        };
+        if width > limit {
+            semantic_error!(INVALID_BIT_FIELD, "Width of the {} exceeds its type `{}` of {} bits", described, ty, limit);
+        }
+        if width == 0 && !name.is_empty() {
+            semantic_error!(INVALID_BIT_FIELD, "Named bit-field `{}` has zero width", name);
+        }
+
+        return Ok(());
+    }
+
+    // Enumerators share the namespace of ordinary identifiers, so they
+    // can't be redeclared in the same scope as anything else
+    fn resolve_enum_declaration(&mut self, dec: EnumDeclaration) -> Result<EnumDeclaration, ASTError> {
+        for enumerator in &dec.enumerators {
+            if self.scopes.last().expect("Resolver has no scope").contains_key(&enumerator.name) {
+                let message = format!("Redeclaration of `{}`", enumerator.name);
+                return Err(self.redeclaration(diagnostics::REDECLARATION, &enumerator.name, enumerator.span, message));
+            }
+
+            self.declare(enumerator.name.clone(), ScopeEntry {
+                unique_name: enumerator.name.clone(),
+                has_linkage: false,
+                constant: Some(enumerator.value.clone()),
+                span: Some(enumerator.span),
+                used: false,
+                unused: None
+            });
+        }
+
+        return Ok(dec);
+    }
+
+    fn resolve_static_assertion(&mut self, assertion: StaticAssertion) -> Result<StaticAssertion, ASTError> {
+        let condition = self.resolve_expression(assertion.condition)?;
+        match consteval::evaluate(&condition) {
+            Some((0, ty)) if ty.is_integer() => {
+                semantic_error!(STATIC_ASSERTION_FAILED at assertion.span, "Static assertion failed: \"{}\"", assertion.message);
+            },
+            Some((_, ty)) if ty.is_integer() => Ok(StaticAssertion { condition, ..assertion }),
+            _ => {
+                semantic_error!(INVALID_CONSTANT at condition.span, "Static assertion condition is not an integer constant expression");
+            }
+        }
+    }
+
+    fn resolve_function(&mut self, def: FunctionDefinition) -> Result<FunctionDefinition, ASTError> {
+        let param_qualifiers: Vec<Qualifiers> = match &def.function_type {
+            Type::Function(f) => f.params.iter().map(|p| p.qualifiers()).collect(),
+            _ => panic!("Internal resolver error -- function `{}` without function type", def.name)
+        };
+        let def = FunctionDefinition { function_type: self.resolve_type(def.function_type)?, ..def };
+        let name = def.name.clone();
+        // A definition without a prototype, as in `int f() { ... }`, has no parameters
+        let def = match def.function_type {
+            Type::Function(f) if def.has_body && !f.has_prototype() => {
+                FunctionDefinition { function_type: Type::Function(FunctionType { variadic: false, ..f }), ..def }
+            },
+            _ => def
+        };
+        let is_static = def.storage_class == Some(StorageClass::Static);
+
+        if is_static && !self.at_file_scope() {
+            semantic_error!(INVALID_STORAGE_CLASS, "Function `{}` declared in block scope cannot be static", name);
+        }
+        if def.is_inline && name == "main" {
+            semantic_error!(INVALID_INLINE, "`main` cannot be declared inline");
+        }
+        if let Some(prior) = self.scopes.last().expect("Resolver has no scope").get(&name) {
+            if !prior.has_linkage {
+                return Err(self.redeclaration(diagnostics::REDECLARATION, &name, def.span, format!("`{}` redeclared as a different kind of symbol", name)));
+            }
+        }
+
+        // Functions have internal linkage when declared static, otherwise they take the
+        // linkage of a prior declaration, or external linkage if there is none
+        let mut global = !is_static;
+        let mut defined = def.has_body;
+        // Only file-scope declarations decide whether a definition is an inline definition
+        let mut inline = def.is_inline;
+        let mut inline_only = !self.at_file_scope() || (def.is_inline && def.storage_class != Some(StorageClass::Extern));
+        let mut function_type = def.function_type.clone();
+        match self.symbols.get(&name).cloned() {
+            Some(Symbol { ty, attributes: SymbolAttributes::Function { defined: was_defined, global: was_global, inline: was_inline, inline_only: was_inline_only } }) => {
+                // Functions called before being declared take the type of their declaration, if it returns `int`
+                let implicit = self.implicit_functions.remove(&name) && matches!(&def.function_type, Type::Function(f) if *f.ret == Type::Int);
+                function_type = match Type::composite(&ty, &def.function_type) {
+                    Some(composite) => composite,
+                    None if implicit => def.function_type.clone(),
+                    None => {
+                        return Err(self.redeclaration(diagnostics::CONFLICTING_TYPES, &name, def.span, format!("Conflicting types for function `{}`: `{}`, previously `{}`", name, def.function_type, ty)));
+                    }
+                };
+                if was_defined && def.has_body {
+                    return Err(self.redeclaration(diagnostics::REDECLARATION, &name, def.span, format!("Redefinition of function `{}`", name)));
+                }
+                if was_global && is_static {
+                    return Err(self.redeclaration(diagnostics::REDECLARATION, &name, def.span, format!("Static declaration of `{}` follows non-static declaration", name)));
+                }
+
+                global = was_global;
+                defined |= was_defined;
+                inline |= was_inline;
+                inline_only &= was_inline_only;
+            },
+            Some(_) => {
+                return Err(self.redeclaration(diagnostics::REDECLARATION, &name, def.span, format!("`{}` redeclared as a different kind of symbol", name)));
+            },
+            None => {}
+        }
+
+        self.symbols.insert(name.clone(), Symbol {
+            ty: function_type,
+            attributes: SymbolAttributes::Function { defined, global, inline, inline_only }
+        });
+        self.declare(name.clone(), ScopeEntry { unique_name: name.clone(), has_linkage: true, constant: None, span: Some(def.span), used: false, unused: None });
+
+        if !def.has_body {
+            return Ok(def);
+        }
+
+        // Parameters share their scope with the outermost block of the body
+        self.push_scope();
+        let param_types = match &def.function_type {
+            Type::Function(f) => f.params.clone(),
+            _ => panic!("Internal resolver error -- function `{}` without function type", name)
+        };
+        let mut params = vec![];
+        for (((param, span), ty), qualifiers) in def.params.into_iter().zip(def.param_spans.iter().copied()).zip(param_types).zip(param_qualifiers) {
+            if self.scopes.last().expect("Resolver has no scope").contains_key(&param) {
+                semantic_error!(REDECLARATION, "Duplicate parameter `{}` in function `{}`", param, name);
+            }
+
+            let unique = self.make_unique(&param);
+            self.symbols.insert(unique.clone(), Symbol { ty: ty.qualified(qualifiers), attributes: SymbolAttributes::Local });
+            self.declare(param, ScopeEntry { unique_name: unique.clone(), has_linkage: false, constant: None, span: Some(span), used: false, unused: Some(Warning::UnusedParameter) });
+            params.push(unique);
+        }
+        // A later declaration may still make this an external definition, as
+        // in `inline int f(void) { ... } extern int f(void);`, but until then,
+        // the body must be valid in an inline definition
+        if global && inline && inline_only {
+            self.inline_definition = Some(name.clone());
+        }
+        let statements = self.resolve_block(def.statements);
+        self.inline_definition = None;
+        let statements = statements?;
+        self.pop_scope();
+
+        return Ok(FunctionDefinition { params, statements, ..def });
+    }
+
+    fn resolve_file_variable(&mut self, var: VariableDeclaration) -> Result<VariableDeclaration, ASTError> {
+        let var = VariableDeclaration { var_type: self.resolve_type(var.var_type)?, ..var };
+        let var = self.resolve_variable_initializer(var)?;
+        let name = var.name.clone();
+
+        if let Some(ScopeEntry { constant: Some(_), .. }) = self.scopes[0].get(&name) {
+            return Err(self.redeclaration(diagnostics::REDECLARATION, &name, var.span, format!("`{}` redeclared as a different kind of symbol", name)));
+        }
+
+        let mut init = match &var.initializer {
+            Some(init) => InitialValue::Initial(self.static_initializer(&name, &var.var_type, init)?),
+            None if var.storage_class == Some(StorageClass::Extern) => InitialValue::NoInitializer,
+            None => InitialValue::Tentative
+        };
+        let mut global = var.storage_class != Some(StorageClass::Static);
+        let mut var_type = var.var_type.clone();
+
+        match self.symbols.get(&name).cloned() {
+            Some(Symbol { ty, attributes: SymbolAttributes::Static { init: prior_init, global: was_global } }) => {
+                let Some(composite) = Type::composite(&ty, &var.var_type) else {
+                    return Err(self.redeclaration(diagnostics::CONFLICTING_TYPES, &name, var.span, format!("Conflicting types for `{}`: `{}`, previously `{}`", name, var.var_type, ty)));
+                };
+                var_type = composite;
+                if var.storage_class == Some(StorageClass::Extern) {
+                    global = was_global;
+                } else if was_global != global {
+                    let message = match global {
+                        true => format!("Non-static declaration of `{}` follows static declaration", name),
+                        false => format!("Static declaration of `{}` follows non-static declaration", name)
+                    };
+                    return Err(self.redeclaration(diagnostics::REDECLARATION, &name, var.span, message));
+                }
+
+                // Merge this declaration with the earlier ones: there may only be one
+                // definition, but any number of tentative definitions
+                if let InitialValue::Initial(_) = prior_init {
+                    if let InitialValue::Initial(_) = init {
+                        return Err(self.redeclaration(diagnostics::REDECLARATION, &name, var.span, format!("Redefinition of `{}`", name)));
+                    }
+                    init = prior_init;
+                } else if prior_init == InitialValue::Tentative && init == InitialValue::NoInitializer {
+                    init = InitialValue::Tentative;
+                }
+            },
+            Some(_) => {
+                return Err(self.redeclaration(diagnostics::REDECLARATION, &name, var.span, format!("`{}` redeclared as a different kind of symbol", name)));
+            },
+            None => {}
+        }
+
+        self.symbols.insert(name.clone(), Symbol {
+            ty: var_type,
+            attributes: SymbolAttributes::Static { init, global }
+        });
+        self.declare(name.clone(), ScopeEntry { unique_name: name, has_linkage: true, constant: None, span: Some(var.span), used: false, unused: None });
+
+        return Ok(var);
+    }
+
+    fn resolve_local_variable(&mut self, var: VariableDeclaration) -> Result<VariableDeclaration, ASTError> {
+        let var = VariableDeclaration { var_type: self.resolve_type(var.var_type)?, ..var };
+        let name = var.name.clone();
+        let is_extern = var.storage_class == Some(StorageClass::Extern);
+
+        if let Some(prior) = self.scopes.last().expect("Resolver has no scope").get(&name) {
+            if !(prior.has_linkage && is_extern) {
+                return Err(self.redeclaration(diagnostics::REDECLARATION, &name, var.span, format!("Redeclaration of `{}`", name)));
+            }
+        }
+
+        // Variable-length arrays are allocated when their declaration is reached
+        if var.var_type.is_variably_modified() && var.storage_class.is_some() {
+            semantic_error!(VARIABLY_MODIFIED_TYPE, "`{}` has variably modified type `{}`, so it can't be static or extern", name, var.var_type);
+        }
+        if var.var_type.is_variable_length() && var.initializer.is_some() {
+            semantic_error!(VARIABLY_MODIFIED_TYPE, "Variable-length array `{}` cannot be initialized", name);
+        }
+
+        match var.storage_class {
+            // Block-scope `extern` declarations refer to an object with linkage,
+            // which is only defined (if at all) at file scope
+            Some(StorageClass::Extern) => {
+                if var.initializer.is_some() {
+                    semantic_error!(INVALID_INITIALIZER, "Block-scope extern declaration of `{}` cannot have an initializer", name);
+                }
+
+                match self.symbols.get(&name).cloned() {
+                    Some(Symbol { ty, attributes: attributes @ SymbolAttributes::Static { .. } }) => {
+                        let Some(composite) = Type::composite(&ty, &var.var_type) else {
+                            return Err(self.redeclaration(diagnostics::CONFLICTING_TYPES, &name, var.span, format!("Conflicting types for `{}`: `{}`, previously `{}`", name, var.var_type, ty)));
+                        };
+                        self.symbols.insert(name.clone(), Symbol { ty: composite, attributes });
+                    },
+                    Some(_) => {
+                        return Err(self.redeclaration(diagnostics::REDECLARATION, &name, var.span, format!("`{}` redeclared as a different kind of symbol", name)));
+                    },
+                    None => {
+                        self.symbols.insert(name.clone(), Symbol {
+                            ty: var.var_type.clone(),
+                            attributes: SymbolAttributes::Static { init: InitialValue::NoInitializer, global: true }
+                        });
+                    }
+                }
+
+                self.declare(name.clone(), ScopeEntry { unique_name: name, has_linkage: true, constant: None, span: Some(var.span), used: false, unused: None });
+                return Ok(var);
+            },
+
+            // Block-scope `static` objects have no linkage, but live for the whole program
+            Some(StorageClass::Static) => {
+                if let Some(function) = &self.inline_definition {
+                    if !var.var_type.qualifiers().constant {
+                        semantic_error!(INVALID_INLINE, "Inline definition of `{}` declares modifiable static object `{}`", function, name);
+                    }
+                }
+
+                let var = self.resolve_variable_initializer(var)?;
+                let init = match &var.initializer {
+                    Some(init) => InitialValue::Initial(self.static_initializer(&name, &var.var_type, init)?),
+                    None if !self.symbols.is_complete(&var.var_type) => {
+                        semantic_error!(INCOMPLETE_TYPE, "Variable `{}` has incomplete type `{}`", name, var.var_type);
+                    },
+                    None => InitialValue::Initial(vec![StaticInit::Zero(self.symbols.size_of(&var.var_type))])
+                };
+
+                let unique = self.make_unique(&name);
+                self.symbols.insert(unique.clone(), Symbol {
+                    ty: var.var_type.clone(),
+                    attributes: SymbolAttributes::Static { init, global: false }
+                });
+                self.declare(name, ScopeEntry { unique_name: unique.clone(), has_linkage: false, constant: None, span: Some(var.span), used: false, unused: Some(Warning::UnusedVariable) });
+                return Ok(VariableDeclaration { name: unique, ..var });
+            },
+
+            None => {
+                // The variable is in scope within its own initializer
+                let unique = self.make_unique(&name);
+                self.symbols.insert(unique.clone(), Symbol { ty: var.var_type.clone(), attributes: SymbolAttributes::Local });
+                self.declare(name, ScopeEntry { unique_name: unique.clone(), has_linkage: false, constant: None, span: Some(var.span), used: false, unused: Some(Warning::UnusedVariable) });
+
+                let var = self.resolve_variable_initializer(var)?;
+                self.symbols.insert(unique.clone(), Symbol { ty: var.var_type.clone(), attributes: SymbolAttributes::Local });
+                return Ok(VariableDeclaration { name: unique, ..var });
+            }
+        }
+    }
+
+    fn resolve_block(&mut self, statements: Vec<Statement>) -> Result<Vec<Statement>, ASTError> {
+        let mut resolved = vec![];
+        for s in statements {
+            let span = s.span;
+            let s = self.resolve_statement(s).map_err(|e| e.within(span))?;
+            resolved.extend(self.counts.drain(..).map(|count| Statement::new(StatementKind::Declaration(Declaration::Variable(count)), s.span)));
+            resolved.push(s);
+        }
+
+        return Ok(resolved);
+    }
+
+    fn resolve_statement(&mut self, s: Statement) -> Result<Statement, ASTError> {
+        let kind = match s.kind {
+            StatementKind::Return(e) => StatementKind::Return(self.resolve_expression(e)?),
+            StatementKind::Expression(e) => StatementKind::Expression(self.resolve_expression(e)?),
+            StatementKind::Declaration(Declaration::Function(def)) => {
+                StatementKind::Declaration(Declaration::Function(self.resolve_function(def)?))
+            },
+            StatementKind::Declaration(Declaration::Variable(var)) => {
+                StatementKind::Declaration(Declaration::Variable(self.resolve_local_variable(var)?))
+            },
+            StatementKind::Declaration(Declaration::Struct(dec)) => {
+                StatementKind::Declaration(Declaration::Struct(self.resolve_struct_declaration(dec)?))
+            },
+            StatementKind::Declaration(Declaration::Enum(dec)) => {
+                StatementKind::Declaration(Declaration::Enum(self.resolve_enum_declaration(dec)?))
+            },
+            StatementKind::Declaration(Declaration::StaticAssert(assertion)) => {
+                StatementKind::Declaration(Declaration::StaticAssert(self.resolve_static_assertion(assertion)?))
+            },
+            StatementKind::Compound(statements) => {
+                self.push_scope();
+                let statements = self.resolve_block(statements)?;
+                self.pop_scope();
+
+                StatementKind::Compound(statements)
+            },
+            StatementKind::Null => StatementKind::Null
+        };
+
+        return Ok(Statement::new(kind, s.span));
+    }
+
+    // Resolves the initializer of a variable, and rewrites it to have one initializer per
+    // element or member (see `initializer::normalize`). Arrays declared without a size,
+    // as in `int a[] = {1, 2}` or `char s[] = "ab"`, take it from their initializer
+    fn resolve_variable_initializer(&mut self, var: VariableDeclaration) -> Result<VariableDeclaration, ASTError> {
+        let Some(init) = var.initializer else {
+            return Ok(var);
+        };
+
+        let init = self.resolve_initializer(init)?;
+        let init = initializer::normalize(init, &var.var_type, var.span, &self.symbols)?;
+        let var_type = match (var.var_type, initializer::array_length(&init)) {
+            (Type::Array(element, 0), Some(length)) if length > 0 => Type::Array(element, length),
+            (Type::Array(_, 0), _) => {
+                semantic_error!(INVALID_ARRAY_SIZE, "Array `{}` has zero or unknown size", var.name);
+            },
+            (ty, _) => ty
+        };
+
+        let init = self.lower_strings(init, &var_type)?;
+        return Ok(VariableDeclaration { var_type, initializer: Some(init), ..var });
+    }
+
+    // String literals are left as they are, as they may initialize a character array
+    fn resolve_initializer(&mut self, init: Initializer) -> Result<Initializer, ASTError> {
+        match init {
+            Initializer::Single(e @ Expression { kind: ExpressionKind::String(_), .. }) => Ok(Initializer::Single(e)),
+            Initializer::Single(e) => Ok(Initializer::Single(self.resolve_expression(e)?)),
+            Initializer::Compound(list, span) => {
+                let mut resolved = vec![];
+                for init in list {
+                    resolved.push(self.resolve_initializer(init)?);
+                }
+
+                Ok(Initializer::Compound(resolved, span))
+            },
+            Initializer::Designated(designators, init) => {
+                let mut resolved = vec![];
+                for designator in designators {
+                    resolved.push(match designator {
+                        Designator::Index(e) => Designator::Index(self.resolve_expression(e)?),
+                        member => member
+                    });
+                }
+
+                Ok(Initializer::Designated(resolved, Box::new(self.resolve_initializer(*init)?)))
+            }
+        }
+    }
+
+    // Replaces the string literals of a normalized initializer of an object of type `ty`,
+    // except those initializing a character array, by the array they designate
+    fn lower_strings(&mut self, init: Initializer, ty: &Type) -> Result<Initializer, ASTError> {
+        match (ty.unqualified(), init) {
+            (_, init @ Initializer::Single(Expression { kind: ExpressionKind::String(_), .. })) if initializer::is_character_array(ty) => Ok(init),
+            (_, Initializer::Single(Expression { kind: ExpressionKind::String(bytes), span, .. })) => {
+                Ok(Initializer::Single(self.string_object(bytes, span)?))
+            },
+            (Type::Array(element, _), Initializer::Compound(list, span)) => {
+                let mut lowered = vec![];
+                for init in list {
+                    lowered.push(self.lower_strings(init, element)?);
+                }
+                Ok(Initializer::Compound(lowered, span))
+            },
+            // Members of unions are designated by name, and excess initializers are left to the type checker
+            (Type::Structure(_, tag), Initializer::Compound(list, span)) => {
+                let members = self.symbols.get_struct(tag).expect("Internal resolver error -- incomplete structure").members.clone();
+                let mut lowered = vec![];
+                for (index, init) in list.into_iter().enumerate() {
+                    let member = match init.union_member().0 {
+                        Some(name) => members.iter().find(|m| m.name == name),
+                        None => members.get(index)
+                    };
+                    lowered.push(match (member, init) {
+                        (Some(member), Initializer::Designated(designators, init)) => {
+                            Initializer::Designated(designators, Box::new(self.lower_strings(*init, &member.ty)?))
+                        },
+                        (Some(member), init) => self.lower_strings(init, &member.ty)?,
+                        (None, init) => init
+                    });
+                }
+                Ok(Initializer::Compound(lowered, span))
+            },
+            (_, init) => Ok(init)
+        }
+    }
+
+    // A string literal designates an array of static storage duration, named like
+    // compound literals, which holds its characters and terminating null
+    fn string_object(&mut self, bytes: Vec<u8>, span: Span) -> Result<Expression, ASTError> {
+        let ty = Type::Array(Box::new(Type::Char), bytes.len() + 1);
+        let init = self.static_initializer("string literal", &ty, &Initializer::Single(Expression::new(ExpressionKind::String(bytes), span)))?;
+        let unique = self.make_unique("string");
+        self.symbols.insert(unique.clone(), Symbol {
+            ty,
+            attributes: SymbolAttributes::Static { init: InitialValue::Initial(init), global: false }
+        });
+        return Ok(Expression::new(ExpressionKind::Variable(unique), span));
+    }
+
+    fn resolve_expression(&mut self, e: Expression) -> Result<Expression, ASTError> {
+        let span = e.span;
+        return self.resolve_expression_inner(e).map_err(|e| e.within(span));
+    }
+
+    fn resolve_expression_inner(&mut self, e: Expression) -> Result<Expression, ASTError> {
+        let span = e.span;
+        let kind = match e.kind {
+            ExpressionKind::Constant(c) => ExpressionKind::Constant(c),
+            ExpressionKind::String(bytes) => return self.string_object(bytes, span),
+
+            ExpressionKind::Variable(name) => {
+                let unique = match self.lookup(&name) {
+                    Some(ScopeEntry { constant: Some(value), .. }) => return Ok(Expression::new(ExpressionKind::Constant(value.clone()), span)),
+                    Some(entry) => entry.unique_name.clone(),
+                    None => {
+                        semantic_error!(UNDECLARED_IDENTIFIER at span, "Use of undeclared identifier `{}`", name);
+                    }
+                };
+                self.mark_used(&name);
+                if let Some(function) = &self.inline_definition {
+                    if let Some(SymbolAttributes::Function { global: false, .. } | SymbolAttributes::Static { global: false, .. }) = self.symbols.get(&unique).map(|s| &s.attributes) {
+                        if self.lookup(&name).is_some_and(|entry| entry.has_linkage) {
+                            semantic_error!(INVALID_INLINE, "Inline definition of `{}` refers to `{}`, which has internal linkage", function, name);
+                        }
+                    }
+                }
+
+                ExpressionKind::Variable(unique)
+            },
+
+            ExpressionKind::Unary(op, inner) => {
+                ExpressionKind::Unary(op, Box::new(self.resolve_expression(*inner)?))
+            },
+
+            ExpressionKind::Binary(op, left, right) => {
+                let left = self.resolve_expression(*left)?;
+                let right = self.resolve_expression(*right)?;
+                ExpressionKind::Binary(op, Box::new(left), Box::new(right))
+            },
+
+            ExpressionKind::Cast(ty, inner) => {
+                let ty = self.resolve_type(ty)?;
+                ExpressionKind::Cast(ty, Box::new(self.resolve_expression(*inner)?))
+            },
+
+            ExpressionKind::VaStart(ap) => ExpressionKind::VaStart(Box::new(self.resolve_expression(*ap)?)),
+            ExpressionKind::VaArg(ap, ty) => {
+                let ty = self.resolve_type(ty)?;
+                ExpressionKind::VaArg(Box::new(self.resolve_expression(*ap)?), ty)
+            },
+
+            ExpressionKind::CompoundLiteral(literal) => return self.resolve_compound_literal(*literal),
+
+            ExpressionKind::Dot(inner, member) => {
+                ExpressionKind::Dot(Box::new(self.resolve_expression(*inner)?), member)
+            },
+
+            ExpressionKind::Arrow(inner, member) => {
+                ExpressionKind::Arrow(Box::new(self.resolve_expression(*inner)?), member)
+            },
+
+            ExpressionKind::AddressOf(inner) => {
+                ExpressionKind::AddressOf(Box::new(self.resolve_expression(*inner)?))
+            },
+
+            ExpressionKind::Dereference(inner) => {
+                ExpressionKind::Dereference(Box::new(self.resolve_expression(*inner)?))
+            },
+
+            ExpressionKind::Subscript(left, right) => {
+                let left = self.resolve_expression(*left)?;
+                let right = self.resolve_expression(*right)?;
+                ExpressionKind::Subscript(Box::new(left), Box::new(right))
+            },
+
+            ExpressionKind::Assignment(left, right) => {
+                if !left.is_lvalue() {
+                    semantic_error!(NOT_ASSIGNABLE at left.span, "Expression is not assignable");
+                }
+
+                let left = self.resolve_expression(*left)?;
+                let right = self.resolve_expression(*right)?;
+                ExpressionKind::Assignment(Box::new(left), Box::new(right))
+            },
+
+            // `sizeof` and `_Alignof` are replaced by their value, which only depends on the
+            // type of their operand, so the operand itself is never evaluated
+            ExpressionKind::SizeOf(inner) => {
+                // Functions aren't objects, so they have no size either
+                if let ExpressionKind::Variable(name) = &inner.kind {
+                    let symbol = self.lookup(name).and_then(|entry| self.symbols.get(&entry.unique_name));
+                    if let Some(Symbol { ty: ty @ Type::Function(_), .. }) = symbol {
+                        return self.layout_constant(ty, false, span);
+                    }
+                }
+
+                let inner = self.resolve_expression(*inner)?;
+                if typecheck::is_bit_field(inner.clone(), &self.symbols)? {
+                    semantic_error!(INVALID_LAYOUT_OPERAND at span, "Invalid application of `sizeof` to a bit-field");
+                }
+                let ty = typecheck::type_of(inner, &self.symbols)?;
+                return self.layout_constant(&ty, false, span);
+            },
+            ExpressionKind::SizeOfType(ty) => {
+                let ty = self.resolve_type(ty)?;
+                return self.layout_constant(&ty, false, span);
+            },
+            ExpressionKind::AlignOf(ty) => {
+                let ty = self.resolve_type(ty)?;
+                return self.layout_constant(&ty, true, span);
+            },
+
+            // Whether the callee is a function is up to the type checker, as it may be any
+            // expression of pointer to function type, as in `(*callbacks[i])(x)`
+            ExpressionKind::FunctionCall(function, arguments) => {
+                if let ExpressionKind::Variable(name) = &function.kind {
+                    if self.lookup(name).is_none() {
+                        self.declare_implicit_function(name, function.span);
+                    }
+                }
+
+                let function = self.resolve_expression(*function)?;
+                let mut resolved = vec![];
+                for a in arguments {
+                    resolved.push(self.resolve_expression(a)?);
+                }
+                ExpressionKind::FunctionCall(Box::new(function), resolved)
+            }
+        };
+
+        return Ok(Expression::new(kind, span));
+    }
+
+    // Compound literals create an object named after them, which is automatic in a block,
+    // like the variables declared there, and static at file scope, where its initializer
+    // must be constant. Arrays declared without a size take it from their initializer
+    fn resolve_compound_literal(&mut self, literal: VariableDeclaration) -> Result<Expression, ASTError> {
+        let literal = VariableDeclaration {
+            name: "compound literal".to_string(),
+            var_type: self.resolve_type(literal.var_type)?,
+            ..literal
+        };
+        if let Type::Function(_) | Type::Void = literal.var_type {
+            semantic_error!(INVALID_DECLARATOR, "Compound literal has invalid type `{}`", literal.var_type);
+        }
+        if literal.var_type.is_variable_length() {
+            semantic_error!(VARIABLY_MODIFIED_TYPE, "Compound literal has variable-length array type `{}`", literal.var_type);
+        }
+        let literal = self.resolve_variable_initializer(literal)?;
+        let unique = self.make_unique("compound");
+        let span = literal.span;
+
+        if !self.at_file_scope() {
+            self.symbols.insert(unique.clone(), Symbol { ty: literal.var_type.clone(), attributes: SymbolAttributes::Local });
+            let literal = VariableDeclaration { name: unique, ..literal };
+            return Ok(Expression::new(ExpressionKind::CompoundLiteral(Box::new(literal)), span));
+        }
+
+        let init = match &literal.initializer {
+            Some(init) => self.static_initializer(&literal.name, &literal.var_type, init)?,
+            None => panic!("Internal resolver error -- compound literal without initializer")
+        };
+        self.symbols.insert(unique.clone(), Symbol {
+            ty: literal.var_type,
+            attributes: SymbolAttributes::Static { init: InitialValue::Initial(init), global: false }
+        });
+        return Ok(Expression::new(ExpressionKind::Variable(unique), span));
+    }
+
+    // Size or alignment of a type, as a constant of type `unsigned long` (`size_t`). The
+    // size of a variable-length array is computed at runtime from its number of elements
+    fn layout_constant(&self, ty: &Type, is_alignment: bool, span: Span) -> Result<Expression, ASTError> {
+        let operator = if is_alignment { "_Alignof" } else { "sizeof" };
+        if let Type::Function(_) = ty {
+            semantic_error!(INVALID_LAYOUT_OPERAND at span, "Invalid application of `{}` to a function type", operator);
+        }
+        if !self.symbols.is_complete(ty) {
+            semantic_error!(INVALID_LAYOUT_OPERAND at span, "Invalid application of `{}` to an incomplete type `{}`", operator, ty);
+        }
+
+        if !is_alignment && ty.is_variable_length() {
+            return Ok(self.size_expression(ty, span));
+        }
+
+        let value = if is_alignment { self.symbols.alignment_of(ty) } else { self.symbols.size_of(ty) };
+        return Ok(Expression::new(ExpressionKind::Constant(ConstantValue::ULong(value as u64)), span));
+    }
+
+    // Size of a variable-length array, as in `n * (3 * 4)` for `int[n][3]`
+    fn size_expression(&self, ty: &Type, span: Span) -> Expression {
+        let (count, element) = match ty.unqualified() {
+            Type::VariableArray(element, count) => (ExpressionKind::Variable(count.clone()), element),
+            Type::Array(element, count) if element.is_variable_length() => {
+                (ExpressionKind::Constant(ConstantValue::ULong(*count as u64)), element)
+            },
+            ty => {
+                let size = ConstantValue::ULong(self.symbols.size_of(ty) as u64);
+                return Expression::new(ExpressionKind::Constant(size), span);
+            }
+        };
+
+        let element = self.size_expression(element, span);
+        return Expression::new(ExpressionKind::Binary(BinaryExpressionType::Multiply, Box::new(Expression::new(count, span)), Box::new(element)), span);
+    }
+
+    // Initial value of a static object, with runs of zero bytes merged. Objects with
+    // static storage duration must be initialized with constants, which are converted
+    // to the type of the object at compile time. Array elements and members without
+    // an initializer are zero-initialized
+    fn static_initializer(&self, name: &str, ty: &Type, init: &Initializer) -> Result<Vec<StaticInit>, ASTError> {
+        let mut merged: Vec<StaticInit> = vec![];
+        for value in self.static_values(name, ty, init)? {
+            match (merged.last_mut(), value) {
+                (Some(StaticInit::Zero(previous)), StaticInit::Zero(bytes)) => *previous += bytes,
+                (_, value) => merged.push(value)
+            }
+        }
+
+        return Ok(merged);
+    }
+
+    fn static_values(&self, name: &str, ty: &Type, init: &Initializer) -> Result<Vec<StaticInit>, ASTError> {
+        if !self.symbols.is_complete(ty) {
+            semantic_error!(INCOMPLETE_TYPE, "Variable `{}` has incomplete type `{}`", name, ty);
+        }
+
+        match (ty.unqualified(), init) {
+            // The terminating null is only stored if there is room for it
+            (Type::Array(element, count), Initializer::Single(Expression { kind: ExpressionKind::String(bytes), span, .. })) if initializer::is_character_array(ty) => {
+                if bytes.len() > *count {
+                    semantic_error!(INVALID_INITIALIZER at *span, "Initializer-string for `{}` is too long", name);
+                }
+
+                let mut values: Vec<StaticInit> = bytes.iter()
+                    .map(|byte| StaticInit::integer(element, *byte as i128))
+                    .collect();
+                if bytes.len() < *count {
+                    values.push(StaticInit::Zero(count - bytes.len()));
+                }
+
+                Ok(values)
+            },
+            (Type::Array(element, count), Initializer::Compound(list, _)) => {
+                if list.len() > *count {
+                    semantic_error!(INVALID_INITIALIZER at list[*count].span(), "Excess elements in initializer of `{}`", name);
+                }
+
+                let mut values = vec![];
+                for init in list {
+                    values.extend(self.static_values(name, element, init)?);
+                }
+                if list.len() < *count {
+                    values.push(StaticInit::Zero((count - list.len()) * self.symbols.size_of(element)));
+                }
+
+                Ok(values)
+            },
+            // A union is initialized through one of its members, the first one unless designated
+            (Type::Structure(StructKind::Union, tag), Initializer::Compound(list, _)) => {
+                let definition = self.symbols.get_struct(tag).expect("Internal resolver error -- incomplete structure");
+                let mut values = vec![];
+                let mut offset = 0;
+                if let Some(init) = list.first() {
+                    let (designated, init) = init.union_member();
+                    let member = match designated {
+                        Some(name) => definition.members.iter().find(|m| m.name == name).expect("Internal resolver error -- unknown union member"),
+                        None => &definition.members[0]
+                    };
+                    match member.bit_field {
+                        Some(bit_field) => {
+                            let mut bytes = vec![];
+                            Self::pack_bit_field(&mut bytes, 0, bit_field, self.bit_field_value(name, &member.ty, init)?);
+                            offset = bytes.len();
+                            values.extend(bytes.into_iter().map(|byte| StaticInit::integer(&Type::UChar, byte as i128)));
+                        },
+                        None => {
+                            values.extend(self.static_values(name, &member.ty, init)?);
+                            offset = self.symbols.size_of(&member.ty);
+                        }
+                    }
+                }
+                if definition.size > offset {
+                    values.push(StaticInit::Zero(definition.size - offset));
+                }
+
+                Ok(values)
+            },
+            // Padding between members is zero-filled
+            (Type::Structure(_, tag), Initializer::Compound(list, _)) => {
+                let definition = self.symbols.get_struct(tag).expect("Internal resolver error -- incomplete structure");
+                if list.len() > definition.members.len() {
+                    semantic_error!(INVALID_INITIALIZER at list[definition.members.len()].span(), "Excess elements in initializer of `{}`", name);
+                }
+
+                // Bit-fields sharing bytes are packed together, and stored byte by byte
+                let mut values = vec![];
+                let mut offset = 0;
+                let mut bytes: Vec<u8> = vec![];
+                for (member, init) in definition.members.iter().zip(list) {
+                    if let Some(bit_field) = member.bit_field {
+                        let value = self.bit_field_value(name, &member.ty, init)?;
+                        Self::pack_bit_field(&mut bytes, member.offset * 8 + bit_field.offset - offset * 8, bit_field, value);
+                        continue;
+                    }
+
+                    offset += bytes.len();
+                    values.extend(bytes.drain(..).map(|byte| StaticInit::integer(&Type::UChar, byte as i128)));
+                    if member.offset > offset {
+                        values.push(StaticInit::Zero(member.offset - offset));
+                    }
+                    values.extend(self.static_values(name, &member.ty, init)?);
+                    offset = member.offset + self.symbols.size_of(&member.ty);
+                }
+                offset += bytes.len();
+                values.extend(bytes.into_iter().map(|byte| StaticInit::integer(&Type::UChar, byte as i128)));
+                if definition.size > offset {
+                    values.push(StaticInit::Zero(definition.size - offset));
+                }
+
+                Ok(values)
+            },
+            (Type::Structure(_, _), Initializer::Single(e)) => {
+                semantic_error!(INVALID_INITIALIZER at e.span, "Structure `{}` must be initialized with a brace-enclosed initializer list", name);
+            },
+            (Type::Array(_, _), Initializer::Single(e)) => {
+                semantic_error!(INVALID_INITIALIZER at e.span, "Array `{}` must be initialized with a brace-enclosed initializer list", name);
+            },
+            (_, Initializer::Compound(_, span)) => {
+                semantic_error!(INVALID_INITIALIZER at *span, "Scalar `{}` cannot be initialized with an initializer list", name);
+            },
+            (_, Initializer::Single(e)) => Ok(vec![self.eval_constant(name, ty, e)?]),
+            (_, Initializer::Designated(_, _)) => panic!("Internal resolver error -- designated initializer was not normalized")
+        }
+    }
+
+    // Value of a bit-field initializer, converted to the type of the bit-field
+    fn bit_field_value(&self, name: &str, ty: &Type, init: &Initializer) -> Result<i128, ASTError> {
+        match self.static_values(name, ty, init)?.as_slice() {
+            [StaticInit::Value(_, value)] => Ok(value.value().expect("Internal resolver error -- bit-field value is not an integer")),
+            _ => Ok(0)
+        }
+    }
+
+    // Stores the low `width` bits of `value` in `bytes` from bit `start` on, growing them as needed
+    fn pack_bit_field(bytes: &mut Vec<u8>, start: usize, bit_field: BitField, value: i128) {
+        let end = start + bit_field.width;
+        if bytes.len() < end.div_ceil(8) {
+            bytes.resize(end.div_ceil(8), 0);
+        }
+
+        for bit in 0..bit_field.width {
+            if (value >> bit) & 1 == 1 {
+                bytes[(start + bit) / 8] |= 1 << ((start + bit) % 8);
+            }
+        }
+    }
+
+    // Pointers can only be initialized with an address constant or a null pointer
+    // constant, and arithmetic objects with an arithmetic constant expression,
+    // converted to their type
+    fn eval_constant(&self, name: &str, ty: &Type, e: &Expression) -> Result<StaticInit, ASTError> {
+        let ty = ty.unqualified();
+        if let Some((symbol, offset, pointer)) = self.address_constant(e) {
+            Self::check_pointer_initializer(name, &pointer, ty, e)?;
+            return Ok(StaticInit::Address(symbol, offset));
+        }
+
+        if ty.is_pointer() {
+            if consteval::is_null_pointer_constant(e) {
+                return Ok(StaticInit::Zero(ty.size()));
+            }
+            // Integer constants cast to pointers are constants as well, as in `(char *)-1`
+            if let ExpressionKind::Cast(pointer @ Type::Pointer(_), inner) = &e.kind {
+                if let Some((value, _)) = consteval::evaluate(inner) {
+                    Self::check_pointer_initializer(name, pointer, ty, e)?;
+                    return match Type::ULong.wrap(value) {
+                        0 => Ok(StaticInit::Zero(ty.size())),
+                        value => Ok(StaticInit::integer(&Type::ULong, value))
+                    };
+                }
+            }
+
+            match consteval::evaluate(e) {
+                Some((0, _)) => return Ok(StaticInit::Zero(ty.size())),
+                Some(_) => {
+                    semantic_error!(INCOMPATIBLE_TYPES, "Invalid initializer of pointer `{}`: integer constants other than 0 are not pointers", name);
+                },
+                None => {
+                    semantic_error!(INVALID_CONSTANT at e.span, "Initializer of `{}` is not a constant expression", name);
+                }
+            }
+        }
+
+        let init = match consteval::evaluate_arithmetic(e) {
+            Some(constant) if ty.is_floating() => constant.convert(ty).map(|constant| StaticInit::Value(ty.clone(), constant)),
+            Some(constant) => constant.integer_value(ty).map(|value| StaticInit::integer(ty, value)),
+            None => None
+        };
+        let Some(init) = init else {
+            semantic_error!(INVALID_CONSTANT at e.span, "Initializer of `{}` is not a constant expression", name);
+        };
+
+        // Negative zero is not all zero bits, so it can't go in .bss
+        let is_zero = match &init {
+            StaticInit::Value(_, ConstantValue::Float(v)) => v.to_bits() == 0,
+            StaticInit::Value(_, ConstantValue::Double(v)) => v.to_bits() == 0,
+            StaticInit::Value(_, constant) => constant.value() == Some(0),
+            _ => false
+        };
+        if is_zero {
+            return Ok(StaticInit::Zero(ty.size()));
+        }
+
+        return Ok(init);
+    }
+
+    // Pointer initializers must point to a compatible type, with at least its qualifiers
+    fn check_pointer_initializer(name: &str, pointer: &Type, ty: &Type, e: &Expression) -> Result<(), ASTError> {
+        match Type::pointer_conversion(pointer, ty) {
+            Some(discarded) if discarded.is_empty() => Ok(()),
+            Some(discarded) => {
+                semantic_error!(INCOMPATIBLE_TYPES at e.span, "Initializer of `{}` discards the `{}` qualifier of `{}`", name, discarded, pointer);
+            },
+            None => {
+                semantic_error!(INCOMPATIBLE_TYPES at e.span, "Initializer of `{}` has incompatible type `{}`", name, pointer);
+            }
+        }
+    }
+
+    // Addresses of functions and static objects are known at link time, so they are
+    // constants as well, e.g. in tables of callbacks, and so are the addresses of their
+    // elements and members, as in `&g[1][2]`, `g + 1` or `&s.m`, and static arrays, which
+    // decay to the address of their first element. Returns the symbol, the offset
+    // in bytes from it, and the pointer type
+    fn address_constant(&self, e: &Expression) -> Option<(String, i64, Type)> {
+        let (symbol, offset, ty) = match &e.kind {
+            ExpressionKind::AddressOf(inner) => {
+                let (symbol, offset, ty) = self.static_object(inner)?;
+                return Some((symbol, offset, Type::Pointer(Box::new(ty))));
+            },
+            ExpressionKind::Binary(op @ (BinaryExpressionType::Add | BinaryExpressionType::Subtract), left, right) => {
+                let (pointer, count) = match (self.address_constant(left), self.address_constant(right)) {
+                    (Some(pointer), None) => (pointer, right),
+                    (None, Some(pointer)) if *op == BinaryExpressionType::Add => (pointer, left),
+                    _ => return None
+                };
+                let (symbol, offset, ty) = pointer;
+                let pointee = ty.pointee()?;
+                if matches!(pointee, Type::Function(_)) || !self.symbols.is_complete(pointee) {
+                    return None;
+                }
+                let size = self.symbols.size_of(pointee) as i64;
+                let count = match consteval::evaluate(count)? {
+                    (count, count_type) if count_type.is_integer() => count as i64,
+                    _ => return None
+                };
+                let offset = match op {
+                    BinaryExpressionType::Add => offset + count * size,
+                    _ => offset - count * size
+                };
+                return Some((symbol, offset, ty));
+            },
+            // Casts only change the type the address points to
+            ExpressionKind::Cast(ty @ Type::Pointer(_), inner) => {
+                let (symbol, offset, _) = self.address_constant(inner)?;
+                return Some((symbol, offset, ty.clone()));
+            },
+            _ => self.static_object(e)?
+        };
+
+        match ty {
+            ty @ Type::Function(_) => Some((symbol, offset, Type::Pointer(Box::new(ty)))),
+            Type::Array(element, _) => Some((symbol, offset, Type::Pointer(element))),
+            _ => None
+        }
+    }
+
+    // The static object or function designated by an expression, as in `g[1].m`
+    // or `*(g + 1)`, with its offset in bytes from the symbol and its type
+    fn static_object(&self, e: &Expression) -> Option<(String, i64, Type)> {
+        match &e.kind {
+            ExpressionKind::Variable(name) => match self.symbols.get(name)? {
+                Symbol { ty: ty @ Type::Function(_), .. } => Some((name.clone(), 0, ty.clone())),
+                Symbol { ty, attributes: SymbolAttributes::Static { .. } } => Some((name.clone(), 0, ty.clone())),
+                _ => None
+            },
+            ExpressionKind::Dereference(inner) => {
+                let (symbol, offset, pointer) = self.address_constant(inner)?;
+                Some((symbol, offset, pointer.pointee()?.clone()))
+            },
+            ExpressionKind::Subscript(left, right) => {
+                let sum = ExpressionKind::Binary(BinaryExpressionType::Add, left.clone(), right.clone());
+                let (symbol, offset, pointer) = self.address_constant(&Expression::new(sum, e.span))?;
+                Some((symbol, offset, pointer.pointee()?.clone()))
+            },
+            ExpressionKind::Dot(inner, name) => {
+                let (symbol, offset, ty) = self.static_object(inner)?;
+                self.member_object(symbol, offset, &ty, name)
+            },
+            ExpressionKind::Arrow(inner, name) => {
+                let (symbol, offset, pointer) = self.address_constant(inner)?;
+                self.member_object(symbol, offset, pointer.pointee()?, name)
+            },
+            _ => None
+        }
+    }
+
+    // A member of a static structure, with its qualifiers, unless it is a bit-field, which has no address
+    fn member_object(&self, symbol: String, offset: i64, ty: &Type, name: &str) -> Option<(String, i64, Type)> {
+        let Type::Structure(_, tag) = ty.unqualified() else {
+            return None;
+        };
+        let member = self.symbols.get_struct(tag)?.members.iter().find(|m| m.name == name)?;
+        if member.bit_field.is_some() {
+            return None;
+        }
+        let member_type = match ty {
+            Type::Qualified(qualifiers, _) => member.ty.clone().qualified(*qualifiers),
+            _ => member.ty.clone()
+        };
+        return Some((symbol, offset + member.offset as i64, member_type));
+    }
+}
@@ -1,56 +1,454 @@
 // AST: Abstract Syntax Tree //
 #![allow(dead_code)]
+pub use super::types::{Type, FunctionType, StructKind, Qualifiers, VA_LIST_TAG};
+// Every statement, expression, declaration and initializer has the span of the
+// tokens it was parsed from. Nodes later passes make up, such as implicit
+// conversions, take the span of the expression they apply to
+pub use crate::lex::source::Span;
 
 // A program consists of many top-level declarations
 pub type Program = Vec<Declaration>;
 
-// Top-level declarations can be functions,
-// or (TODO) static variables/constants
+// Declarations can be functions or variables, and can
+// appear both at file scope and within a block
 #[derive(Debug, Clone)]
 pub enum Declaration {
     Function(FunctionDefinition),
+    Variable(VariableDeclaration),
+    Struct(StructDeclaration),
+    Enum(EnumDeclaration),
+    StaticAssert(StaticAssertion)
 }
 
-// Function consist of a name and multiple internal
-// statements and (TODO) types
+// Storage-class specifiers that may precede a declaration,
+// and which determine its linkage and storage duration
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StorageClass {
+    Static,
+    Extern
+}
+
+// Function consist of a name, its parameters, its type and
+// multiple internal statements. A function without a body
+// (`has_body == false`) is only a declaration
 #[derive(Debug, Clone)]
 pub struct FunctionDefinition {
     pub name: String,
-    pub statements: Vec<Statement>
+    pub params: Vec<String>,
+    // Where each parameter is declared
+    pub param_spans: Vec<Span>,
+    pub function_type: Type,
+    pub statements: Vec<Statement>,
+    pub has_body: bool,
+    pub storage_class: Option<StorageClass>,
+    pub is_inline: bool,
+    pub span: Span
+}
+
+// Variables consist of a name, a type and an optional initializer
+#[derive(Debug, Clone, PartialEq)]
+pub struct VariableDeclaration {
+    pub name: String,
+    pub var_type: Type,
+    pub initializer: Option<Initializer>,
+    pub storage_class: Option<StorageClass>,
+    pub span: Span
+}
+
+// Structure and union declarations introduce a tag, and define
+// its members if they have a member list. Without one, as in
+// `struct s;`, they declare an incomplete type
+#[derive(Debug, Clone)]
+pub struct StructDeclaration {
+    pub tag: String,
+    pub kind: StructKind,
+    pub members: Option<Vec<MemberDeclaration>>,
+    pub span: Span
+}
+
+// Bit-fields have a width in bits, as in `unsigned flag : 1;`, and may be
+// unnamed, as in `int : 3;`, in which case they only take up space
+#[derive(Debug, Clone)]
+pub struct MemberDeclaration {
+    pub name: String,
+    pub member_type: Type,
+    pub bit_width: Option<usize>,
+    pub span: Span
+}
+
+// Enumerations declare integer constants of type `int`, whose values the
+// parser determines, e.g. `enum color { RED, GREEN = 4, BLUE }`
+#[derive(Debug, Clone)]
+pub struct EnumDeclaration {
+    pub enumerators: Vec<Enumerator>,
+    pub span: Span
+}
+
+#[derive(Debug, Clone)]
+pub struct Enumerator {
+    pub name: String,
+    pub value: ConstantValue,
+    pub span: Span
+}
+
+// `_Static_assert(condition, "message")`, whose condition is an integer constant
+// expression that the resolver evaluates, failing compilation with the message if it is 0
+#[derive(Debug, Clone)]
+pub struct StaticAssertion {
+    pub condition: Expression,
+    pub message: String,
+    pub span: Span
+}
+
+// Scalars are initialized with a single expression, and arrays and structures
+// with a brace-enclosed list of initializers for their elements, e.g. `{1, 2}`
+//
+// Elements of a list may be designated, as in `{ [2] = 1, .x.y = 2 }`, and braces
+// around nested aggregates may be elided. The resolver rewrites every list to have
+// one initializer per element or member, in order, so designators only remain to
+// name the member a union is initialized through, if it isn't the first one. Lists
+// the resolver makes up take the span of the list or declaration they come from
+#[derive(Debug, Clone, PartialEq)]
+pub enum Initializer {
+    Single(Expression),
+    Compound(Vec<Initializer>, Span),
+    Designated(Vec<Designator>, Box<Initializer>)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Designator {
+    Index(Expression),
+    Member(String, Span)
+}
+
+impl Designator {
+    pub fn span(&self) -> Span {
+        match self {
+            Designator::Index(e) => e.span,
+            Designator::Member(_, span) => *span
+        }
+    }
+}
+
+impl Initializer {
+    // Designated initializers span from their first designator to the end of their value
+    pub fn span(&self) -> Span {
+        match self {
+            Initializer::Single(e) => e.span,
+            Initializer::Compound(_, span) => *span,
+            Initializer::Designated(designators, init) => {
+                let start = designators.first().map_or(init.span().start, |designator| designator.span().start);
+                Span { start, end: init.span().end }
+            }
+        }
+    }
+
+    // The member a union initializer applies to, by name, if it isn't the first one
+    pub fn union_member(&self) -> (Option<&str>, &Initializer) {
+        match self {
+            Initializer::Designated(designators, init) => match designators.as_slice() {
+                [Designator::Member(name, _)] => (Some(name), init),
+                _ => panic!("Internal error -- union initializer with a nested designator")
+            },
+            init => (None, init)
+        }
+    }
 }
 
 // Statements called within functions -- this includes
-// a return, or (TODO) a function call, or a variable
-// declaration
+// a return, an expression (such as an assignment or a
+// function call), a declaration, or a nested block
+#[derive(Debug, Clone)]
+pub struct Statement {
+    pub kind: StatementKind,
+    pub span: Span
+}
+
 #[derive(Debug, Clone)]
-pub enum Statement {
-    Return(Expression)
+pub enum StatementKind {
+    Return(Expression),
+    Expression(Expression),
+    Declaration(Declaration),
+    Compound(Vec<Statement>),
+    Null
+}
+
+impl Statement {
+    pub fn new(kind: StatementKind, span: Span) -> Self {
+        return Self { kind, span };
+    }
 }
 
-// Expressions are part of statements and can be 
+// Expressions are part of statements and can be
 // thought of as values -- for example, we return
 // an expression, which could be `8` or `~1`, or `1 + 2`
-#[derive(Debug, Clone)]
-pub enum Expression {
+//
+// The type of every expression is filled in by the type checker
+#[derive(Debug, Clone, PartialEq)]
+pub struct Expression {
+    pub kind: ExpressionKind,
+    pub ty: Option<Type>,
+    pub span: Span
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExpressionKind {
     Constant(ConstantValue),
-    Unary(UnaryExpressionType, Box<Expression>)
+    // String literals, without their terminating null character
+    String(Vec<u8>),
+    Variable(String),
+    Unary(UnaryExpressionType, Box<Expression>),
+    Binary(BinaryExpressionType, Box<Expression>, Box<Expression>),
+    Assignment(Box<Expression>, Box<Expression>),
+    // The function is a function designator, or a pointer to a function
+    FunctionCall(Box<Expression>, Vec<Expression>),
+    AddressOf(Box<Expression>),
+    Dereference(Box<Expression>),
+    Subscript(Box<Expression>, Box<Expression>),
+    // Member access, with `.` and `->`
+    Dot(Box<Expression>, String),
+    Arrow(Box<Expression>, String),
+    // Explicit casts, as well as the implicit conversions inserted by the type checker
+    Cast(Type, Box<Expression>),
+    // `sizeof expr`, `sizeof(type)` and `_Alignof(type)`, which the resolver
+    // replaces by their value without evaluating the operand
+    SizeOf(Box<Expression>),
+    SizeOfType(Type),
+    AlignOf(Type),
+    // `__builtin_va_start(ap, last)` and `__builtin_va_arg(ap, type)`, on a `va_list`;
+    // `__builtin_va_end` and `__builtin_va_copy` are rewritten by the parser
+    VaStart(Box<Expression>),
+    VaArg(Box<Expression>, Type),
+    // Compound literals, as in `(struct point){1, 2}`, create an unnamed object initialized
+    // like a variable. The resolver names it, and replaces those at file scope, which
+    // have static storage duration, by the static object it creates for them
+    CompoundLiteral(Box<VariableDeclaration>)
 }
 
-#[derive(Debug, Clone)]
+impl Expression {
+    // An expression that has not been type checked yet
+    pub fn new(kind: ExpressionKind, span: Span) -> Self {
+        return Self { kind, ty: None, span };
+    }
+
+    pub fn typed(kind: ExpressionKind, ty: Type, span: Span) -> Self {
+        return Self { kind, ty: Some(ty), span };
+    }
+
+    pub fn ty(&self) -> &Type {
+        return self.ty.as_ref().expect("Internal type error -- expression has not been type checked");
+    }
+
+    // Expressions that designate an object, which can be assigned to
+    // and have their address taken
+    pub fn is_lvalue(&self) -> bool {
+        match &self.kind {
+            ExpressionKind::Variable(_) | ExpressionKind::Dereference(_) | ExpressionKind::Subscript(_, _) | ExpressionKind::Arrow(_, _) |
+            ExpressionKind::CompoundLiteral(_) => true,
+            // Members of an rvalue structure, e.g. `f().x`, are not lvalues
+            ExpressionKind::Dot(inner, _) => inner.is_lvalue(),
+            _ => false
+        }
+    }
+}
+
+// Arithmetic constants, and their type: that of their literal (see `from_literal`),
+// or the type an operation on constants has, as they are folded
+#[derive(Debug, Clone, PartialEq)]
 pub enum ConstantValue {
-    Int(String)
+    Int(i32),
+    UInt(u32),
+    Long(i64),
+    ULong(u64),
+    LongLong(i64),
+    ULongLong(u64),
+    Float(f32),
+    Double(f64)
+}
+
+// Why a number literal has no value
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LiteralError {
+    Invalid,
+    // Too large for every type the literal may have, e.g. `18446744073709551616`
+    OutOfRange
 }
+
 impl ConstantValue {
-    pub fn inner(&self) -> &String {
+    pub fn ty(&self) -> Type {
         match self {
-            Self::Int(s) => s
+            Self::Int(_) => Type::Int,
+            Self::UInt(_) => Type::UInt,
+            Self::Long(_) => Type::Long,
+            Self::ULong(_) => Type::ULong,
+            Self::LongLong(_) => Type::LongLong,
+            Self::ULongLong(_) => Type::ULongLong,
+            Self::Float(_) => Type::Float,
+            Self::Double(_) => Type::Double
+        }
+    }
+
+    // Constant of the given type, if there is a literal for that type. The
+    // value is converted to the type, as in `(unsigned)-1`
+    pub fn from_value(value: i128, ty: &Type) -> Option<Self> {
+        match ty {
+            Type::Int => Some(Self::Int(value as i32)),
+            Type::UInt => Some(Self::UInt(value as u32)),
+            Type::Long => Some(Self::Long(value as i64)),
+            Type::ULong => Some(Self::ULong(value as u64)),
+            Type::LongLong => Some(Self::LongLong(value as i64)),
+            Type::ULongLong => Some(Self::ULongLong(value as u64)),
+            _ => None
+        }
+    }
+
+    // Floating constant of the given type, rounded to its precision
+    pub fn from_float(value: f64, ty: &Type) -> Option<Self> {
+        match ty {
+            Type::Float => Some(Self::Float(value as f32)),
+            Type::Double => Some(Self::Double(value)),
+            _ => None
+        }
+    }
+
+    pub fn is_floating(&self) -> bool {
+        matches!(self, Self::Float(_) | Self::Double(_))
+    }
+
+    // Value of an integer constant
+    pub fn value(&self) -> Option<i128> {
+        match *self {
+            Self::Int(v) => Some(v as i128),
+            Self::UInt(v) => Some(v as i128),
+            Self::Long(v) | Self::LongLong(v) => Some(v as i128),
+            Self::ULong(v) | Self::ULongLong(v) => Some(v as i128),
+            Self::Float(_) | Self::Double(_) => None
+        }
+    }
+
+    // Value of a constant as a floating value, exact for floating constants
+    pub fn to_float(&self) -> f64 {
+        match *self {
+            Self::Float(v) => v as f64,
+            Self::Double(v) => v,
+            _ => self.value().unwrap_or_default() as f64
+        }
+    }
+
+    // Value of the constant once converted to an integer type. Floating values are
+    // truncated, and have no conversion if they don't fit in the integer type,
+    // except for `_Bool`, which any value converts to
+    pub fn integer_value(&self, to: &Type) -> Option<i128> {
+        let Some(value) = self.value() else {
+            let value = self.to_float();
+            if *to.unqualified() == Type::Bool {
+                return Some((value != 0.0) as i128);
+            }
+            if !value.is_finite() {
+                return None;
+            }
+
+            let truncated = value.trunc() as i128;
+            return (to.wrap(truncated) == truncated).then_some(truncated);
+        };
+
+        Some(to.wrap(value))
+    }
+
+    // Converts the constant to another arithmetic type, as the conversion would at runtime
+    pub fn convert(&self, to: &Type) -> Option<Self> {
+        if to.is_integer() {
+            return Self::from_value(self.integer_value(to)?, to);
+        }
+        return Self::from_float(self.to_float(), to);
+    }
+
+    // Parses a literal such as `10UL`, `0x1f` or `2.5f`. Integer literals take the first
+    // type of those allowed by their suffix that can represent their value, where octal
+    // and hexadecimal literals, unlike decimal ones, may also be unsigned, as in C11 6.4.4.1.
+    // Literals with a decimal point or an exponent are floating, and `double` unless
+    // suffixed with `f`
+    pub fn from_literal(literal: &str) -> Result<Self, LiteralError> {
+        let (radix, digits) = match literal.get(..2) {
+            Some("0x" | "0X") => (16, &literal[2..]),
+            _ if literal.starts_with('0') && literal.len() > 1 => (8, &literal[1..]),
+            _ => (10, literal)
+        };
+        if radix != 16 && literal.contains(['.', 'e', 'E']) {
+            let (digits, ty) = match literal.strip_suffix(['f', 'F']) {
+                Some(digits) => (digits, Type::Float),
+                None => (literal, Type::Double)
+            };
+            let value: f64 = digits.parse().map_err(|_| LiteralError::Invalid)?;
+            let constant = Self::from_float(value, &ty).ok_or(LiteralError::Invalid)?;
+            if constant.to_float().is_infinite() {
+                return Err(LiteralError::OutOfRange);
+            }
+            return Ok(constant);
+        }
+
+        let split = digits.find(|c: char| !c.is_digit(radix)).unwrap_or(digits.len());
+        let (digits, suffix) = digits.split_at(split);
+        if digits.is_empty() && radix == 16 {
+            return Err(LiteralError::Invalid);
+        }
+        let value = match digits {
+            "" => 0,
+            digits => u128::from_str_radix(digits, radix).map_err(|_| LiteralError::OutOfRange)?
+        };
+
+        use Type as T;
+        let decimal = radix == 10;
+        let candidates: &[Type] = match (suffix.to_ascii_lowercase().as_str(), decimal) {
+            ("", true) => &[T::Int, T::Long, T::LongLong],
+            ("", false) => &[T::Int, T::UInt, T::Long, T::ULong, T::LongLong, T::ULongLong],
+            ("u", _) => &[T::UInt, T::ULong, T::ULongLong],
+            ("l", true) => &[T::Long, T::LongLong],
+            ("l", false) => &[T::Long, T::ULong, T::LongLong, T::ULongLong],
+            ("ul" | "lu", _) => &[T::ULong, T::ULongLong],
+            ("ll", true) => &[T::LongLong],
+            ("ll", false) => &[T::LongLong, T::ULongLong],
+            ("ull" | "llu", _) => &[T::ULongLong],
+            _ => return Err(LiteralError::Invalid)
+        };
+        let ty = candidates.iter().find(|ty| ty.wrap(value as i128) == value as i128).ok_or(LiteralError::OutOfRange)?;
+        return Ok(Self::from_value(value as i128, ty).expect("Integer literals have integer types"));
+    }
+}
+
+impl std::fmt::Display for ConstantValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Int(v) => write!(f, "{}", v),
+            Self::UInt(v) => write!(f, "{}", v),
+            Self::Long(v) | Self::LongLong(v) => write!(f, "{}", v),
+            Self::ULong(v) | Self::ULongLong(v) => write!(f, "{}", v),
+            Self::Float(v) => write!(f, "{}", v),
+            Self::Double(v) => write!(f, "{}", v)
         }
     }
 }
 
 // Unary expressions contained within statements
 // and can be complements or negations
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum UnaryExpressionType {
     Complement, Negation
 }
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BinaryExpressionType {
+    Add, Subtract, Multiply, Divide, Remainder,
+    BitAnd, BitOr, BitXor, ShiftLeft, ShiftRight,
+    Equal, NotEqual, Less, LessEqual, Greater, GreaterEqual
+}
+impl BinaryExpressionType {
+    pub fn is_comparison(&self) -> bool {
+        use BinaryExpressionType as B;
+        matches!(self, B::Equal | B::NotEqual | B::Less | B::LessEqual | B::Greater | B::GreaterEqual)
+    }
+
+    pub fn is_shift(&self) -> bool {
+        matches!(self, Self::ShiftLeft | Self::ShiftRight)
+    }
+}
@@ -0,0 +1,209 @@
+// Symbol table shared by the semantic analysis passes
+// and the later compilation stages //
+#![allow(dead_code)]
+use std::collections::HashMap;
+use super::symbols::ConstantValue;
+use super::types::{Type, Qualifiers};
+
+// The initial value of an object with static storage duration
+#[derive(Debug, Clone, PartialEq)]
+pub enum InitialValue {
+    // Declared without initializer or `extern` at file scope,
+    // it becomes a zero-initialized definition if no other
+    // declaration defines it
+    Tentative,
+    Initial(Vec<StaticInit>),
+    // Declared `extern` without an initializer; it is defined elsewhere
+    NoInitializer
+}
+
+// Pieces of the initial value of a static object, in memory order: either
+// a scalar of the given type, a number of zero bytes, or an address
+#[derive(Debug, Clone, PartialEq)]
+pub enum StaticInit {
+    // The constant is already converted to the type; there are no constants of
+    // character and short types, so their values are held by an `int` constant
+    Value(Type, ConstantValue),
+    Zero(usize),
+    // The address of a function or static object, plus an offset in bytes, as in `&g[2]`
+    Address(String, i64)
+}
+
+impl StaticInit {
+    // Scalar of an integer type, with the value converted to it
+    pub fn integer(ty: &Type, value: i128) -> Self {
+        let constant = ConstantValue::from_value(ty.wrap(value), &ty.promote()).expect("Internal resolver error -- static value is not an integer");
+        return Self::Value(ty.unqualified().clone(), constant);
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum SymbolAttributes {
+    // `inline` makes calls to a function candidates for inlining, and when every file-scope
+    // declaration of an external function is `inline` without `extern`, its definition is
+    // an inline definition, which is left to another translation unit to define externally
+    Function { defined: bool, global: bool, inline: bool, inline_only: bool },
+    Static { init: InitialValue, global: bool },
+    Local
+}
+
+// Layout of a complete structure or union type
+#[derive(Debug, Clone)]
+pub struct StructDefinition {
+    pub size: usize,
+    pub alignment: usize,
+    pub members: Vec<Member>
+}
+
+#[derive(Debug, Clone)]
+pub struct Member {
+    pub name: String,
+    pub ty: Type,
+    pub offset: usize,
+    pub bit_field: Option<BitField>
+}
+
+// A bit-field is part of a storage unit of the type of its member, at the offset of
+// the member: it takes up `width` bits of it, starting `offset` bits from its least
+// significant bit. Other members may share the storage unit, as in `struct { char c;
+// int x : 4; }`, where `x` is bits 8 to 11 of the `int` at offset 0
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BitField {
+    pub offset: usize,
+    pub width: usize
+}
+
+#[derive(Debug, Clone)]
+pub struct Symbol {
+    pub ty: Type,
+    pub attributes: SymbolAttributes
+}
+
+// Locals, like tags, are renamed to be unique, as in `x.3`; messages use the name they
+// had in the source, which is the whole name at file scope, as C names have no `.`
+pub fn source_name(name: &str) -> &str {
+    return name.split('.').next().unwrap_or_default();
+}
+
+// Symbols are kept in insertion order, so that the generated
+// code does not depend on the ordering of the hash map
+#[derive(Debug, Clone, Default)]
+pub struct SymbolTable {
+    entries: HashMap<String, Symbol>,
+    order: Vec<String>,
+    // Definitions of structure and union types, by unique tag
+    structs: HashMap<String, StructDefinition>
+}
+
+impl SymbolTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Symbol> {
+        self.entries.get(name)
+    }
+
+    pub fn insert(&mut self, name: String, symbol: Symbol) {
+        if !self.entries.contains_key(&name) {
+            self.order.push(name.clone());
+        }
+        self.entries.insert(name, symbol);
+    }
+
+    pub fn is_static(&self, name: &str) -> bool {
+        matches!(self.get(name), Some(Symbol { attributes: SymbolAttributes::Static { .. }, .. }))
+    }
+
+    // Type of a symbol that must exist, as it was declared before its use
+    pub fn type_of(&self, name: &str) -> &Type {
+        match self.get(name) {
+            Some(symbol) => &symbol.ty,
+            None => panic!("Internal error -- `{}` missing from symbol table", name)
+        }
+    }
+
+    pub fn define_struct(&mut self, tag: String, definition: StructDefinition) {
+        self.structs.insert(tag, definition);
+    }
+
+    pub fn get_struct(&self, tag: &str) -> Option<&StructDefinition> {
+        self.structs.get(tag)
+    }
+
+    // Member of a structure type, and the offset at which it is stored
+    pub fn member(&self, ty: &Type, name: &str) -> Option<&Member> {
+        match ty.unqualified() {
+            Type::Structure(_, tag) => self.get_struct(tag)?.members.iter().find(|m| m.name == name),
+            _ => None
+        }
+    }
+
+    // Incomplete types are `void`, structures without a definition, arrays declared
+    // without a size and arrays of them; objects of those types cannot be created
+    pub fn is_complete(&self, ty: &Type) -> bool {
+        match ty {
+            Type::Void | Type::Array(_, 0) => false,
+            Type::Structure(_, tag) => self.structs.contains_key(tag),
+            Type::Array(element, _) | Type::VariableArray(element, _) | Type::Qualified(_, element) => self.is_complete(element),
+            _ => true
+        }
+    }
+
+    // The size of variable-length arrays is only known at runtime
+    pub fn size_of(&self, ty: &Type) -> usize {
+        return self.checked_size_of(ty).expect("Internal error -- array is too large");
+    }
+
+    // Size of a complete type, unless it is an array too large for its size to be counted
+    pub fn checked_size_of(&self, ty: &Type) -> Option<usize> {
+        match ty {
+            Type::Structure(_, tag) => Some(self.struct_definition(tag).size),
+            Type::Array(element, count) => self.checked_size_of(element)?.checked_mul(*count),
+            Type::Qualified(_, inner) => self.checked_size_of(inner),
+            _ => Some(ty.size())
+        }
+    }
+
+    pub fn alignment_of(&self, ty: &Type) -> usize {
+        match ty {
+            Type::Structure(_, tag) => self.struct_definition(tag).alignment,
+            Type::Array(element, _) | Type::VariableArray(element, _) | Type::Qualified(_, element) => self.alignment_of(element),
+            _ => ty.alignment()
+        }
+    }
+
+    // Objects with a `const` member can't be assigned to as a whole
+    pub fn is_read_only(&self, ty: &Type) -> bool {
+        return self.any_part(ty, &|qualifiers| qualifiers.constant);
+    }
+
+    // Accessing an object as a whole also accesses any volatile member of it
+    pub fn is_volatile(&self, ty: &Type) -> bool {
+        return self.any_part(ty, &|qualifiers| qualifiers.volatile);
+    }
+
+    // Whether the qualifiers of an object, or of any of its members, satisfy a predicate
+    fn any_part(&self, ty: &Type, predicate: &dyn Fn(Qualifiers) -> bool) -> bool {
+        if predicate(ty.qualifiers()) {
+            return true;
+        }
+
+        match ty.unqualified() {
+            Type::Structure(_, tag) => self.get_struct(tag).is_some_and(|definition| definition.members.iter().any(|m| self.any_part(&m.ty, predicate))),
+            Type::Array(element, _) | Type::VariableArray(element, _) => self.any_part(element, predicate),
+            _ => false
+        }
+    }
+
+    fn struct_definition(&self, tag: &str) -> &StructDefinition {
+        match self.get_struct(tag) {
+            Some(definition) => definition,
+            None => panic!("Internal error -- incomplete type `{}` has no layout", tag)
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &Symbol)> {
+        self.order.iter().map(|name| (name, &self.entries[name]))
+    }
+}
@@ -30,12 +30,12 @@ mod ast_tests {
                 assert_eq!(d.name, "main");
                 assert_eq!(d.statements.len(), 1);
 
-                match &d.statements[0] {
-                    Statement::Return(exp) => {
+                match &d.statements[0].kind {
+                    StatementKind::Return(exp) => {
                         // let exp = exp.as_ref().expect("Return should have expression");
                         match exp {
-                            Expression::Constant(ConstantValue::Int(int_val)) => {
-                                assert_eq!(*int_val, "2".to_string());
+                            Expression { kind: ExpressionKind::Constant(ConstantValue::Int(int_val)), .. } => {
+                                assert_eq!(*int_val, 2);
                             }
                             _ => { panic!("Expression should be of type Int"); }
                         }
@@ -92,12 +92,12 @@ mod ast_tests {
                 assert_eq!(d.name, "custom");
                 assert_eq!(d.statements.len(), 2);
 
-                match &d.statements[0] {
-                    Statement::Return(exp) => {
+                match &d.statements[0].kind {
+                    StatementKind::Return(exp) => {
                         // let exp = exp.as_ref().expect("Return should have expression");
                         match exp {
-                            Expression::Constant(ConstantValue::Int(int_val)) => {
-                                assert_eq!(*int_val, "500".to_string());
+                            Expression { kind: ExpressionKind::Constant(ConstantValue::Int(int_val)), .. } => {
+                                assert_eq!(*int_val, 500);
                             },
                             _ => { panic!("Expression should be of type Int"); }
                         }
@@ -108,15 +108,16 @@ mod ast_tests {
                     _ => { panic!("Statement should be of type Return"); }
                 }
 
-                match &d.statements[1] {
-                    Statement::Return(exp) => {
+                match &d.statements[1].kind {
+                    StatementKind::Return(exp) => {
                         match exp {
-                            Expression::Constant(ConstantValue::Int(int_val)) => {
-                                assert_eq!(*int_val, "10".to_string());
+                            Expression { kind: ExpressionKind::Constant(ConstantValue::Int(int_val)), .. } => {
+                                assert_eq!(*int_val, 10);
                             },
                             _ => { panic!("Expression should be of type Return(Int)"); }
                         }
                     }
+                    _ => { panic!("Statement should be of type Return"); }
                 }
             },
             
@@ -130,15 +131,16 @@ mod ast_tests {
                 assert_eq!(d.name, "two");
                 assert_eq!(d.statements.len(), 1);
 
-                match &d.statements[0] {
-                    Statement::Return(exp) => {
+                match &d.statements[0].kind {
+                    StatementKind::Return(exp) => {
                         match exp {
-                            Expression::Constant(ConstantValue::Int(int_val)) => {
-                                assert_eq!(*int_val, "5".to_string());
+                            Expression { kind: ExpressionKind::Constant(ConstantValue::Int(int_val)), .. } => {
+                                assert_eq!(*int_val, 5);
                             }
                             _ => { panic!("Expression should be of type Return(Int)"); }
                         }
                     }
+                    _ => { panic!("Statement should be of type Return"); }
                 }
             },
             
@@ -259,7 +261,7 @@ mod ast_tests {
         check_m2_negation_result(&ast);
     }
 
-    fn check_m2_negation_result(ast: &Vec<Declaration>) {
+    fn check_m2_negation_result(ast: &[Declaration]) {
         assert_eq!(ast.len(), 1);
 
         match &ast[0] {
@@ -267,19 +269,19 @@ mod ast_tests {
                 assert_eq!(d.name, "main");
                 assert_eq!(d.statements.len(), 1);
 
-                match &d.statements[0] {
-                    Statement::Return(s1) => {
+                match &d.statements[0].kind {
+                    StatementKind::Return(s1) => {
                         match s1 {
-                            Expression::Unary(kind, s2) => {
+                            Expression { kind: ExpressionKind::Unary(kind, s2), .. } => {
                                 assert!(matches!(kind, UnaryExpressionType::Negation));
 
                                 match *s2.clone() {
-                                    Expression::Unary(kind, s3) => {
+                                    Expression { kind: ExpressionKind::Unary(kind, s3), .. } => {
                                         assert!(matches!(kind, UnaryExpressionType::Complement));
 
                                         match *s3.clone() {
-                                            Expression::Constant(ConstantValue::Int(int_val)) => {
-                                                assert_eq!(*int_val, "2".to_string());
+                                            Expression { kind: ExpressionKind::Constant(ConstantValue::Int(int_val)), .. } => {
+                                                assert_eq!(int_val, 2);
                                             }
                                             _ => { panic!("Expected Constant Expression 3"); }
                                         }
@@ -328,4 +330,1359 @@ mod ast_tests {
         assert!(parser.parse().is_err());
     }
 
+    // Lexes a source string into the parser's input
+    fn tokenize(src: &str) -> (String, Vec<Token>) {
+        let mut lexer = crate::lexer::Lexer::load_test_str(src);
+        let mut tokens = vec![];
+        loop {
+            let token = lexer.next();
+            if token.tag == Tag::Eof {
+                break;
+            }
+            tokens.push(token);
+        }
+
+        (src.to_string(), tokens)
+    }
+
+    fn resolve_str(src: &str) -> Result<(Program, crate::ast::table::SymbolTable), ASTError> {
+        let (buffer, tokens) = tokenize(src);
+        let program = ASTParser::new(buffer, tokens).parse().map_err(|mut errors| errors.remove(0))?;
+        crate::ast::resolve::resolve(program).map(|(program, symbols, _)| (program, symbols))
+    }
+
+    #[test]
+    fn test_file_scope_declarations() {
+        let (buffer, tokens) = tokenize("static int x = 3;\nextern int f(int a, int b);\nint y;");
+        let ast = ASTParser::new(buffer, tokens).parse().expect("Unable to generate AST");
+        assert_eq!(ast.len(), 3);
+
+        match &ast[0] {
+            Declaration::Variable(v) => {
+                assert_eq!(v.name, "x");
+                assert_eq!(v.storage_class, Some(StorageClass::Static));
+                assert!(matches!(&v.initializer, Some(Initializer::Single(Expression { kind: ExpressionKind::Constant(ConstantValue::Int(3)), .. }))));
+            },
+            _ => { panic!("AST root node 0 should match Declaration::Variable"); }
+        }
+
+        match &ast[1] {
+            Declaration::Function(d) => {
+                assert_eq!(d.name, "f");
+                assert_eq!(d.params, vec!["a".to_string(), "b".to_string()]);
+                assert_eq!(d.storage_class, Some(StorageClass::Extern));
+                assert!(!d.has_body);
+            },
+            _ => { panic!("AST root node 1 should match Declaration::Function"); }
+        }
+
+        match &ast[2] {
+            Declaration::Variable(v) => {
+                assert_eq!(v.name, "y");
+                assert_eq!(v.storage_class, None);
+                assert!(v.initializer.is_none());
+            },
+            _ => { panic!("AST root node 2 should match Declaration::Variable"); }
+        }
+    }
+
+    #[test]
+    fn test_linkage() {
+        use crate::ast::table::{InitialValue, StaticInit, SymbolAttributes};
+
+        let (_, symbols) = resolve_str(
+            "static int a;\nint a2 = 1;\nint t;\nint t;\nstatic int f(void);\nint f(void) { extern int a; static int s; return a; }"
+        ).expect("Program should resolve");
+
+        assert!(matches!(symbols.get("a").unwrap().attributes, SymbolAttributes::Static { init: InitialValue::Tentative, global: false }));
+        assert!(matches!(symbols.get("t").unwrap().attributes, SymbolAttributes::Static { init: InitialValue::Tentative, global: true }));
+        assert!(matches!(symbols.get("f").unwrap().attributes, SymbolAttributes::Function { defined: true, global: false, .. }));
+        match &symbols.get("a2").unwrap().attributes {
+            SymbolAttributes::Static { init: InitialValue::Initial(i), global: true } => {
+                assert_eq!(i, &vec![StaticInit::Value(Type::Int, ConstantValue::Int(1))]);
+            },
+            _ => { panic!("`a2` should be an initialized global"); }
+        }
+
+        // Block-scope statics are renamed, and have no linkage
+        let (name, symbol) = symbols.iter().find(|(name, _)| name.starts_with("s.")).expect("Static local should be in the table");
+        assert_ne!(name, "s");
+        assert!(matches!(symbol.attributes, SymbolAttributes::Static { global: false, .. }));
+    }
+
+    #[test]
+    fn test_conflicting_linkage() {
+        assert!(resolve_str("int x;\nstatic int x;").is_err());
+        assert!(resolve_str("static int x;\nint x;").is_err());
+        assert!(resolve_str("int x = 1;\nint x = 2;").is_err());
+        assert!(resolve_str("int f(void) { static int s = f; return 0; }").is_err());
+        assert!(resolve_str("int f(void) { extern int e = 1; return 0; }").is_err());
+        assert!(resolve_str("int f(void) { static int g(void); return 0; }").is_err());
+        assert!(resolve_str("static int x;\nextern int x;").is_ok());
+    }
+
+    #[test]
+    fn test_binary_precedence() {
+        let (buffer, tokens) = tokenize("int x = 1 + 2 * 3 << 1 == 14;");
+        let ast = ASTParser::new(buffer, tokens).parse().expect("Program should parse");
+
+        let init = match &ast[0] {
+            Declaration::Variable(v) => match &v.initializer {
+                Some(Initializer::Single(e)) => e,
+                _ => { panic!("`x` should have a single initializer"); }
+            },
+            _ => { panic!("AST root node 0 should match Declaration::Variable"); }
+        };
+
+        // ((1 + (2 * 3)) << 1) == 14
+        match init {
+            Expression { kind: ExpressionKind::Binary(BinaryExpressionType::Equal, left, _), .. } => match left.as_ref() {
+                Expression { kind: ExpressionKind::Binary(BinaryExpressionType::ShiftLeft, left, _), .. } => match left.as_ref() {
+                    Expression { kind: ExpressionKind::Binary(BinaryExpressionType::Add, _, right), .. } => {
+                        assert!(matches!(right.as_ref(), Expression { kind: ExpressionKind::Binary(BinaryExpressionType::Multiply, _, _), .. }));
+                    },
+                    _ => { panic!("Shift operand should be an addition"); }
+                },
+                _ => { panic!("Equality operand should be a shift"); }
+            },
+            _ => { panic!("Initializer should be an equality"); }
+        }
+    }
+
+    #[test]
+    fn test_type_specifiers() {
+        let (_, symbols) = resolve_str(
+            "unsigned long int a;\nlong long b;\nshort unsigned c;\nsigned char d;\nchar e;\nunsigned f;"
+        ).expect("Program should resolve");
+
+        assert_eq!(symbols.type_of("a"), &Type::ULong);
+        assert_eq!(symbols.type_of("b"), &Type::LongLong);
+        assert_eq!(symbols.type_of("c"), &Type::UShort);
+        assert_eq!(symbols.type_of("d"), &Type::SChar);
+        assert_eq!(symbols.type_of("e"), &Type::Char);
+        assert_eq!(symbols.type_of("f"), &Type::UInt);
+
+        assert!(resolve_str("long short x;").is_err());
+        assert!(resolve_str("signed unsigned x;").is_err());
+        assert!(resolve_str("long long long x;").is_err());
+        assert!(resolve_str("int int x;").is_err());
+    }
+
+    #[test]
+    fn test_usual_arithmetic_conversions() {
+        assert_eq!(Type::common(&Type::Int, &Type::UInt), Type::UInt);
+        assert_eq!(Type::common(&Type::Char, &Type::Short), Type::Int);
+        assert_eq!(Type::common(&Type::UInt, &Type::Long), Type::Long);
+        assert_eq!(Type::common(&Type::Long, &Type::ULongLong), Type::ULongLong);
+        assert_eq!(Type::UChar.promote(), Type::Int);
+        assert_eq!(Type::Char.wrap(300), 44);
+        assert_eq!(Type::UInt.wrap(-1), 4294967295);
+
+        // File-scope initializers are converted to the type of the variable
+        use crate::ast::table::{InitialValue, StaticInit, SymbolAttributes};
+        let (_, symbols) = resolve_str("unsigned char c = 300;\nlong l = 2147483647 + 1;").expect("Program should resolve");
+        assert!(matches!(&symbols.get("c").unwrap().attributes,
+            SymbolAttributes::Static { init: InitialValue::Initial(i), .. } if i == &vec![StaticInit::Value(Type::UChar, ConstantValue::Int(44))]));
+        assert!(matches!(&symbols.get("l").unwrap().attributes,
+            SymbolAttributes::Static { init: InitialValue::Initial(i), .. } if i == &vec![StaticInit::Value(Type::Long, ConstantValue::Long(-2147483648))]));
+    }
+
+    #[test]
+    fn test_implicit_conversions() {
+        let (program, symbols) = resolve_str("long f(int i) { long x = i; return x + i; }").expect("Program should resolve");
+        let (program, _) = crate::ast::typecheck::typecheck(program, &symbols).expect("Program should typecheck");
+
+        let statements = match &program[0] {
+            Declaration::Function(f) => &f.statements,
+            _ => { panic!("AST root node 0 should match Declaration::Function"); }
+        };
+
+        match &statements[0].kind {
+            StatementKind::Declaration(Declaration::Variable(v)) => {
+                assert!(matches!(&v.initializer, Some(Initializer::Single(Expression { kind: ExpressionKind::Cast(Type::Long, _), .. }))));
+            },
+            _ => { panic!("Statement 0 should be a declaration"); }
+        }
+        match &statements[1].kind {
+            StatementKind::Return(Expression { kind: ExpressionKind::Binary(BinaryExpressionType::Add, _, right), .. }) => {
+                assert!(matches!(right.as_ref(), Expression { kind: ExpressionKind::Cast(Type::Long, _), .. }));
+            },
+            _ => { panic!("Statement 1 should return a sum"); }
+        }
+    }
+
+    #[test]
+    fn test_pointer_declarators() {
+        let (_, symbols) = resolve_str(
+            "int *p;\nlong **pp;\nvoid *v;\nint *(f)(int *a, char **b);"
+        ).expect("Program should resolve");
+
+        let pointer = |ty: Type| Type::Pointer(Box::new(ty));
+        assert_eq!(symbols.type_of("p"), &pointer(Type::Int));
+        assert_eq!(symbols.type_of("pp"), &pointer(pointer(Type::Long)));
+        assert_eq!(symbols.type_of("v"), &pointer(Type::Void));
+        assert_eq!(symbols.type_of("f"), &Type::Function(FunctionType {
+            params: vec![pointer(Type::Int), pointer(pointer(Type::Char))],
+            ret: Box::new(pointer(Type::Int)),
+            variadic: false
+        }));
+
+        assert!(resolve_str("int *p = 0;").is_ok());
+        assert!(resolve_str("int *p = 1;").is_err());
+    }
+
+    #[test]
+    fn test_pointer_typecheck() {
+        let typecheck = |src: &str| {
+            let (program, symbols) = resolve_str(src)?;
+            crate::ast::typecheck::typecheck(program, &symbols).map(|(program, _)| program)
+        };
+
+        // Integers added to pointers are converted to long, and the pointer moved to the left
+        let program = typecheck("int *f(int *p, int i) { return i + p; }").expect("Program should typecheck");
+        match &program[0] {
+            Declaration::Function(f) => match &f.statements[0].kind {
+                StatementKind::Return(Expression { kind: ExpressionKind::Binary(BinaryExpressionType::Add, left, right), .. }) => {
+                    assert!(matches!(left.as_ref(), Expression { kind: ExpressionKind::Variable(_), .. }));
+                    assert!(matches!(right.as_ref(), Expression { kind: ExpressionKind::Cast(Type::Long, _), .. }));
+                },
+                _ => { panic!("Statement 0 should return a sum"); }
+            },
+            _ => { panic!("AST root node 0 should match Declaration::Function"); }
+        }
+
+        assert!(typecheck("long f(int *p, int *q) { return p - q; }").is_ok());
+        assert!(typecheck("int f(int *p) { return p == 0; }").is_ok());
+        assert!(typecheck("int f(int *p) { void *v = p; int *q = v; return *q; }").is_ok());
+        assert!(typecheck("int f(int *p, int *q) { return p + q; }").is_err());
+        assert!(typecheck("int f(int *p, long *q) { return p == q; }").is_err());
+        assert!(typecheck("int f(int x) { int *p = x; return 0; }").is_err());
+        assert!(typecheck("int f(int x) { return *x; }").is_err());
+        assert!(typecheck("int f(void) { return *&3; }").is_err());
+    }
+
+    #[test]
+    fn test_array_declarators() {
+        use crate::ast::table::{InitialValue, StaticInit, SymbolAttributes};
+
+        let (_, symbols) = resolve_str(
+            "int a[3] = {1, 2};\nlong m[2][3];\nint *p[4];\nint (*q)[4];\nint f(char *argv[], int b[][2]);"
+        ).expect("Program should resolve");
+
+        let pointer = |ty: Type| Type::Pointer(Box::new(ty));
+        let array = |ty: Type, n: usize| Type::Array(Box::new(ty), n);
+        assert_eq!(symbols.type_of("a"), &array(Type::Int, 3));
+        assert_eq!(symbols.type_of("m"), &array(array(Type::Long, 3), 2));
+        assert_eq!(symbols.type_of("p"), &array(pointer(Type::Int), 4));
+        assert_eq!(symbols.type_of("q"), &pointer(array(Type::Int, 4)));
+        assert_eq!(symbols.type_of("m").size(), 48);
+
+        // Array parameters are adjusted to pointers
+        assert_eq!(symbols.type_of("f"), &Type::Function(FunctionType {
+            params: vec![pointer(pointer(Type::Char)), pointer(array(Type::Int, 2))],
+            ret: Box::new(Type::Int),
+            variadic: false
+        }));
+
+        // Missing elements of static arrays are zero-filled
+        match &symbols.get("a").unwrap().attributes {
+            SymbolAttributes::Static { init: InitialValue::Initial(init), .. } => {
+                assert_eq!(init, &vec![
+                    StaticInit::Value(Type::Int, ConstantValue::Int(1)),
+                    StaticInit::Value(Type::Int, ConstantValue::Int(2)),
+                    StaticInit::Zero(4)
+                ]);
+            },
+            _ => { panic!("`a` should be an initialized static"); }
+        }
+
+        assert!(resolve_str("int a[2] = {1, 2, 3};").is_err());
+        assert!(resolve_str("int a[2] = 1;").is_err());
+        assert!(resolve_str("int a[2][];").is_err());
+
+        // Arrays declared without a size are completed by a later declaration, or have
+        // one element if they are tentatively defined and never completed
+        let (_, symbols) = resolve_str("extern int e[];\nint a[];\nint b[];\nint b[3];\nint f(void) { extern int e[]; return e[1]; }\nint e[4];")
+            .expect("Program should resolve");
+        assert_eq!(symbols.type_of("e"), &Type::Array(Box::new(Type::Int), 4));
+        assert_eq!(symbols.type_of("a"), &Type::Array(Box::new(Type::Int), 1));
+        assert_eq!(symbols.type_of("b"), &Type::Array(Box::new(Type::Int), 3));
+        assert!(resolve_str("int b[2];\nint b[3];").is_err());
+        assert!(resolve_str("extern int e[];\nunsigned long n = sizeof e;").is_err());
+        let result = resolve_str("int f(void) {\n  int a[];\n  return 0;\n}").and_then(|(program, symbols)| crate::ast::typecheck::typecheck(program, &symbols));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_array_typecheck() {
+        let typecheck = |src: &str| {
+            let (program, symbols) = resolve_str(src)?;
+            crate::ast::typecheck::typecheck(program, &symbols).map(|(program, _)| program)
+        };
+
+        // Automatic arrays are padded with zero initializers
+        let program = typecheck("int f(void) { int a[2][2] = {{1}}; return a[1][0]; }").expect("Program should typecheck");
+        match &program[0] {
+            Declaration::Function(f) => match &f.statements[0].kind {
+                StatementKind::Declaration(Declaration::Variable(v)) => match &v.initializer {
+                    Some(Initializer::Compound(rows, _)) => {
+                        assert_eq!(rows.len(), 2);
+                        assert!(matches!(&rows[1], Initializer::Compound(row, _) if row.len() == 2));
+                    },
+                    _ => { panic!("`a` should have an initializer list"); }
+                },
+                _ => { panic!("Statement 0 should be a declaration"); }
+            },
+            _ => { panic!("AST root node 0 should match Declaration::Function"); }
+        }
+
+        assert!(typecheck("int f(int *p) { int a[3]; p = a; return 2[a]; }").is_ok());
+        assert!(typecheck("int f(void) { int a[3]; int (*p)[3] = &a; return (*p)[0]; }").is_ok());
+        assert!(typecheck("int f(void) { int a[3]; int b[3]; a = b; return 0; }").is_err());
+        assert!(typecheck("int f(void) { int a[3]; long *p = a; return 0; }").is_err());
+        assert!(typecheck("int f(int x) { return x[0]; }").is_err());
+    }
+
+    #[test]
+    fn test_struct_layout() {
+        use crate::ast::table::{InitialValue, StaticInit, SymbolAttributes};
+
+        let (_, symbols) = resolve_str(
+            "struct s { char c; long l; short h; };\nunion u { char c[5]; int i; } y;\nstruct s x = {1, 2};"
+        ).expect("Program should resolve");
+
+        let ty = symbols.type_of("x").clone();
+        assert_eq!((symbols.size_of(&ty), symbols.alignment_of(&ty)), (24, 8));
+        assert_eq!(symbols.member(&ty, "l").map(|m| m.offset), Some(8));
+        assert_eq!(symbols.member(&ty, "h").map(|m| m.offset), Some(16));
+
+        let ty = symbols.type_of("y").clone();
+        assert_eq!((symbols.size_of(&ty), symbols.alignment_of(&ty)), (8, 4));
+        assert_eq!(symbols.member(&ty, "i").map(|m| m.offset), Some(0));
+
+        // Padding between members, and members without an initializer, are zero-filled
+        match &symbols.get("x").unwrap().attributes {
+            SymbolAttributes::Static { init: InitialValue::Initial(init), .. } => {
+                assert_eq!(init, &vec![
+                    StaticInit::Value(Type::Char, ConstantValue::Int(1)),
+                    StaticInit::Zero(7),
+                    StaticInit::Value(Type::Long, ConstantValue::Long(2)),
+                    StaticInit::Zero(8)
+                ]);
+            },
+            _ => { panic!("`x` should be an initialized static"); }
+        }
+
+        assert!(resolve_str("struct s { int a; int a; };").is_err());
+        assert!(resolve_str("struct s { int a; }; struct s { int b; };").is_err());
+        assert!(resolve_str("struct s { struct t m; };").is_err());
+        assert!(resolve_str("struct s; union s *p;").is_err());
+        assert!(resolve_str("struct s { int a; }; struct s x = {1, 2};").is_err());
+    }
+
+    #[test]
+    fn test_struct_typecheck() {
+        let typecheck = |src: &str| {
+            let (program, symbols) = resolve_str(src)?;
+            crate::ast::typecheck::typecheck(program, &symbols).map(|(program, _)| program)
+        };
+
+        assert!(typecheck("struct s { int a; struct s *next; }; int f(struct s *p) { return p->next->a; }").is_ok());
+        assert!(typecheck("struct s { int a; }; struct s f(struct s x) { struct s y = x; y.a = 1; return y; }").is_ok());
+        assert!(typecheck("struct s; struct s *p; struct s { int a; }; int f(void) { return p->a; }").is_ok());
+        assert!(typecheck("struct s { int a; }; int f(void) { struct s; struct s *p; return p->a; }").is_err());
+        assert!(typecheck("struct s { int a; }; int f(struct s x) { return x.b; }").is_err());
+        assert!(typecheck("struct s { int a; }; int f(struct s *p) { return p.a; }").is_err());
+        assert!(typecheck("struct s { int a; }; struct t { int a; }; int f(struct s x) { struct t y = x; return 0; }").is_err());
+        assert!(typecheck("struct s x;").is_err());
+        assert!(typecheck("int f(void) { struct s x; return 0; }").is_err());
+    }
+
+    #[test]
+    fn test_enumerations() {
+        let (program, symbols) = resolve_str(
+            "enum e { A, B = 5, C };\nint a[C];\nenum e f(void) { int B = 1; return A + B; }"
+        ).expect("Program should resolve");
+
+        // Enumerators are replaced by their value, unless hidden by another declaration
+        assert_eq!(symbols.type_of("a"), &Type::Array(Box::new(Type::Int), 6));
+        match &program[2] {
+            Declaration::Function(f) => match &f.statements[1].kind {
+                StatementKind::Return(Expression { kind: ExpressionKind::Binary(_, left, right), .. }) => {
+                    assert!(matches!(left.as_ref(), Expression { kind: ExpressionKind::Constant(ConstantValue::Int(0)), .. }));
+                    assert!(matches!(right.as_ref(), Expression { kind: ExpressionKind::Variable(_), .. }));
+                },
+                _ => { panic!("Statement 1 should return a binary expression"); }
+            },
+            _ => { panic!("AST root node 2 should match Declaration::Function"); }
+        }
+
+        assert!(resolve_str("enum { A, A };").is_err());
+        assert!(resolve_str("enum { A }; int A;").is_err());
+        assert!(resolve_str("int f(void) { int A; enum { A }; return 0; }").is_err());
+        assert!(resolve_str("enum e x;").is_err());
+        assert!(resolve_str("enum e { A }; enum e { B };").is_err());
+        assert!(resolve_str("enum { A = 2147483647, B };").is_err());
+        assert!(resolve_str("int x; enum { A = x };").is_err());
+    }
+
+    #[test]
+    fn test_typedef_names() {
+        let (program, symbols) = resolve_str(
+            "typedef long T;\nT *p;\nint f(int x) { T * y; int T = 2; T * x; return (T) * (x); }"
+        ).expect("Program should resolve");
+
+        // `T * y` declares a pointer, but once `T` is hidden by a variable,
+        // `T * x` and `(T) * (x)` are multiplications
+        assert_eq!(symbols.type_of("p"), &Type::Pointer(Box::new(Type::Long)));
+        match &program[1] {
+            Declaration::Function(f) => {
+                assert!(matches!(&f.statements[0].kind, StatementKind::Declaration(Declaration::Variable(v)) if v.var_type == Type::Pointer(Box::new(Type::Long))));
+                assert!(matches!(&f.statements[2].kind, StatementKind::Expression(Expression { kind: ExpressionKind::Binary(BinaryExpressionType::Multiply, _, _), .. })));
+                assert!(matches!(&f.statements[3].kind, StatementKind::Return(Expression { kind: ExpressionKind::Binary(BinaryExpressionType::Multiply, _, _), .. })));
+            },
+            _ => { panic!("AST root node 1 should match Declaration::Function"); }
+        }
+
+        let (program, _) = resolve_str("typedef int T; long f(long x) { return (T)(x); }").expect("Program should resolve");
+        match &program[0] {
+            Declaration::Function(f) => assert!(matches!(&f.statements[0].kind, StatementKind::Return(Expression { kind: ExpressionKind::Cast(Type::Int, _), .. }))),
+            _ => { panic!("AST root node 0 should match Declaration::Function"); }
+        }
+
+        assert!(resolve_str("typedef int T; typedef int T; T x;").is_ok());
+        assert!(resolve_str("typedef int T; typedef long T;").is_err());
+        assert!(resolve_str("typedef int T; int T;").is_err());
+        assert!(resolve_str("typedef int T; unsigned T x;").is_err());
+        assert!(resolve_str("typedef int T = 1;").is_err());
+    }
+
+    #[test]
+    fn test_declarator_lists() {
+        let (program, symbols) = resolve_str(concat!(
+            "int a = 1, *p = &a, f(void);\n",
+            "typedef struct { char a, b; int c : 3, : 2, d : 4; } X, *PX;\n",
+            "_Static_assert(sizeof(X) == 4, \"bit-fields share the unit after `a` and `b`\");\n",
+            "X x;\nPX px = &x;\n",
+            "int g(void) { long m = 2, n = m; return a + n; }"
+        )).expect("Program should resolve");
+
+        assert_eq!(symbols.type_of("a"), &Type::Int);
+        assert_eq!(symbols.type_of("p"), &Type::Pointer(Box::new(Type::Int)));
+        assert!(matches!(symbols.type_of("f"), Type::Function(_)));
+        assert_eq!(symbols.type_of("px"), &Type::Pointer(Box::new(symbols.type_of("x").clone())));
+        assert!(matches!(&program[..3], [Declaration::Variable(_), Declaration::Variable(_), Declaration::Function(_)]));
+        match program.last() {
+            Some(Declaration::Function(g)) => assert_eq!(g.statements.len(), 3),
+            _ => { panic!("Last AST root node should match Declaration::Function"); }
+        }
+
+        // Only a lone function declarator may have a body
+        assert!(resolve_str("int a, f(void) { return 0; }").is_err());
+        assert!(resolve_str("int f(void) { return 0; }, a;").is_err());
+        assert!(resolve_str("int f(void) { int a = 1, a; return a; }").is_err());
+        assert!(resolve_str("int a b;").is_err());
+    }
+
+    #[test]
+    fn test_conversion_diagnostics() {
+        let typecheck = |src: &str| {
+            let (program, symbols) = resolve_str(src)?;
+            crate::ast::typecheck::typecheck(program, &symbols)
+        };
+
+        // Every expression is typed, including the conversions made explicit
+        let (program, warnings) = typecheck("long f(int *p) { return (long)p + 1; }").expect("Program should typecheck");
+        assert!(warnings.is_empty());
+        match &program[0] {
+            Declaration::Function(f) => match &f.statements[0].kind {
+                StatementKind::Return(e @ Expression { kind: ExpressionKind::Binary(_, left, right), .. }) => {
+                    assert_eq!(e.ty(), &Type::Long);
+                    assert!(matches!(left.as_ref(), Expression { kind: ExpressionKind::Cast(Type::Long, _), ty: Some(Type::Long), .. }));
+                    assert!(matches!(right.as_ref(), Expression { kind: ExpressionKind::Constant(ConstantValue::Long(_)), ty: Some(Type::Long), .. }));
+                },
+                _ => { panic!("Statement 0 should return a sum"); }
+            },
+            _ => { panic!("AST root node 0 should match Declaration::Function"); }
+        }
+
+        // Implicit narrowing is reported in assignments, returns and arguments,
+        // but not for explicit casts or constants that keep their value
+        let warnings = |src: &str| typecheck(src).expect("Program should typecheck").1;
+        assert_eq!(warnings("int f(long l) { int i; i = l; return i; }").len(), 1);
+        assert_eq!(warnings("int f(long l) { return l; }").len(), 1);
+        assert_eq!(warnings("int g(char c); int f(int i) { return g(i); }").len(), 1);
+        assert_eq!(warnings("int f(long l) { char c = 300; return (int)l + c; }").len(), 1);
+        assert!(warnings("int f(long l) { char c = 100; short s = c; return (int)l + s; }").is_empty());
+        assert!(warnings("long f(int *p) { return (long)p; }").is_empty());
+        assert_eq!(warnings("int f(int *p) { return (int)p; }").len(), 1);
+
+        // Conversions between integers and pointers require a cast
+        assert!(typecheck("int *f(long l) { return (int *)l; }").is_ok());
+        assert!(typecheck("int *f(long l) { return l; }").is_err());
+        assert!(typecheck("long f(int *p) { long l = p; return l; }").is_err());
+        assert!(typecheck("int g(int *p); int f(long l) { return g(l); }").is_err());
+        assert!(typecheck("int *f(void) { return (int *)0; }").is_ok());
+    }
+
+    #[test]
+    fn test_floating_types() {
+        let typecheck = |src: &str| {
+            let (program, symbols) = resolve_str(src)?;
+            crate::ast::typecheck::typecheck(program, &symbols)
+        };
+
+        // Integer operands are converted to the floating type of the other operand
+        let (program, _) = typecheck("double f(int i) { return i * 1.5f; }").expect("Program should typecheck");
+        match &program[0] {
+            Declaration::Function(f) => match &f.statements[0].kind {
+                StatementKind::Return(Expression { kind: ExpressionKind::Cast(Type::Double, inner), .. }) => {
+                    assert_eq!(inner.ty(), &Type::Float);
+                    assert!(matches!(inner.as_ref(), Expression { kind: ExpressionKind::Binary(_, left, _), .. } if left.ty() == &Type::Float));
+                },
+                _ => { panic!("Statement 0 should return a converted product"); }
+            },
+            _ => { panic!("AST root node 0 should match Declaration::Function"); }
+        }
+
+        // Static initializers are folded, and converted to the type of the variable
+        use crate::ast::table::{InitialValue, StaticInit, SymbolAttributes};
+        let (_, symbols) = resolve_str("double d = 1 + 0.5;\nint i = 2.75;\ndouble z = 0.0;").expect("Program should resolve");
+        assert!(matches!(&symbols.get("d").unwrap().attributes,
+            SymbolAttributes::Static { init: InitialValue::Initial(i), .. } if i == &vec![StaticInit::Value(Type::Double, ConstantValue::Double(1.5))]));
+        assert!(matches!(&symbols.get("i").unwrap().attributes,
+            SymbolAttributes::Static { init: InitialValue::Initial(i), .. } if i == &vec![StaticInit::Value(Type::Int, ConstantValue::Int(2))]));
+        assert!(matches!(&symbols.get("z").unwrap().attributes,
+            SymbolAttributes::Static { init: InitialValue::Initial(i), .. } if i == &vec![StaticInit::Zero(8)]));
+
+        // Integer-only operators and conversions to pointers are rejected
+        assert!(typecheck("double f(double d) { return d % 2; }").is_err());
+        assert!(typecheck("double f(double d) { return ~d; }").is_err());
+        assert!(typecheck("int *f(double d) { return (int *)d; }").is_err());
+
+        // Conversions to narrower or integer types may change the value
+        let warnings = |src: &str| typecheck(src).expect("Program should typecheck").1;
+        assert_eq!(warnings("float f(double d) { return d; }").len(), 1);
+        assert_eq!(warnings("int f(double d) { return d; }").len(), 1);
+        assert_eq!(warnings("int f(void) { return 2.5; }").len(), 1);
+        assert!(warnings("double f(float x) { return x + 2.0; }").is_empty());
+        assert!(warnings("float f(void) { return 0.5; }").is_empty());
+
+        // Their values are shown in their shortest exact form
+        let message = |src: &str| warnings(src)[0].message.clone();
+        assert_eq!(message("int f(void) { return 2.5; }"), "Implicit conversion from `double` to `int` in return changes value from 2.5 to 2");
+        assert!(message("float f(void) { return 1e300; }").ends_with("changes value from 1e300 to inf"));
+    }
+
+    #[test]
+    fn test_sizeof() {
+        use crate::ast::table::{InitialValue, StaticInit, SymbolAttributes};
+        let typecheck = |src: &str| {
+            let (program, symbols) = resolve_str(src)?;
+            crate::ast::typecheck::typecheck(program, &symbols)
+        };
+
+        // Sizes are constants of type `unsigned long`, usable wherever a constant is expected
+        let (_, symbols) = resolve_str(
+            "struct s { char c; long l; };\nenum { N = sizeof(int) * _Alignof(short) };\nunsigned long n = sizeof(struct s);\nint a[sizeof n + N];\nchar b[_Alignof(struct s)][sizeof a / sizeof a[0]];"
+        ).expect("Program should resolve");
+        assert!(matches!(&symbols.get("n").unwrap().attributes,
+            SymbolAttributes::Static { init: InitialValue::Initial(i), .. } if i == &vec![StaticInit::Value(Type::ULong, ConstantValue::ULong(16))]));
+        assert_eq!(symbols.type_of("a"), &Type::Array(Box::new(Type::Int), 16));
+        assert_eq!(symbols.type_of("b"), &Type::Array(Box::new(Type::Array(Box::new(Type::Char), 16)), 8));
+
+        // The operand is not evaluated, and arrays don't decay
+        let (program, _) = typecheck("long f(int x) { int a[3]; return sizeof(x = 2) + sizeof a; }").expect("Program should typecheck");
+        match &program[0] {
+            Declaration::Function(f) => match &f.statements[1].kind {
+                StatementKind::Return(Expression { kind: ExpressionKind::Cast(Type::Long, inner), .. }) => {
+                    assert!(matches!(&inner.kind, ExpressionKind::Binary(_, left, right)
+                        if left.kind == ExpressionKind::Constant(ConstantValue::ULong(4))
+                        && right.kind == ExpressionKind::Constant(ConstantValue::ULong(12))));
+                },
+                _ => { panic!("Statement 1 should return a converted sum"); }
+            },
+            _ => { panic!("AST root node 0 should match Declaration::Function"); }
+        }
+
+        // Incomplete types and functions have no size
+        assert!(resolve_str("struct s; unsigned long n = sizeof(struct s);").is_err());
+        assert!(resolve_str("int f(void) { return sizeof(void); }").is_err());
+        assert!(resolve_str("void g(void); int f(void) { return sizeof g(); }").is_err());
+        assert!(resolve_str("int g(void); int f(void) { return sizeof g; }").is_err());
+        assert!(resolve_str("int f(void) { return _Alignof(union u); }").is_err());
+        assert!(resolve_str("int f(int n) { int a[n]; return sizeof a; }").is_ok());
+    }
+
+    #[test]
+    fn test_initializer_lists() {
+        use crate::ast::table::{InitialValue, StaticInit, SymbolAttributes};
+        let static_init = |symbols: &crate::ast::table::SymbolTable, name: &str| match &symbols.get(name).unwrap().attributes {
+            SymbolAttributes::Static { init: InitialValue::Initial(i), .. } => i.clone(),
+            _ => panic!("`{}` should have an initial value", name)
+        };
+        let value = |ty: Type, v: i128| StaticInit::integer(&ty, v);
+
+        // Designators, elided braces, and sizes taken from the initializer
+        let (_, symbols) = resolve_str(
+            "struct p { int x; int y; };\nstruct p a[] = { 1, 2, [2].y = 4 };\nint b[] = { [3] = 1, 2, [1] = 5 };\nunion u { char c; long l; } u = { .l = 7 };"
+        ).expect("Program should resolve");
+        assert_eq!(symbols.type_of("a"), &Type::Array(Box::new(Type::Structure(StructKind::Struct, "p.0".to_string())), 3));
+        assert_eq!(static_init(&symbols, "a"), vec![value(Type::Int, 1), value(Type::Int, 2), StaticInit::Zero(12), value(Type::Int, 4)]);
+        assert_eq!(symbols.type_of("b"), &Type::Array(Box::new(Type::Int), 5));
+        assert_eq!(static_init(&symbols, "b"), vec![StaticInit::Zero(4), value(Type::Int, 5), StaticInit::Zero(4), value(Type::Int, 1), value(Type::Int, 2)]);
+        assert_eq!(static_init(&symbols, "u"), vec![value(Type::Long, 7)]);
+
+        // String literals initialize character arrays, with their null if there is room for it
+        let (_, symbols) = resolve_str("char s[] = \"a\\n\" \"b\";\nchar t[2] = { \"xy\" };").expect("Program should resolve");
+        assert_eq!(symbols.type_of("s"), &Type::Array(Box::new(Type::Char), 4));
+        assert_eq!(static_init(&symbols, "s"), vec![value(Type::Char, 97), value(Type::Char, 10), value(Type::Char, 98), StaticInit::Zero(1)]);
+        assert_eq!(static_init(&symbols, "t"), vec![value(Type::Char, 120), value(Type::Char, 121)]);
+
+        // Anywhere else, they designate a static array of characters
+        let (_, symbols) = resolve_str("char *p = \"ab\";\nstruct { int n; char *s; } v = { 1, \"c\" };").expect("Program should resolve");
+        let init = static_init(&symbols, "p");
+        let [StaticInit::Address(string, 0)] = init.as_slice() else { panic!("`p` should point to its string literal") };
+        assert_eq!(symbols.type_of(string), &Type::Array(Box::new(Type::Char), 3));
+        assert_eq!(static_init(&symbols, string), vec![value(Type::Char, 97), value(Type::Char, 98), StaticInit::Zero(1)]);
+        assert!(matches!(static_init(&symbols, "v").as_slice(), [_, StaticInit::Zero(4), StaticInit::Address(_, 0)]));
+        assert!(resolve_str("int f(void) { return (sizeof \"abc\" == 4) & \"abc\"[1]; }").is_ok());
+
+        // Addresses of elements and members of static objects are constants too
+        let (_, symbols) = resolve_str(concat!(
+            "int g[2][3];\nstruct s { char c; int m[2]; } v;\n",
+            "int *a = &g[1][2];\nint (*b)[3] = g + 1;\nint *c = &v.m[1];\nchar *d = (char *)&v + 1;\nint *e = g[1] - 1;"
+        )).expect("Program should resolve");
+        for (name, symbol, offset) in [("a", "g", 20), ("b", "g", 12), ("c", "v", 8), ("d", "v", 1), ("e", "g", 8)] {
+            assert_eq!(static_init(&symbols, name), vec![StaticInit::Address(symbol.to_string(), offset)], "{}", name);
+        }
+        let src = "int x;\nint *p = &x;\nint *q = p + 1;";
+        let error = resolve_str(src).expect_err("Program should not resolve").into_diagnostic();
+        assert_eq!(error.primary_span().map(|span| &src[span.start..span.end]), Some("p + 1"));
+        assert!(resolve_str("struct b { int f : 3; } w;\nint *p = &w.f;").is_err());
+
+        // Null pointer constants, and integer constants cast to pointers
+        let (_, symbols) = resolve_str(concat!(
+            "void *a = (void *)0;\nint *b = (void *)0;\nchar *c = (char *)-1;\nint *d = (int *)0;\n",
+            "int f(void) { static int *e = (void *)0; return 0; }"
+        )).expect("Program should resolve");
+        for name in ["a", "b", "d"] {
+            assert_eq!(static_init(&symbols, name), vec![StaticInit::Zero(8)], "{}", name);
+        }
+        assert_eq!(static_init(&symbols, "c"), vec![value(Type::ULong, 18446744073709551615)]);
+        assert!(resolve_str("int *p = (char *)-1;").is_err());
+        assert!(resolve_str("int *p = (void *)1;").is_ok());
+
+        assert!(resolve_str("int a[2] = { 1, 2, 3 };").is_err());
+        assert!(resolve_str("int a[2] = { [2] = 1 };").is_err());
+        assert!(resolve_str("struct p { int x; } v = { .y = 1 };").is_err());
+        assert!(resolve_str("int x = { .y = 1 };").is_err());
+        assert!(resolve_str("char s[2] = \"abc\";").is_err());
+        assert!(resolve_str("int f(void) {\n  static int a[];\n  return 0;\n}").is_err());
+    }
+
+    #[test]
+    fn test_function_pointers() {
+        use crate::ast::table::{InitialValue, StaticInit, SymbolAttributes};
+        let typecheck = |src: &str| {
+            let (program, symbols) = resolve_str(src)?;
+            crate::ast::typecheck::typecheck(program, &symbols)
+        };
+        let pointer = |ty: Type| Type::Pointer(Box::new(ty));
+        let function = |params: Vec<Type>, ret: Type| Type::Function(FunctionType { params, ret: Box::new(ret), variadic: false });
+
+        // Parenthesized declarators, and function parameters adjusted to pointers
+        let (program, symbols) = resolve_str(
+            "int (*(*f)(int))[3];\nint (*g(int x))(long);\nint h(int (*)(void), int k(char));\nint s(int);\nint (*table[2])(int) = { s, &s };"
+        ).expect("Program should resolve");
+        assert_eq!(symbols.type_of("f"), &pointer(function(vec![Type::Int], pointer(Type::Array(Box::new(Type::Int), 3)))));
+        assert_eq!(symbols.type_of("g"), &function(vec![Type::Int], pointer(function(vec![Type::Long], Type::Int))));
+        assert_eq!(symbols.type_of("h"), &function(vec![
+            pointer(function(vec![], Type::Int)),
+            pointer(function(vec![Type::Char], Type::Int))
+        ], Type::Int));
+        assert!(matches!(&program[1], Declaration::Function(FunctionDefinition { params, .. }) if params == &vec!["x".to_string()]));
+        assert!(matches!(&symbols.get("table").unwrap().attributes,
+            SymbolAttributes::Static { init: InitialValue::Initial(i), .. } if i == &vec![StaticInit::Address("s".to_string(), 0), StaticInit::Address("s".to_string(), 0)]));
+
+        // Calls by name stay direct, while other callees decay to a pointer
+        let (program, _) = typecheck("int s(int);\nint f(int (*p)(int)) { return s(1) + p(2); }").expect("Program should typecheck");
+        match &program[1] {
+            Declaration::Function(f) => match &f.statements[0].kind {
+                StatementKind::Return(Expression { kind: ExpressionKind::Binary(_, left, right), .. }) => {
+                    assert!(matches!(&left.kind, ExpressionKind::FunctionCall(callee, _) if matches!(callee.ty(), Type::Function(_))));
+                    assert!(matches!(&right.kind, ExpressionKind::FunctionCall(callee, _) if callee.ty().is_pointer()));
+                },
+                _ => { panic!("Statement 0 should return a sum of calls"); }
+            },
+            _ => { panic!("AST root node 1 should match Declaration::Function"); }
+        }
+
+        assert!(typecheck("int s(int); int f(void) { int (*p)(int) = s; return (*p)(1) + (&s)(2); }").is_ok());
+        assert!(typecheck("int f(void) { int x = 0; return x(); }").is_err());
+        assert!(typecheck("int s(int); int f(void) { return s(1, 2); }").is_err());
+        assert!(typecheck("int s(int); int f(void) { int (*p)(int) = s; return *(p + 1)(0); }").is_err());
+        assert!(typecheck("int s(int); int t(int); int f(void) { s = t; return 0; }").is_err());
+        assert!(resolve_str("int f(int)[3];").is_err());
+        assert!(resolve_str("int f(int)(int);").is_err());
+        assert!(resolve_str("int f(int) { return 0; }").is_err());
+        assert!(resolve_str("int s(int); long *p = s;").is_err());
+
+        // Types are shown in C's declarator syntax
+        let constant = |ty: Type| ty.qualified(Qualifiers { constant: true, ..Default::default() });
+        for (ty, shown) in [
+            (pointer(function(vec![Type::Int], Type::Int)), "int (*)(int)"),
+            (pointer(Type::Array(Box::new(Type::Int), 3)), "int (*)[3]"),
+            (Type::Array(Box::new(pointer(Type::Int)), 3), "int *[3]"),
+            (pointer(pointer(Type::Int)), "int **"),
+            (function(vec![], Type::Int), "int (void)"),
+            (Type::Function(FunctionType { params: vec![Type::Int], ret: Box::new(Type::Int), variadic: true }), "int (int, ...)"),
+            (function(vec![Type::Int], pointer(function(vec![Type::Long], Type::Int))), "int (*(int))(long)"),
+            (pointer(function(vec![Type::Int], pointer(Type::Array(Box::new(Type::Int), 3)))), "int (*(*)(int))[3]"),
+            (pointer(constant(pointer(constant(Type::Char)))), "const char *const *"),
+            (constant(pointer(function(vec![], Type::Void))), "void (*const)(void)")
+        ] {
+            assert_eq!(ty.to_string(), shown);
+        }
+    }
+
+    #[test]
+    fn test_variadic_functions() {
+        let typecheck = |src: &str| {
+            let (program, symbols) = resolve_str(src)?;
+            crate::ast::typecheck::typecheck(program, &symbols)
+        };
+
+        let (_, symbols) = resolve_str("int f(int n, ...);\nint (*p)(char *, ...);").expect("Program should resolve");
+        assert!(matches!(symbols.type_of("f"), Type::Function(FunctionType { params, variadic: true, .. }) if params == &vec![Type::Int]));
+        assert!(matches!(symbols.type_of("p"), Type::Pointer(f) if matches!(&**f, Type::Function(FunctionType { variadic: true, .. }))));
+        assert!(resolve_str("int f(...);").is_err());
+        assert!(resolve_str("int f(int n, ..., int m);").is_err());
+
+        // Extra arguments are promoted, and `va_arg` reads them back from a `va_list`
+        let (program, _) = typecheck(
+            "long f(int n, ...) { __builtin_va_list ap; __builtin_va_start(ap, n); long x = __builtin_va_arg(ap, long); __builtin_va_end(ap); return x; }\nlong g(void) { float x = 1.5f; char c = 2; return f(2, x, c); }"
+        ).expect("Program should typecheck");
+        match &program[1] {
+            Declaration::Function(f) => match &f.statements[2].kind {
+                StatementKind::Return(Expression { kind: ExpressionKind::FunctionCall(_, args), .. }) => {
+                    assert_eq!(args.iter().map(|arg| arg.ty().clone()).collect::<Vec<_>>(), vec![Type::Int, Type::Double, Type::Int]);
+                },
+                _ => { panic!("Statement 2 should return a call"); }
+            },
+            _ => { panic!("AST root node 1 should match Declaration::Function"); }
+        }
+
+        assert!(typecheck("long f(__builtin_va_list ap) { return __builtin_va_arg(ap, long); }").is_ok());
+        assert!(typecheck("int f(int n, ...); int g(void) { return f(); }").is_err());
+        assert!(typecheck("int f(int n) { __builtin_va_list ap; __builtin_va_start(ap, n); return 0; }").is_err());
+        assert!(typecheck("int f(int n, ...) { long ap; __builtin_va_start(ap, n); return 0; }").is_err());
+
+        // Functions declared with `()` have no prototype, and take any arguments until a
+        // declaration with parameters is found, which must take promoted arguments
+        assert!(typecheck("int f();\nint g(void) { return f(1, 2) + f(); }").is_ok());
+        assert!(typecheck("int g(int (*p)()) { return p(1.5); }\nint h(int a, int b);\nint k(void) { return g(h); }").is_ok());
+        let (_, symbols) = resolve_str("int f();\nint f(int a, double d);\nint f();").expect("Program should resolve");
+        assert!(matches!(symbols.type_of("f"), Type::Function(FunctionType { params, variadic: false, .. }) if params == &vec![Type::Int, Type::Double]));
+        assert!(resolve_str("int f();\nint f(char c);").is_err());
+        assert!(resolve_str("int f();\nint f(int n, ...);").is_err());
+        assert!(resolve_str("int f();\nlong f(int n);").is_err());
+
+        // Definitions without parameters take no arguments
+        let (_, symbols) = resolve_str("int f();\nint f() { return 0; }").expect("Program should resolve");
+        assert_eq!(symbols.type_of("f").to_string(), "int (void)");
+        assert!(typecheck("int f() { return 0; }\nint g(void) { return f(1); }").is_err());
+        assert!(resolve_str("int f(int n);\nint f() { return 0; }").is_err());
+    }
+
+    #[test]
+    fn test_type_qualifiers() {
+        use crate::zil::symbols as Z;
+        let typecheck = |src: &str| {
+            let (program, symbols) = resolve_str(src)?;
+            crate::ast::typecheck::typecheck(program, &symbols)
+        };
+        let constant = Qualifiers { constant: true, ..Qualifiers::default() };
+        let volatile = Qualifiers { volatile: true, ..Qualifiers::default() };
+        let restrict = Qualifiers { restrict: true, ..Qualifiers::default() };
+        let pointer = |ty: Type| Type::Pointer(Box::new(ty));
+
+        // Qualifiers of arrays apply to their elements, and those after `*` to the pointer
+        let (_, symbols) = resolve_str(
+            "const int x;\nint const *p;\nchar *const volatile q;\nconst char *restrict s;\nconst int a[2] = {1, 2};"
+        ).expect("Program should resolve");
+        assert_eq!(symbols.type_of("x"), &Type::Int.qualified(constant));
+        assert_eq!(symbols.type_of("p"), &pointer(Type::Int.qualified(constant)));
+        assert_eq!(symbols.type_of("q"), &pointer(Type::Char).qualified(constant.union(volatile)));
+        assert_eq!(symbols.type_of("s"), &pointer(Type::Char.qualified(constant)).qualified(restrict));
+        assert_eq!(symbols.type_of("a"), &Type::Array(Box::new(Type::Int.qualified(constant)), 2));
+        assert!(resolve_str("restrict int x;").is_err());
+        assert!(resolve_str("int (*restrict f)(void);").is_err());
+
+        // Qualifiers of parameters are not part of the function type, but apply in its body
+        assert!(typecheck("int f(int); int f(const int x) { return x; }").is_ok());
+        assert!(typecheck("int f(const int x) { x = 1; return x; }").is_err());
+
+        // Objects that are const, or have a const member, can't be assigned to
+        assert!(typecheck("int f(void) { const int x = 1; int y = x; y = 2; return y; }").is_ok());
+        assert!(typecheck("int f(void) { const int x = 1; x = 2; return x; }").is_err());
+        assert!(typecheck("int f(const int *p) { *p = 1; return 0; }").is_err());
+        assert!(typecheck("int f(int *const p) { *p = 1; p = 0; return 0; }").is_err());
+        assert!(typecheck("struct s { const int x; int y; }; int f(struct s *p) { p->y = 1; return 0; }").is_ok());
+        assert!(typecheck("struct s { const int x; int y; }; int f(struct s *p) { p->x = 1; return 0; }").is_err());
+        assert!(typecheck("struct s { const int x; int y; }; int f(struct s *p, struct s v) { *p = v; return 0; }").is_err());
+        assert!(typecheck("struct s { int x; }; int f(void) { const struct s v = { 1 }; v.x = 2; return 0; }").is_err());
+
+        // Pointer conversions may add qualifiers to the type pointed to, but not discard them
+        assert!(typecheck("int f(int *p) { const int *q = p; const void *v = q; return q == p; }").is_ok());
+        assert!(typecheck("int f(const int *p) { int *q = p; return 0; }").is_err());
+        assert!(typecheck("int f(const int *p) { void *v = p; return 0; }").is_err());
+        assert!(typecheck("int g(char *s); int f(const char *s) { return g(s); }").is_err());
+        assert!(typecheck("int f(const int *p) { int *q = (int *) p; return 0; }").is_ok());
+        assert!(resolve_str("const int x = 1; int *p = &x;").is_err());
+        assert!(resolve_str("int x = 1; const int *p = &x;").is_ok());
+
+        // Accesses to volatile objects are marked in ZIL, even when their value is discarded
+        let (program, symbols) = resolve_str("volatile int v; int f(volatile int *p) { v; *p = 1; return 0; }").expect("Program should resolve");
+        let (program, _) = crate::ast::typecheck::typecheck(program, &symbols).expect("Program should typecheck");
+        let mut symbols = symbols;
+        let zil = crate::zil::transpile::parse(program, &mut symbols);
+        let Some(Z::Construct::Function(f)) = zil.first() else {
+            panic!("ZIL construct 0 should be a function");
+        };
+        let volatile: Vec<bool> = f.instructions.iter()
+            .filter(|i| !matches!(i, Z::Instruction::Location(_)))
+            .map(|i| i.is_volatile(&symbols))
+            .collect();
+        assert_eq!(volatile, vec![true, true, false, false]);
+    }
+
+    #[test]
+    fn test_bool_type() {
+        use crate::ast::table::{InitialValue, StaticInit, Symbol, SymbolAttributes};
+        use crate::zil::symbols as Z;
+
+        let (_, symbols) = resolve_str("_Bool a = 256; static _Bool b = 0.5; _Bool c = (_Bool)-1; struct s { _Bool x; char y; } v;").expect("Program should resolve");
+        assert_eq!(symbols.type_of("a"), &Type::Bool);
+        assert_eq!(symbols.size_of(&Type::Bool), 1);
+        assert_eq!(symbols.size_of(symbols.type_of("v")), 2);
+        for name in ["a", "b", "c"] {
+            let Some(Symbol { attributes: SymbolAttributes::Static { init, .. }, .. }) = symbols.get(name) else {
+                panic!("`{}` should be static", name);
+            };
+            assert_eq!(init, &InitialValue::Initial(vec![StaticInit::Value(Type::Bool, ConstantValue::Int(1))]));
+        }
+        assert!(resolve_str("unsigned _Bool x;").is_err());
+
+        // `_Bool` is promoted to `int`, and pointers convert to it implicitly
+        let (program, symbols) = resolve_str("int f(int *p, long l) { _Bool b = p; b = l; return b + b; }").expect("Program should resolve");
+        let (program, warnings) = crate::ast::typecheck::typecheck(program, &symbols).expect("Program should typecheck");
+        assert!(warnings.is_empty());
+
+        // Conversions to `_Bool` compare with zero rather than truncate
+        let mut symbols = symbols;
+        let zil = crate::zil::transpile::parse(program, &mut symbols);
+        let Some(Z::Construct::Function(f)) = zil.first() else {
+            panic!("ZIL construct 0 should be a function");
+        };
+        let comparisons = f.instructions.iter().filter(|i| matches!(i, Z::Instruction::Binary(Z::BinaryInstructionDefinition { operator: Z::BinaryInstructionOperator::NotEqual, .. }))).count();
+        assert_eq!(comparisons, 2);
+    }
+
+    #[test]
+    fn test_compound_literals() {
+        use crate::ast::table::{InitialValue, StaticInit, Symbol, SymbolAttributes};
+        let typecheck = |src: &str| {
+            let (program, symbols) = resolve_str(src)?;
+            crate::ast::typecheck::typecheck(program, &symbols)
+        };
+
+        // At file scope, compound literals are static objects, whose address is constant
+        let (_, symbols) = resolve_str("struct point { int x; int y; };\nstruct point *p = &(struct point){ .y = 2 };\nint *a = (int[]){ 1, 2, 3 };")
+            .expect("Program should resolve");
+        let statics: Vec<(&String, &Symbol)> = symbols.iter().filter(|(name, _)| name.starts_with("compound.")).collect();
+        assert_eq!(statics.len(), 2);
+        let SymbolAttributes::Static { init: InitialValue::Initial(init), global: false } = &statics[0].1.attributes else {
+            panic!("Compound literal should be a static object");
+        };
+        assert_eq!(init, &vec![StaticInit::Zero(4), StaticInit::Value(Type::Int, ConstantValue::Int(2))]);
+        assert_eq!(statics[1].1.ty, Type::Array(Box::new(Type::Int), 3));
+        let SymbolAttributes::Static { init: InitialValue::Initial(init), .. } = &symbols.get("p").expect("`p` should be declared").attributes else {
+            panic!("`p` should be initialized");
+        };
+        assert_eq!(init, &vec![StaticInit::Address(statics[0].0.clone(), 0)]);
+        assert!(resolve_str("int x; int *p = &(int){ x };").is_err());
+
+        // In a block, they are automatic lvalues, and postfix operators apply to them
+        let (program, _) = typecheck("struct point { int x; int y; }; int f(int n) { int *a = (int[]){ n, 2 }; (int){ 1 } = n; return (struct point){ 1, n }.y + a[1]; }")
+            .expect("Program should typecheck");
+        let Some(Declaration::Function(f)) = program.last() else {
+            panic!("Last declaration should be a function");
+        };
+        let StatementKind::Return(e) = &f.statements[2].kind else {
+            panic!("Statement 2 should be a return");
+        };
+        let ExpressionKind::Binary(BinaryExpressionType::Add, left, _) = &e.kind else {
+            panic!("Return value should be an addition");
+        };
+        assert!(matches!(&left.kind, ExpressionKind::Dot(inner, member) if member == "y" && matches!(inner.kind, ExpressionKind::CompoundLiteral(_))));
+        assert!(typecheck("int f(void) { return sizeof (int[]){ 1, 2, 3 }; }").is_ok());
+        assert!(typecheck("struct s; int f(void) { (struct s){ 0 }; return 0; }").is_err());
+        assert!(resolve_str("int f(void) { return sizeof(int[]); }").is_err());
+    }
+
+    #[test]
+    fn test_static_assertions() {
+        assert!(resolve_str("_Static_assert(sizeof(int) == 4, \"int is 32 bits\");").is_ok());
+        assert!(resolve_str("struct s { char c; long l; }; int f(void) { _Static_assert(sizeof(struct s) == 16, \"padded\"); return 0; }").is_ok());
+        assert!(resolve_str("enum { A = 3 }; _Static_assert(A - 3, \"A is 3\");").is_err_and(|e| e.to_string().contains("Static assertion failed: \"A is 3\"")));
+        assert!(resolve_str("int f(int x) { _Static_assert(x, \"x\"); return 0; }").is_err());
+        assert!(resolve_str("_Static_assert(1);").is_err());
+    }
+
+    #[test]
+    fn test_bit_fields() {
+        use crate::ast::table::{BitField, InitialValue, StaticInit, SymbolAttributes};
+        let typecheck = |src: &str| {
+            let (program, symbols) = resolve_str(src)?;
+            crate::ast::typecheck::typecheck(program, &symbols)
+        };
+
+        // Bit-fields are packed into storage units of their type, starting a new one when they
+        // don't fit in the current one or after a zero-width bit-field. `b` is a plain `int`
+        let (_, symbols) = resolve_str("struct s { char c; int a : 4; unsigned b : 30; unsigned : 0; short d : 3; } v = { 1, -1, 2, 3 };")
+            .expect("Program should resolve");
+        let ty = symbols.type_of("v");
+        assert_eq!(symbols.size_of(ty), 12);
+        let layout = |name: &str| symbols.member(ty, name).map(|m| (m.offset, m.bit_field));
+        assert_eq!(layout("a"), Some((0, Some(BitField { offset: 8, width: 4 }))));
+        assert_eq!(layout("b"), Some((4, Some(BitField { offset: 0, width: 30 }))));
+        assert_eq!(layout("d"), Some((8, Some(BitField { offset: 0, width: 3 }))));
+        let SymbolAttributes::Static { init: InitialValue::Initial(init), .. } = &symbols.get("v").expect("`v` should be declared").attributes else {
+            panic!("`v` should be initialized");
+        };
+        let byte = |value: i32| StaticInit::Value(Type::UChar, ConstantValue::Int(value));
+        assert_eq!(init, &vec![
+            StaticInit::Value(Type::Char, ConstantValue::Int(1)), byte(15), byte(0), byte(0),
+            byte(2), byte(0), byte(0), byte(0), byte(3), StaticInit::Zero(3)
+        ]);
+
+        // Bit-fields whose values fit in an `int` are promoted to it, even if unsigned
+        let (program, _) = typecheck("struct s { unsigned a : 3; unsigned long b : 40; }; unsigned long f(struct s *p) { return p->a - 4 + p->b; }")
+            .expect("Program should typecheck");
+        let Some(Declaration::Function(f)) = program.last() else {
+            panic!("Last declaration should be a function");
+        };
+        let StatementKind::Return(e) = &f.statements[0].kind else {
+            panic!("Statement 0 should be a return");
+        };
+        let ExpressionKind::Binary(BinaryExpressionType::Add, left, right) = &e.kind else {
+            panic!("Return value should be an addition");
+        };
+        assert!(matches!(&left.kind, ExpressionKind::Cast(Type::ULong, inner) if inner.ty() == &Type::Int));
+        assert_eq!(right.ty(), &Type::ULong);
+
+        assert!(typecheck("struct s { int x : 3; }; int *f(struct s *p) { return &p->x; }").is_err_and(|e| e.to_string().contains("bit-field `x`")));
+        assert!(resolve_str("struct s { int x : 3; }; int f(struct s v) { return sizeof v.x; }").is_err());
+        assert!(resolve_str("struct s { int x : 33; };").is_err());
+        assert!(resolve_str("struct s { _Bool x : 2; };").is_err());
+        assert!(resolve_str("struct s { int x : 0; };").is_err());
+        assert!(resolve_str("struct s { double x : 3; };").is_err());
+        assert!(resolve_str("int n; struct s { int x : n; };").is_err());
+    }
+
+    #[test]
+    fn test_variable_length_arrays() {
+        let typecheck = |src: &str| {
+            let (program, symbols) = resolve_str(src)?;
+            crate::ast::typecheck::typecheck(program, &symbols)
+        };
+
+        // The number of elements is evaluated into a local declared before the array,
+        // from which `sizeof` is computed at runtime
+        let (program, _) = typecheck("unsigned long f(int n) { int a[n][3]; return sizeof a; }").expect("Program should typecheck");
+        let Some(Declaration::Function(f)) = program.last() else {
+            panic!("Last declaration should be a function");
+        };
+        let StatementKind::Declaration(Declaration::Variable(count)) = &f.statements[0].kind else {
+            panic!("Statement 0 should declare the number of elements");
+        };
+        assert_eq!(count.var_type, Type::ULong);
+        let StatementKind::Declaration(Declaration::Variable(array)) = &f.statements[1].kind else {
+            panic!("Statement 1 should declare the array");
+        };
+        assert_eq!(array.var_type, Type::VariableArray(Box::new(Type::Array(Box::new(Type::Int), 3)), count.name.clone()));
+        let StatementKind::Return(e) = &f.statements[2].kind else {
+            panic!("Statement 2 should be a return");
+        };
+        assert!(matches!(&e.kind, ExpressionKind::Binary(BinaryExpressionType::Multiply, left, _) if left.kind == ExpressionKind::Variable(count.name.clone())));
+
+        // Pointers to arrays of any number of elements are compatible with them
+        assert!(typecheck("int f(int n, int m) { int a[n][m]; int (*p)[m] = a; int (*q)[4] = a; return p[1][2] + q[0][1] + (int) sizeof(int[n]); }").is_ok());
+        assert!(typecheck("int f(int n) { int a[n][2]; long (*p)[n] = a; return 0; }").is_err());
+
+        assert!(resolve_str("int n = 3; int a[n];").is_err());
+        assert!(resolve_str("int f(int n) { static int a[n]; return 0; }").is_err());
+        assert!(resolve_str("int f(int n) { int a[n] = { 1 }; return 0; }").is_err());
+        assert!(resolve_str("int f(int n) { struct s { int a[n]; }; return 0; }").is_err());
+        assert!(resolve_str("int f(int n) { return (int[n]){ 1 }[0]; }").is_err());
+        assert!(resolve_str("int f(int n) { int g(int a[][n]); return 0; }").is_err());
+        assert!(resolve_str("int f(int *p) { int a[p]; return 0; }").is_err());
+    }
+
+    #[test]
+    fn test_inline_functions() {
+        use crate::ast::table::SymbolAttributes;
+        use crate::zil::symbols as Z;
+
+        // Definitions are only inline definitions when no file-scope declaration is `extern` or omits `inline`
+        let (_, symbols) = resolve_str(
+            "inline int f(void) { return 1; }\ninline int g(void) { return 2; }\nextern int g(void);\nstatic inline int h(void) { return 3; }\nint main(void) { inline int f(void); return 0; }"
+        ).expect("Program should resolve");
+        assert!(matches!(symbols.get("f").unwrap().attributes, SymbolAttributes::Function { global: true, inline: true, inline_only: true, .. }));
+        assert!(matches!(symbols.get("g").unwrap().attributes, SymbolAttributes::Function { global: true, inline: true, inline_only: false, .. }));
+        assert!(matches!(symbols.get("h").unwrap().attributes, SymbolAttributes::Function { global: false, inline: true, .. }));
+
+        assert!(resolve_str("inline int x;").is_err());
+        assert!(resolve_str("inline typedef int f(void);").is_err());
+        assert!(resolve_str("struct s { inline int x; };").is_err());
+        assert!(resolve_str("int f(inline int x);").is_err());
+        assert!(resolve_str("inline int main(void) { return 0; }").is_err());
+        assert!(resolve_str("inline int f(void) { static int n; return n; }").is_err());
+        assert!(resolve_str("inline int f(void) { static const int n = 1; return n; }").is_ok());
+        assert!(resolve_str("static int s; inline int f(void) { return s; }").is_err());
+        assert!(resolve_str("static int s; static inline int f(void) { return s; }").is_ok());
+        assert!(resolve_str("static int s; int f(void); inline int f(void) { return s; }").is_ok());
+
+        // Calls to inline functions are replaced by their body, after which unreferenced
+        // `static inline` functions and inline definitions are not emitted at all
+        let (program, symbols) = resolve_str(
+            "static inline int add(int a, int b) { return a + b; }\nstatic inline int twice(int x) { return add(x, x); }\ninline int one(void) { return 1; }\nint main(void) { return twice(2) + add(1, one()); }"
+        ).expect("Program should resolve");
+        let (program, _) = crate::ast::typecheck::typecheck(program, &symbols).expect("Program should typecheck");
+        let mut symbols = symbols;
+        let zil = crate::zil::transpile::parse(program, &mut symbols);
+        let functions: Vec<&Z::FunctionDefinition> = zil.iter().filter_map(|c| match c {
+            Z::Construct::Function(f) => Some(f),
+            _ => None
+        }).collect();
+        assert_eq!(functions.len(), 1);
+        assert_eq!(functions[0].identifier, "main");
+        assert!(!functions[0].instructions.iter().any(|i| matches!(i, Z::Instruction::FunctionCall(_))));
+    }
+
+    #[test]
+    fn test_error_recovery() {
+        // Parsing resumes after the `;` ending the statement, and before the `}`
+        // closing the block or a declaration on the next line
+        let src = "int f(int x) {\n  int y = x + ;\n  return 1 2;\n  return y\n}\nstatic int s int;\nint g(void) { return 0; }\n}\nint h(void) { return (1; }\n";
+        let (buffer, tokens) = tokenize(src);
+        let errors = ASTParser::new(buffer, tokens).parse().expect_err("Program should not parse");
+        assert_eq!(errors.len(), 6);
+        assert!(errors.iter().all(|e| matches!(e, ASTError::SyntaxError(_))));
+
+        let (buffer, tokens) = tokenize(src);
+        let mut parser = ASTParser::new(buffer, tokens);
+        parser.set_error_limit(2);
+        assert_eq!(parser.parse().expect_err("Program should not parse").len(), 2);
+        assert!(parser.stopped_early());
+
+        // Reaching the limit with the last error doesn't stop parsing early
+        let (buffer, tokens) = tokenize(src);
+        let mut parser = ASTParser::new(buffer, tokens);
+        parser.set_error_limit(6);
+        assert_eq!(parser.parse().expect_err("Program should not parse").len(), 6);
+        assert!(!parser.stopped_early());
+
+        // Declarations after an error are still parsed, in the scope they appear in
+        let (buffer, tokens) = tokenize("typedef int T;\nint f(void) { T x = ; T y = 1; return y; }\nT g(void) { return 0; }");
+        assert_eq!(ASTParser::new(buffer, tokens).parse().expect_err("Program should not parse").len(), 1);
+
+        // A parenthesis left open doesn't hide the `}` closing the block, and the `;`
+        // after a structure is skipped along with it
+        let count = |src: &str| {
+            let (buffer, tokens) = tokenize(src);
+            ASTParser::new(buffer, tokens).parse().expect_err("Program should not parse").len()
+        };
+        assert_eq!(count("int h(void) { return (3 + ; }\nint main(void) { return 0; }"), 1);
+        assert_eq!(count("struct s { int a int b; };\nint main(void) { return 0; }"), 1);
+    }
+
+    #[test]
+    fn test_source_spans() {
+        use crate::lex::source::{Location, SourceMap};
+
+        let src = "# 1 \"a.c\"\nint f(int x) {\n  int y = x;\n# 10 \"b.h\" 1\n  return y  *  2;\n}\n";
+        let (buffer, tokens) = tokenize(src);
+        let map = SourceMap::new(&buffer, "main.c");
+        let program = ASTParser::new(buffer.clone(), tokens).parse().expect("Program should parse");
+        let Declaration::Function(f) = &program[0] else {
+            panic!("Declaration 0 should be a function");
+        };
+
+        // Statements span their tokens, binary expressions both of their operands
+        assert_eq!(map.locate(f.span.start), Location { file: "a.c", line: 1, column: 1 });
+        assert_eq!(&buffer[f.statements[0].span.start..f.statements[0].span.end], "int y = x;");
+        let StatementKind::Return(e) = &f.statements[1].kind else {
+            panic!("Statement 1 should be a return");
+        };
+        assert_eq!(&buffer[e.span.start..e.span.end], "y  *  2");
+        assert_eq!(map.locate(e.span.start), Location { file: "b.h", line: 10, column: 10 });
+
+        // Without linemarkers, lines are those of the buffer
+        let map = SourceMap::new("int x;\n\nint y;", "main.c");
+        assert_eq!(map.locate(12), Location { file: "main.c", line: 3, column: 5 });
+    }
+
+    #[test]
+    fn test_diagnostics() {
+        use crate::diagnostics::{self, render};
+        use crate::lex::source::SourceMap;
+
+        // Syntax errors point at the unexpected token, and suggest a fix when something is missing
+        let src = "int f(void) {\n  return 1 2;\n}\n";
+        let map = SourceMap::new(src, "main.c");
+        let (buffer, tokens) = tokenize(src);
+        let mut errors = ASTParser::new(buffer, tokens).parse().expect_err("Program should not parse");
+        let diagnostic = errors.remove(0).into_diagnostic();
+        assert_eq!(diagnostic.code, Some(diagnostics::UNEXPECTED_TOKEN));
+        assert_eq!(render(&diagnostic, Some(&map), false), concat!(
+            "error[E0002]: Expected `;` after expression, found `2`\n",
+            " --> main.c:2:12\n",
+            "  |\n",
+            "2 |   return 1 2;\n",
+            "  |            ^ expected `;`\n",
+            "  |\n",
+            "help: insert `;`\n",
+            "  |\n",
+            "2 |   return 1; 2;\n",
+            "  |           +\n"
+        ));
+
+        // Casts are suggested around the whole expression, with its type as in a declaration
+        let fixit = |src: &str| {
+            let map = SourceMap::new(src, "main.c");
+            let (program, symbols) = resolve_str(src).expect("Program should resolve");
+            let error = crate::ast::typecheck::typecheck(program, &symbols).expect_err("Program should not typecheck");
+            render(&error.into_diagnostic(), Some(&map), false).split("help: ").nth(1).map(str::to_string)
+        };
+        assert_eq!(fixit("int f(long l) {\n  int *q = l + 1;\n  return 0;\n}\n").as_deref(), Some(concat!(
+            "an explicit cast is required\n",
+            "  |\n",
+            "2 |   int *q = (int *)(l + 1);\n",
+            "  |            ++++++++     +\n"
+        )));
+        assert!(fixit("int f(long l) {\n  int *p = (l + 1);\n  return 0;\n}\n").is_some_and(|help| help.contains("int *p = ((int *)(l + 1));")));
+        assert!(fixit("int f(long l) {\n  int (*g)(int) = l;\n  return 0;\n}\n").is_some_and(|help| help.contains("int (*g)(int) = (int (*)(int))(l);")));
+
+        // A missing `;` is only suggested where the next statement could start
+        let (buffer, tokens) = tokenize("int f(int a, int b) {\n  return a, b;\n}\n");
+        let mut errors = ASTParser::new(buffer, tokens).parse().expect_err("Program should not parse");
+        assert!(errors.remove(0).into_diagnostic().fixits.is_empty());
+        let (buffer, tokens) = tokenize("int f(void) {\n  int a = 1\n  return a;\n}\n");
+        let mut errors = ASTParser::new(buffer, tokens).parse().expect_err("Program should not parse");
+        assert_eq!(errors.remove(0).into_diagnostic().fixits.len(), 1);
+
+        // Declarations that parse but can't be accepted point at their declarator
+        let cases = [
+            ("typedef int T;\ntypedef long T;", diagnostics::CONFLICTING_TYPES, (2, 14)),
+            ("int x;\ntypedef int x;", diagnostics::REDECLARATION, (2, 13)),
+            ("int f(void) {\n  void v;\n  return 0;\n}", diagnostics::INVALID_DECLARATOR, (2, 8)),
+            ("int f(void) {\n  int g(void) { return 0; }\n  return 0;\n}", diagnostics::INVALID_DECLARATOR, (2, 3)),
+            ("int a[-1];", diagnostics::INVALID_CONSTANT, (1, 7)),
+            ("restrict int x;", diagnostics::INVALID_SPECIFIERS, (1, 14))
+        ];
+        for (src, code, (line, column)) in cases {
+            let map = SourceMap::new(src, "main.c");
+            let (buffer, tokens) = tokenize(src);
+            let mut errors = ASTParser::new(buffer, tokens).parse().expect_err("Program should not parse");
+            let diagnostic = errors.remove(0).into_diagnostic();
+            assert_eq!(diagnostic.code, Some(code), "{}", src);
+            let location = map.locate(diagnostic.primary_span().expect("Error should have a span").start);
+            assert_eq!((location.line, location.column), (line, column), "{}", src);
+        }
+
+        // Semantic errors point at the innermost expression they are found in,
+        // and redeclarations at the previous declaration as well
+        let src = "int f(void) {\n  int x = 1;\n  return x + y;\n}\n";
+        let error = resolve_str(src).expect_err("Program should not resolve").into_diagnostic();
+        let map = SourceMap::new(src, "main.c");
+        assert_eq!(error.code, Some(diagnostics::UNDECLARED_IDENTIFIER));
+        assert_eq!(map.locate(error.primary_span().expect("Error should have a span").start).column, 14);
+
+        let cases = [
+            ("_Static_assert(sizeof(int) == 2, \"int is 2 bytes\");", diagnostics::STATIC_ASSERTION_FAILED),
+            ("int f(void);\nunsigned long n = sizeof f;", diagnostics::INVALID_LAYOUT_OPERAND),
+            ("struct s;\nunsigned long n = _Alignof(struct s);", diagnostics::INVALID_LAYOUT_OPERAND),
+            ("int x;\nint y = x;", diagnostics::INVALID_CONSTANT),
+            ("int f(void) {\n  const int x = 1;\n  x = 2;\n  return x;\n}", diagnostics::NOT_ASSIGNABLE),
+            ("struct s;\nint f(void) {\n  struct s v;\n  return 0;\n}", diagnostics::INCOMPLETE_TYPE),
+            ("int f(void) {\n  static int g(void);\n  return 0;\n}", diagnostics::INVALID_STORAGE_CLASS),
+            ("static int s;\ninline int f(void) {\n  return s;\n}", diagnostics::INVALID_INLINE),
+            ("int f(int n) {\n  static int a[n];\n  return 0;\n}", diagnostics::VARIABLY_MODIFIED_TYPE),
+            ("int f(void) {\n  int a[1.5];\n  return 0;\n}", diagnostics::INVALID_ARRAY_SIZE),
+            ("int a[1UL << 62][4];", diagnostics::INVALID_ARRAY_SIZE),
+            ("unsigned long n = sizeof(int[1UL << 62][4]);", diagnostics::INVALID_ARRAY_SIZE),
+            ("int f(void) {\n  int a[2] = { 1, 2, 3 };\n  return 0;\n}", diagnostics::INVALID_INITIALIZER),
+            ("struct b { int f : 3; } w;\nint f(void) {\n  return *&w.f;\n}", diagnostics::NOT_ADDRESSABLE),
+            ("void f(void) {\n  return 1;\n}", diagnostics::INVALID_RETURN),
+            ("void g(void);\nint f(void) {\n  return g();\n}", diagnostics::VOID_VALUE),
+            ("struct s { int m; } v;\nint f(void) {\n  return (int)v;\n}", diagnostics::INVALID_CAST),
+            ("struct s { int m; } v;\nint f(void) {\n  return v.n;\n}", diagnostics::UNKNOWN_MEMBER),
+            ("int f(int n) {\n  __builtin_va_list ap;\n  __builtin_va_start(ap, n);\n  return 0;\n}", diagnostics::NOT_VARIADIC),
+            ("int f(void) {\n  int x = 1;\n  return *x;\n}", diagnostics::INVALID_OPERANDS),
+            ("int f(int *p) {\n  const int *q = p;\n  int *r = q;\n  return 0;\n}", diagnostics::INCOMPATIBLE_TYPES)
+        ];
+        for (src, code) in cases {
+            let result = resolve_str(src).and_then(|(program, symbols)| crate::ast::typecheck::typecheck(program, &symbols));
+            let error = result.expect_err("Program should not compile").into_diagnostic();
+            assert_eq!(error.code, Some(code), "{}", src);
+            assert!(error.primary_span().is_some(), "{}", src);
+        }
+
+        // Enumerators, members, designators and initializer elements have spans of their own
+        let cases = [
+            ("enum e { A, B, A };", diagnostics::REDECLARATION, (1, 16)),
+            ("struct s {\n  int m;\n  long m;\n} v;", diagnostics::REDECLARATION, (3, 8)),
+            ("struct s {\n  int a;\n  int b : 40;\n} v;", diagnostics::INVALID_BIT_FIELD, (3, 7)),
+            ("struct s { int a; } v = { 1, .b = 2 };", diagnostics::UNKNOWN_MEMBER, (1, 30)),
+            ("int a[2] = { 1, 2, 3 };", diagnostics::INVALID_INITIALIZER, (1, 20)),
+            ("int f(void) {\n  int a[2] = { 1, 2, { 3 } };\n  return 0;\n}", diagnostics::INVALID_INITIALIZER, (2, 22)),
+            ("int f(void) {\n  int x = { 1, 2 };\n  return x;\n}", diagnostics::INVALID_INITIALIZER, (2, 11))
+        ];
+        for (src, code, (line, column)) in cases {
+            let map = SourceMap::new(src, "main.c");
+            let result = resolve_str(src).and_then(|(program, symbols)| crate::ast::typecheck::typecheck(program, &symbols));
+            let error = result.expect_err("Program should not compile").into_diagnostic();
+            assert_eq!(error.code, Some(code), "{}", src);
+            let location = map.locate(error.primary_span().expect("Error should have a span").start);
+            assert_eq!((location.line, location.column), (line, column), "{}", src);
+        }
+        let error = resolve_str("enum e { A, B, A };").expect_err("Program should not resolve").into_diagnostic();
+        assert_eq!(error.labels.len(), 2);
+
+        // Locals are named as in the source, not by their unique name
+        let messages = [
+            ("struct s;\nint f(void) {\n  struct s v;\n  return 0;\n}", "Variable `v` has incomplete type `struct s`"),
+            ("int g(int);\nint f(void) {\n  int (*p)(int) = g;\n  return p(1, 2);\n}", "Call to `p` expects 1 arguments, but 2 were given")
+        ];
+        for (src, message) in messages {
+            let result = resolve_str(src).and_then(|(program, symbols)| crate::ast::typecheck::typecheck(program, &symbols));
+            assert_eq!(result.expect_err("Program should not compile").into_diagnostic().message, message);
+        }
+
+        let src = "int f(void) {\n  int x = 1;\n  long x;\n  return x;\n}\n";
+        let error = resolve_str(src).expect_err("Program should not resolve").into_diagnostic();
+        assert_eq!(render(&error, Some(&SourceMap::new(src, "main.c")), false), concat!(
+            "error[E0102]: Redeclaration of `x`\n",
+            " --> main.c:3:3\n",
+            "  |\n",
+            "2 |   int x = 1;\n",
+            "  |   ---------- previously declared here\n",
+            "3 |   long x;\n",
+            "  |   ^^^^^^^\n"
+        ));
+    }
+
+    #[test]
+    fn test_literal_types() {
+        use crate::ast::symbols::LiteralError;
+
+        // Literals take the first type their value fits in, where only octal and
+        // hexadecimal literals may be unsigned without a `u` suffix
+        let literal = |src: &str| ConstantValue::from_literal(src);
+        assert_eq!(literal("2147483647"), Ok(ConstantValue::Int(2147483647)));
+        assert_eq!(literal("2147483648"), Ok(ConstantValue::Long(2147483648)));
+        assert_eq!(literal("0x80000000"), Ok(ConstantValue::UInt(0x80000000)));
+        assert_eq!(literal("0x100000000"), Ok(ConstantValue::Long(0x100000000)));
+        assert_eq!(literal("0xFFFFFFFFFFFFFFFF"), Ok(ConstantValue::ULong(u64::MAX)));
+        assert_eq!(literal("0755"), Ok(ConstantValue::Int(493)));
+        assert_eq!(literal("0"), Ok(ConstantValue::Int(0)));
+        assert_eq!(literal("4294967296u"), Ok(ConstantValue::ULong(4294967296)));
+        assert_eq!(literal("1ll"), Ok(ConstantValue::LongLong(1)));
+        assert_eq!(literal("0x1e"), Ok(ConstantValue::Int(30)));
+        assert_eq!(literal("2.5f"), Ok(ConstantValue::Float(2.5)));
+        assert_eq!(literal("9223372036854775808"), Err(LiteralError::OutOfRange));
+        assert_eq!(literal("18446744073709551616u"), Err(LiteralError::OutOfRange));
+        assert_eq!(literal("1e999"), Err(LiteralError::OutOfRange));
+        assert_eq!(literal("08"), Err(LiteralError::Invalid));
+        assert_eq!(literal("0x"), Err(LiteralError::Invalid));
+        assert_eq!(literal("1lul"), Err(LiteralError::Invalid));
+
+        // Literals too large for a signed type suggest making them unsigned
+        let (buffer, tokens) = tokenize("long f(void) { return 9223372036854775808; }");
+        let mut errors = ASTParser::new(buffer, tokens).parse().expect_err("Program should not parse");
+        let diagnostic = errors.remove(0).into_diagnostic();
+        assert_eq!(diagnostic.code, Some(crate::diagnostics::LITERAL_OUT_OF_RANGE));
+        assert_eq!(diagnostic.fixits[0].edits[0].replacement, "u");
+    }
+
+    #[test]
+    fn test_warnings() {
+        use crate::diagnostics::{Severity, warnings::{Warning, WarningOptions}};
+
+        let warnings = |src: &str| {
+            let (buffer, tokens) = tokenize(src);
+            let program = ASTParser::new(buffer, tokens).parse().expect("Program should parse");
+            let (program, symbols, mut warnings) = crate::ast::resolve::resolve(program).expect("Program should resolve");
+            warnings.extend(crate::ast::typecheck::typecheck(program, &symbols).expect("Program should typecheck").1);
+            warnings.iter().filter_map(|w| w.warning).collect::<Vec<_>>()
+        };
+        assert_eq!(warnings("int f(int a, int b) { int x = 1; int y; y = a; return y; }"), vec![Warning::UnusedParameter, Warning::UnusedVariable]);
+        assert_eq!(warnings("int f(void) { int x = 1; x = 2; }"), vec![Warning::ReturnType]);
+        assert_eq!(warnings("int main(void) { return f(1); } int f(int x) { return x; }"), vec![Warning::ImplicitFunctionDeclaration]);
+        assert_eq!(warnings("int x; int f(int y) { int x = y; { long y = x; return y; } }"), vec![Warning::Shadow, Warning::Shadow, Warning::Conversion]);
+        assert_eq!(warnings("int f(int a, unsigned b) { return (a < b) + (1 < b); }"), vec![Warning::SignCompare]);
+        assert!(warnings("void f(void) {} int main(void) { f(); }").is_empty());
+
+        // Options about a warning take precedence over those about its group, whatever their order
+        let options = |flags: &[&str]| {
+            let mut options = WarningOptions::default();
+            for flag in flags {
+                assert!(options.apply(flag), "`-W{}` should be a warning option", flag);
+            }
+            options
+        };
+        assert_eq!(options(&[]).severity(Warning::UnusedVariable), None);
+        assert_eq!(options(&[]).severity(Warning::ReturnType), Some(Severity::Warning));
+        assert_eq!(options(&[]).severity(Warning::ImplicitFunctionDeclaration), Some(Severity::Error));
+        assert_eq!(options(&["no-unused-variable", "all"]).severity(Warning::UnusedVariable), None);
+        assert_eq!(options(&["extra"]).severity(Warning::SignCompare), Some(Severity::Warning));
+        assert_eq!(options(&["error", "no-error=return-type"]).severity(Warning::ReturnType), Some(Severity::Warning));
+        assert_eq!(options(&["error=shadow"]).severity(Warning::Shadow), Some(Severity::Error));
+        assert_eq!(options(&["no-error=implicit-function-declaration"]).severity(Warning::ImplicitFunctionDeclaration), Some(Severity::Warning));
+        let mut suppressed = options(&["error=return-type"]);
+        suppressed.suppress();
+        assert_eq!(suppressed.severity(Warning::Conversion), None);
+        assert_eq!(suppressed.severity(Warning::ReturnType), Some(Severity::Error));
+        assert!(!WarningOptions::default().apply("no-such-warning"));
+    }
+
+    #[test]
+    fn test_diagnostics_formats() {
+        use crate::diagnostics::json;
+        use crate::lex::source::SourceMap;
+
+        let src = "int f(void) {\n  return 1 2;\n}\n";
+        let map = SourceMap::new(src, "dir/\"main\".c");
+        let (buffer, tokens) = tokenize(src);
+        let mut errors = ASTParser::new(buffer, tokens).parse().expect_err("Program should not parse");
+        let diagnostic = errors.remove(0).into_diagnostic();
+
+        let location = |line, start, end| format!(
+            "{{\"file\":\"dir/\\\"main\\\".c\",\"line\":{},\"column\":{},\"span\":{{\"start\":{{\"line\":{},\"column\":{}}},\"end\":{{\"line\":{},\"column\":{}}}}}}}",
+            line, start, line, start, line, end
+        );
+        assert_eq!(json::encode(&diagnostic, Some(&map)), format!(
+            "{{\"severity\":\"error\",\"code\":\"E0002\",\"message\":\"Expected `;` after expression, found `2`\",\"location\":{},\"labels\":[{{\"location\":{},\"message\":\"expected `;`\",\"primary\":true}}],\"notes\":[],\"fixits\":[{{\"edits\":[{{\"location\":{},\"replacement\":\";\"}}],\"message\":\"insert `;`\"}}]}}",
+            location(2, 12, 13), location(2, 12, 13), location(2, 11, 11)
+        ));
+
+        // SARIF results refer to their rule, and describe fix-its as replacements
+        let result = json::encode_sarif(&diagnostic, Some(&map));
+        assert!(result.starts_with("{\"ruleId\":\"E0002\",\"level\":\"error\","));
+        assert!(result.contains("\"region\":{\"startLine\":2,\"startColumn\":12,\"endLine\":2,\"endColumn\":13}"));
+        assert!(result.contains("\"replacements\":[{\"deletedRegion\":{\"startLine\":2,\"startColumn\":11,\"endLine\":2,\"endColumn\":11},\"insertedContent\":{\"text\":\";\"}}]"));
+        assert!(json::sarif_log(&[result], &["E0002"]).contains("\"rules\":[{\"id\":\"E0002\"}]"));
+    }
 }
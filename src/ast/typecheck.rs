@@ -0,0 +1,917 @@
+// Semantic analysis: type checking //
+// Determines the type of every expression, and makes the implicit
+// conversions of C explicit by wrapping operands in `Expression::Cast`,
+// so later passes only ever see operations on operands of equal type.
+// Implicit conversions that may change a value are reported as warnings.
+use super::symbols::*;
+use crate::diagnostics::{self, Diagnostic, Edit, warnings::Warning};
+use super::parser::ASTError;
+use super::table::{self, SymbolTable, BitField};
+use super::{consteval, initializer};
+
+
+// Returns the checked program, along with the warnings found in it
+pub fn typecheck(program: Program, symbols: &SymbolTable) -> Result<(Program, Vec<Diagnostic>), ASTError> {
+    let mut checked = Program::new();
+    let mut warnings = vec![];
+
+    for dec in program {
+        match dec {
+            Declaration::Function(def) => {
+                let mut checker = TypeChecker::new(symbols, &def.function_type);
+                if def.has_body {
+                    checker.check_signature(&def).map_err(|e| e.within(def.span))?;
+                }
+                let statements = checker.check_block(def.statements)?;
+                warnings.append(&mut checker.warnings);
+
+                // `main` returns 0 when it reaches its end, but other functions return garbage
+                let returns_value = matches!(&def.function_type, Type::Function(f) if *f.ret != Type::Void);
+                if def.has_body && returns_value && def.name != "main" && !statements.iter().any(has_return) {
+                    let end = Span { start: def.span.end - 1, end: def.span.end };
+                    let message = format!("Non-void function `{}` does not return a value", def.name);
+                    warnings.push(Diagnostic::warning(message).with_warning(Warning::ReturnType).with_label(end, ""));
+                }
+                checked.push(Declaration::Function(FunctionDefinition { statements, ..def }));
+            },
+
+            // Initializers of file-scope variables were already evaluated
+            // (and converted) by the resolver, but their type may only be
+            // completed later in the file, as their structure or array size
+            Declaration::Variable(var) => {
+                let is_definition = var.storage_class != Some(StorageClass::Extern) || var.initializer.is_some();
+                let ty = symbols.type_of(&var.name);
+                if is_definition && !symbols.is_complete(ty) {
+                    semantic_error!(INCOMPLETE_TYPE at var.span, "Variable `{}` has incomplete type `{}`", var.name, ty);
+                }
+                checked.push(Declaration::Variable(var));
+            },
+
+            Declaration::Struct(_) | Declaration::Enum(_) | Declaration::StaticAssert(_) => checked.push(dec)
+        }
+    }
+
+    return Ok((checked, warnings));
+}
+
+fn has_return(s: &Statement) -> bool {
+    match &s.kind {
+        StatementKind::Return(_) => true,
+        StatementKind::Compound(statements) => statements.iter().any(has_return),
+        _ => false
+    }
+}
+
+// Type of an expression that is not evaluated, such as the operand of `sizeof`
+pub fn type_of(e: Expression, symbols: &SymbolTable) -> Result<Type, ASTError> {
+    let mut checker = TypeChecker { symbols, return_type: Type::Void, is_variadic: false, warnings: vec![] };
+    let e = checker.check_object(e)?;
+    return Ok(e.ty().clone());
+}
+
+// Whether an expression that is not evaluated designates a bit-field, which has no size
+pub fn is_bit_field(e: Expression, symbols: &SymbolTable) -> Result<bool, ASTError> {
+    let mut checker = TypeChecker { symbols, return_type: Type::Void, is_variadic: false, warnings: vec![] };
+    let e = checker.check_object(e)?;
+    return Ok(checker.bit_field(&e).is_some());
+}
+
+
+struct TypeChecker<'a> {
+    symbols: &'a SymbolTable,
+    return_type: Type,
+    // Whether the function takes variable arguments, which `va_start` refers to
+    is_variadic: bool,
+    warnings: Vec<Diagnostic>
+}
+
+impl<'a> TypeChecker<'a> {
+    fn new(symbols: &'a SymbolTable, function_type: &Type) -> Self {
+        let (return_type, is_variadic) = match function_type {
+            Type::Function(f) => (*f.ret.clone(), f.variadic),
+            _ => panic!("Internal type error -- function without function type")
+        };
+
+        Self { symbols, return_type, is_variadic, warnings: vec![] }
+    }
+
+    // Parameters and return values of a function definition must have complete types
+    fn check_signature(&self, def: &FunctionDefinition) -> Result<(), ASTError> {
+        let Type::Function(f) = &def.function_type else {
+            panic!("Internal type error -- function without function type");
+        };
+
+        for param in &f.params {
+            if !self.symbols.is_complete(param) {
+                semantic_error!(INCOMPLETE_TYPE, "Parameter of `{}` has incomplete type `{}`", def.name, param);
+            }
+        }
+        if *f.ret != Type::Void && !self.symbols.is_complete(&f.ret) {
+            semantic_error!(INCOMPLETE_TYPE, "Function `{}` has incomplete return type `{}`", def.name, f.ret);
+        }
+
+        return Ok(());
+    }
+
+    fn check_block(&mut self, statements: Vec<Statement>) -> Result<Vec<Statement>, ASTError> {
+        let mut checked = vec![];
+        for s in statements {
+            let span = s.span;
+            checked.push(self.check_statement(s).map_err(|e| e.within(span))?);
+        }
+
+        return Ok(checked);
+    }
+
+    fn check_statement(&mut self, s: Statement) -> Result<Statement, ASTError> {
+        let kind = match s.kind {
+            StatementKind::Return(e) => {
+                if self.return_type == Type::Void {
+                    semantic_error!(INVALID_RETURN, "Void function should not return a value");
+                }
+
+                let e = self.check_expression(e)?;
+                let return_type = self.return_type.clone();
+                StatementKind::Return(self.convert_by_assignment(e, &return_type, "return")?)
+            },
+
+            StatementKind::Expression(e) => StatementKind::Expression(self.check_expression(e)?),
+
+            // Only automatic variables are initialized at runtime
+            StatementKind::Declaration(Declaration::Variable(var)) if var.storage_class.is_some() => {
+                StatementKind::Declaration(Declaration::Variable(var))
+            },
+            StatementKind::Declaration(Declaration::Variable(var)) => {
+                StatementKind::Declaration(Declaration::Variable(self.check_local_variable(var)?))
+            },
+            kind @ StatementKind::Declaration(_) => kind,
+
+            StatementKind::Compound(statements) => StatementKind::Compound(self.check_block(statements)?),
+            StatementKind::Null => StatementKind::Null
+        };
+
+        return Ok(Statement::new(kind, s.span));
+    }
+
+    fn check_local_variable(&mut self, var: VariableDeclaration) -> Result<VariableDeclaration, ASTError> {
+        if !self.symbols.is_complete(&var.var_type) {
+            semantic_error!(INCOMPLETE_TYPE, "Variable `{}` has incomplete type `{}`", table::source_name(&var.name), var.var_type);
+        }
+
+        let initializer = match var.initializer {
+            Some(init) => Some(self.check_initializer(init, &var.var_type)?),
+            None => None
+        };
+        return Ok(VariableDeclaration { initializer, ..var });
+    }
+
+    // Checks the initializer of an automatic variable; arrays and structures are padded
+    // with zero initializers, spanning the list they pad, so that every element and
+    // member is explicitly initialized
+    fn check_initializer(&mut self, init: Initializer, ty: &Type) -> Result<Initializer, ASTError> {
+        match (ty.unqualified(), init) {
+            // A string literal initializes the characters of an array one by one,
+            // followed by its terminating null if there is room for it
+            (Type::Array(element, count), Initializer::Single(Expression { kind: ExpressionKind::String(bytes), span, .. })) if initializer::is_character_array(ty) => {
+                if bytes.len() > *count {
+                    semantic_error!(INVALID_INITIALIZER at span, "Initializer-string for `{}` is too long", ty);
+                }
+
+                let mut checked = vec![];
+                for byte in bytes {
+                    let c = Expression::typed(ExpressionKind::Constant(ConstantValue::Int(byte as i32)), Type::Int, span);
+                    checked.push(Initializer::Single(Self::convert(c, element)?));
+                }
+                while checked.len() < *count {
+                    checked.push(self.zero_initializer(element, span));
+                }
+
+                Ok(Initializer::Compound(checked, span))
+            },
+            (Type::Array(element, count), Initializer::Compound(list, span)) => {
+                if list.len() > *count {
+                    semantic_error!(INVALID_INITIALIZER at list[*count].span(), "Excess elements in array initializer");
+                }
+
+                let mut checked = vec![];
+                for init in list {
+                    checked.push(self.check_initializer(init, element)?);
+                }
+                while checked.len() < *count {
+                    checked.push(self.zero_initializer(element, span));
+                }
+
+                Ok(Initializer::Compound(checked, span))
+            },
+            (Type::Array(_, _), Initializer::Single(e)) => {
+                semantic_error!(INVALID_INITIALIZER at e.span, "Array must be initialized with a brace-enclosed initializer list");
+            },
+            // A union is initialized through a single member, the first one unless designated
+            (Type::Structure(StructKind::Union, tag), Initializer::Compound(mut list, span)) => {
+                let definition = self.symbols.get_struct(tag).expect("Internal type error -- incomplete union");
+                let init = match list.pop() {
+                    Some(init) => init,
+                    None => return Ok(self.zero_initializer(ty, span))
+                };
+
+                match init.union_member() {
+                    (Some(name), init) => {
+                        let member = definition.members.iter().find(|m| m.name == name).expect("Internal type error -- unknown union member");
+                        let (name, member_type) = (name.to_string(), member.ty.clone());
+                        let init = self.check_initializer(init.clone(), &member_type)?;
+                        Ok(Initializer::Compound(vec![Initializer::Designated(vec![Designator::Member(name, span)], Box::new(init))], span))
+                    },
+                    (None, init) => {
+                        let member_type = definition.members[0].ty.clone();
+                        Ok(Initializer::Compound(vec![self.check_initializer(init.clone(), &member_type)?], span))
+                    }
+                }
+            },
+            (Type::Structure(_, tag), Initializer::Compound(list, span)) => {
+                let members = self.struct_members(tag);
+                let count = members.len();
+                if list.len() > count {
+                    semantic_error!(INVALID_INITIALIZER at list[count].span(), "Excess elements in initializer of `{}`", ty);
+                }
+
+                let mut checked = vec![];
+                for (init, member) in list.into_iter().zip(&members) {
+                    checked.push(self.check_initializer(init, member)?);
+                }
+                while checked.len() < count {
+                    checked.push(self.zero_initializer(&members[checked.len()], span));
+                }
+
+                Ok(Initializer::Compound(checked, span))
+            },
+            (_, Initializer::Compound(_, span)) => {
+                semantic_error!(INVALID_INITIALIZER at span, "Scalar `{}` cannot be initialized with an initializer list", ty);
+            },
+            (_, Initializer::Single(e)) => {
+                let e = self.check_expression(e)?;
+                Ok(Initializer::Single(self.convert_by_assignment(e, ty, "initialization")?))
+            },
+            (_, Initializer::Designated(_, _)) => panic!("Internal type error -- designated initializer was not normalized")
+        }
+    }
+
+    fn zero_initializer(&self, ty: &Type, span: Span) -> Initializer {
+        match ty.unqualified() {
+            Type::Array(element, count) => Initializer::Compound(vec![self.zero_initializer(element, span); *count], span),
+            Type::Structure(kind, tag) => {
+                let members = self.struct_members(tag);
+                let count = match kind {
+                    StructKind::Struct => members.len(),
+                    StructKind::Union => 1
+                };
+                Initializer::Compound(members.iter().take(count).map(|m| self.zero_initializer(m, span)).collect(), span)
+            },
+            _ => {
+                let zero = Expression::typed(ExpressionKind::Constant(ConstantValue::Int(0)), Type::Int, span);
+                Initializer::Single(Self::convert(zero, ty).expect("Zero converts to any scalar"))
+            }
+        }
+    }
+
+    // Types of the members of a complete structure, in order
+    fn struct_members(&self, tag: &str) -> Vec<Type> {
+        match self.symbols.get_struct(tag) {
+            Some(definition) => definition.members.iter().map(|m| m.ty.clone()).collect(),
+            None => panic!("Internal type error -- incomplete structure `{}`", tag)
+        }
+    }
+
+
+    // Checks an expression whose value is used, so arrays decay into a pointer to
+    // their first element, functions into a pointer to them, and the value of
+    // a qualified object has the unqualified type, e.g. `int` for a `const int`
+    fn check_expression(&mut self, e: Expression) -> Result<Expression, ASTError> {
+        let span = e.span;
+        let e = self.check_object(e)?;
+        return self.decay(e).map_err(|e| e.within(span));
+    }
+
+    fn decay(&self, e: Expression) -> Result<Expression, ASTError> {
+        // Bit-fields whose values all fit in an `int` are promoted to it, even if unsigned
+        if let Some(bit_field) = self.bit_field(&e) {
+            let fits = match e.ty().is_signed() {
+                true => bit_field.width <= 32,
+                false => bit_field.width < 32
+            };
+            if fits && !matches!(e.ty().unqualified(), Type::Bool | Type::Int) {
+                return Self::convert(e, &Type::Int);
+            }
+        }
+
+        match e.ty().unqualified().clone() {
+            ty @ Type::Structure(_, _) if !self.symbols.is_complete(&ty) => {
+                semantic_error!(INCOMPLETE_TYPE, "Incomplete type `{}` used as a value", ty);
+            },
+            ty @ (Type::Array(_, _) | Type::VariableArray(_, _)) => {
+                let (Type::Array(element, _) | Type::VariableArray(element, _)) = &ty else { unreachable!() };
+                let pointer = Type::Pointer(element.clone());
+                let span = e.span;
+                let address = Expression::typed(ExpressionKind::AddressOf(Box::new(e)), Type::Pointer(Box::new(ty.clone())), span);
+                Ok(Expression::typed(ExpressionKind::Cast(pointer.clone(), Box::new(address)), pointer, span))
+            },
+            ty @ Type::Function(_) => {
+                let pointer = Type::Pointer(Box::new(ty));
+                let span = e.span;
+                Ok(Expression::typed(ExpressionKind::AddressOf(Box::new(e)), pointer, span))
+            },
+            ty => Ok(Expression { ty: Some(ty), ..e })
+        }
+    }
+
+    // Checks an expression without converting arrays to pointers, which is only done for
+    // the operand of `&`, the left side of assignments and structures whose member is
+    // accessed. Objects keep their qualifiers, as in `const int *` for `&x` with `const int x`
+    fn check_object(&mut self, e: Expression) -> Result<Expression, ASTError> {
+        let span = e.span;
+        return self.check_object_inner(e).map_err(|e| e.within(span));
+    }
+
+    fn check_object_inner(&mut self, e: Expression) -> Result<Expression, ASTError> {
+        let span = e.span;
+        let (kind, ty) = match e.kind {
+            ExpressionKind::Constant(c) => {
+                let ty = c.ty();
+                (ExpressionKind::Constant(c), ty)
+            },
+            // The array of a string literal includes its terminating null
+            ExpressionKind::String(bytes) => {
+                let ty = Type::Array(Box::new(Type::Char), bytes.len() + 1);
+                (ExpressionKind::String(bytes), ty)
+            },
+
+            ExpressionKind::Variable(name) => {
+                let ty = self.symbols.type_of(&name).clone();
+                (ExpressionKind::Variable(name), ty)
+            },
+
+            // The object of a compound literal in a block is initialized like an automatic variable
+            ExpressionKind::CompoundLiteral(literal) => {
+                if !self.symbols.is_complete(&literal.var_type) {
+                    semantic_error!(INCOMPLETE_TYPE, "Compound literal has incomplete type `{}`", literal.var_type);
+                }
+                let literal = self.check_local_variable(*literal)?;
+                let ty = literal.var_type.clone();
+                (ExpressionKind::CompoundLiteral(Box::new(literal)), ty)
+            },
+
+            // The operand of an arithmetic unary operator is promoted; only
+            // negation is defined for floating operands
+            ExpressionKind::Unary(op, inner) => {
+                let inner = self.check_expression(*inner)?;
+                let valid = match op {
+                    UnaryExpressionType::Negation => inner.ty().is_arithmetic(),
+                    UnaryExpressionType::Complement => inner.ty().is_integer()
+                };
+                if !valid {
+                    semantic_error!(INVALID_OPERANDS, "Invalid argument type `{}` to unary expression", inner.ty());
+                }
+                let promoted = inner.ty().promote();
+                let inner = Self::convert(inner, &promoted)?;
+
+                (ExpressionKind::Unary(op, Box::new(inner)), promoted)
+            },
+
+            ExpressionKind::Binary(op, left, right) => {
+                let left = self.check_expression(*left)?;
+                let right = self.check_expression(*right)?;
+                let (left_type, right_type) = (left.ty().clone(), right.ty().clone());
+                if left_type.is_pointer() || right_type.is_pointer() {
+                    return self.check_pointer_binary(op, left, right);
+                }
+                if !left_type.is_arithmetic() || !right_type.is_arithmetic() {
+                    semantic_error!(INVALID_OPERANDS at span, "Invalid operands to binary expression (`{}` and `{}`)", left_type, right_type);
+                }
+                // Remainders, bitwise operators and shifts are only defined for integers
+                let integer_only = op.is_shift() || matches!(op, BinaryExpressionType::Remainder | BinaryExpressionType::BitAnd | BinaryExpressionType::BitOr | BinaryExpressionType::BitXor);
+                if integer_only && (left_type.is_floating() || right_type.is_floating()) {
+                    semantic_error!(INVALID_OPERANDS at span, "Invalid operands to binary expression (`{}` and `{}`)", left_type, right_type);
+                }
+
+                // Both operands of a shift are promoted independently
+                if op.is_shift() {
+                    let left = Self::convert(left, &left_type.promote())?;
+                    let right = Self::convert(right, &right_type.promote())?;
+
+                    (ExpressionKind::Binary(op, Box::new(left), Box::new(right)), left_type.promote())
+                } else {
+                    // Every other operator converts both operands to their common type
+                    let common = Type::common(&left_type, &right_type);
+                    if op.is_comparison() {
+                        self.check_sign_compare(&left, &right, &common, span);
+                    }
+                    let left = Self::convert(left, &common)?;
+                    let right = Self::convert(right, &common)?;
+
+                    let result_type = if op.is_comparison() { Type::Int } else { common };
+                    (ExpressionKind::Binary(op, Box::new(left), Box::new(right)), result_type)
+                }
+            },
+
+            ExpressionKind::Assignment(left, right) => {
+                let left = self.check_object(*left)?;
+                let left_type = left.ty().clone();
+                if let Type::Array(_, _) | Type::VariableArray(_, _) = left_type {
+                    semantic_error!(NOT_ASSIGNABLE at left.span, "Array type `{}` is not assignable", left_type);
+                }
+                if let Type::Function(_) = left_type {
+                    semantic_error!(NOT_ASSIGNABLE at left.span, "Function type `{}` is not assignable", left_type);
+                }
+                if self.symbols.is_read_only(&left_type) {
+                    semantic_error!(NOT_ASSIGNABLE at left.span, "Cannot assign to read-only object of type `{}`", left_type);
+                }
+
+                let right = self.check_expression(*right)?;
+                let left_type = left_type.unqualified().clone();
+                let right = self.convert_by_assignment(right, &left_type, "assignment")?;
+
+                (ExpressionKind::Assignment(Box::new(left), Box::new(right)), left_type)
+            },
+
+            // Calls by name are kept direct, while any other callee is a pointer to a function
+            ExpressionKind::FunctionCall(function, arguments) => {
+                let function = self.check_object(*function)?;
+                let function = match (&function.kind, function.ty()) {
+                    (ExpressionKind::Variable(_), Type::Function(_)) => function,
+                    _ => self.decay(function)?
+                };
+                let function_type = match function.ty() {
+                    Type::Function(f) => f.clone(),
+                    Type::Pointer(inner) if matches!(**inner, Type::Function(_)) => {
+                        let Type::Function(f) = &**inner else { unreachable!() };
+                        f.clone()
+                    },
+                    ty => {
+                        semantic_error!(INVALID_OPERANDS, "Called object type `{}` is not a function or function pointer", ty);
+                    }
+                };
+                let callee = match &function.kind {
+                    ExpressionKind::Variable(name) => format!("`{}`", table::source_name(name)),
+                    _ => "function pointer".to_string()
+                };
+
+                if function_type.variadic && arguments.len() < function_type.params.len() {
+                    semantic_error!(ARGUMENT_COUNT at span, "Call to {} expects at least {} arguments, but {} were given", callee, function_type.params.len(), arguments.len());
+                }
+                if !function_type.variadic && function_type.params.len() != arguments.len() {
+                    semantic_error!(ARGUMENT_COUNT at span, "Call to {} expects {} arguments, but {} were given", callee, function_type.params.len(), arguments.len());
+                }
+
+                // Arguments are converted as if by assignment to their parameters, while
+                // variable arguments only undergo the default argument promotions
+                let mut checked = vec![];
+                for (i, argument) in arguments.into_iter().enumerate() {
+                    let argument = self.check_expression(argument)?;
+                    let context = format!("argument {} of {}", i + 1, callee);
+                    match function_type.params.get(i) {
+                        Some(param_type) => checked.push(self.convert_by_assignment(argument, param_type, &context)?),
+                        None if *argument.ty() == Type::Void => {
+                            semantic_error!(VOID_VALUE, "Invalid use of void expression in {}", context);
+                        },
+                        None => {
+                            let promoted = argument.ty().promote_argument();
+                            checked.push(Self::convert(argument, &promoted)?);
+                        }
+                    }
+                }
+
+                if *function_type.ret != Type::Void && !self.symbols.is_complete(&function_type.ret) {
+                    semantic_error!(INCOMPLETE_TYPE, "Calling {} with incomplete return type `{}`", callee, function_type.ret);
+                }
+                (ExpressionKind::FunctionCall(Box::new(function), checked), *function_type.ret)
+            },
+
+            // Anything can be cast to void to discard its value; otherwise
+            // casts are only defined between scalar types
+            ExpressionKind::Cast(ty, inner) => {
+                let inner = self.check_expression(*inner)?;
+                let ty = ty.unqualified().clone();
+                if ty == Type::Void {
+                    (ExpressionKind::Cast(ty, Box::new(inner)), Type::Void)
+                } else {
+                    let inner_type = inner.ty().clone();
+                    if !ty.is_scalar() || !inner_type.is_scalar() {
+                        semantic_error!(INVALID_CAST, "Invalid cast from `{}` to `{}`", inner_type, ty);
+                    }
+                    // Pointers can't be converted to or from floating types
+                    if (ty.is_pointer() && inner_type.is_floating()) || (ty.is_floating() && inner_type.is_pointer()) {
+                        semantic_error!(INVALID_CAST, "Invalid cast from `{}` to `{}`", inner_type, ty);
+                    }
+
+                    // Casting between pointers and integers of a different size loses bits
+                    // (or makes them up), which is rarely intended for anything but constants.
+                    // Casting a pointer to `_Bool` tests it instead
+                    if ty.is_pointer() != inner_type.is_pointer() && ty.size() != inner_type.size() && ty != Type::Bool && consteval::evaluate(&inner).is_none() {
+                        let warning = if ty.is_pointer() { Warning::IntToPointerCast } else { Warning::PointerToIntCast };
+                        self.warnings.push(Diagnostic::warning(format!("Cast from `{}` to `{}` of different size", inner_type, ty)).with_warning(warning).with_label(span, ""));
+                    }
+
+                    return Self::convert(inner, &ty);
+                }
+            },
+
+            ExpressionKind::AddressOf(inner) => {
+                if !inner.is_lvalue() {
+                    semantic_error!(NOT_ADDRESSABLE, "Cannot take the address of an rvalue");
+                }
+
+                let inner = self.check_object(*inner)?;
+                if let ExpressionKind::Dot(_, name) | ExpressionKind::Arrow(_, name) = &inner.kind {
+                    if self.bit_field(&inner).is_some() {
+                        semantic_error!(NOT_ADDRESSABLE, "Cannot take the address of bit-field `{}`", name);
+                    }
+                }
+
+                let ty = Type::Pointer(Box::new(inner.ty().clone()));
+                (ExpressionKind::AddressOf(Box::new(inner)), ty)
+            },
+
+            // `a[i]` is `*(a + i)`, so the subscript may also come first, as in `i[a]`
+            ExpressionKind::Subscript(left, right) => {
+                let left = self.check_expression(*left)?;
+                let right = self.check_expression(*right)?;
+
+                let (pointer, index) = match (left.ty(), right.ty()) {
+                    (Type::Pointer(_), ty) if ty.is_integer() => (left, right),
+                    (ty, Type::Pointer(_)) if ty.is_integer() => (right, left),
+                    (left_type, right_type) => {
+                        semantic_error!(INVALID_OPERANDS, "Subscripted value is not an array or pointer (`{}` and `{}`)", left_type, right_type);
+                    }
+                };
+                self.check_arithmetic_pointee(pointer.ty())?;
+
+                let element = pointer.ty().pointee().cloned().expect("Subscripted value is a pointer");
+                let index = Self::convert(index, &Type::Long)?;
+                (ExpressionKind::Subscript(Box::new(pointer), Box::new(index)), element)
+            },
+
+            ExpressionKind::Dereference(inner) => {
+                let inner = self.check_expression(*inner)?;
+                let pointee = match inner.ty().pointee() {
+                    Some(pointee) if *pointee.unqualified() == Type::Void => {
+                        semantic_error!(INVALID_OPERANDS, "Dereferencing a `void *` pointer");
+                    },
+                    Some(pointee) => pointee.clone(),
+                    None => {
+                        semantic_error!(INVALID_OPERANDS, "Indirection requires a pointer operand (`{}` invalid)", inner.ty());
+                    }
+                };
+
+                (ExpressionKind::Dereference(Box::new(inner)), pointee)
+            },
+
+            // Members of a qualified structure have its qualifiers as well
+            ExpressionKind::Dot(inner, name) => {
+                let inner = self.check_object(*inner)?;
+                if !matches!(inner.ty().unqualified(), Type::Structure(_, _)) {
+                    semantic_error!(INVALID_OPERANDS, "Member reference base type `{}` is not a structure or union", inner.ty());
+                }
+
+                let member = self.check_member(inner.ty(), &name)?;
+                (ExpressionKind::Dot(Box::new(inner), name), member)
+            },
+
+            ExpressionKind::Arrow(inner, name) => {
+                let inner = self.check_expression(*inner)?;
+                let pointee = match inner.ty().pointee() {
+                    Some(pointee) if matches!(pointee.unqualified(), Type::Structure(_, _)) => pointee.clone(),
+                    _ => {
+                        semantic_error!(INVALID_OPERANDS, "Member reference type `{}` is not a pointer to a structure or union", inner.ty());
+                    }
+                };
+
+                let member = self.check_member(&pointee, &name)?;
+                (ExpressionKind::Arrow(Box::new(inner), name), member)
+            },
+
+            ExpressionKind::SizeOf(_) | ExpressionKind::SizeOfType(_) | ExpressionKind::AlignOf(_) => {
+                panic!("Internal type error -- `sizeof` should have been folded by the resolver");
+            },
+
+            // `va_start` points a `va_list` at the arguments after the parameters of
+            // the enclosing function, and each `va_arg` fetches the next one
+            ExpressionKind::VaStart(ap) => {
+                if !self.is_variadic {
+                    semantic_error!(NOT_VARIADIC, "`va_start` used in a function with fixed arguments");
+                }
+                let ap = self.check_va_list(*ap)?;
+                (ExpressionKind::VaStart(Box::new(ap)), Type::Void)
+            },
+            ExpressionKind::VaArg(ap, ty) => {
+                let ap = self.check_va_list(*ap)?;
+                let ty = ty.unqualified().clone();
+                if !(ty.is_scalar() || matches!(ty, Type::Structure(_, _))) || !self.symbols.is_complete(&ty) {
+                    semantic_error!(INVALID_OPERANDS, "Invalid type `{}` for `va_arg`", ty);
+                }
+
+                let promoted = ty.promote_argument();
+                if promoted != ty {
+                    let message = format!("`{}` is promoted to `{}` when passed through `...`, so `va_arg` should use `{}`", ty, promoted, promoted);
+                    self.warnings.push(Diagnostic::warning(message).with_warning(Warning::Varargs).with_label(span, ""));
+                }
+                (ExpressionKind::VaArg(Box::new(ap), ty.clone()), ty)
+            }
+        };
+
+        return Ok(Expression::typed(kind, ty, span));
+    }
+
+    // A `va_list` is used through a pointer to it, which is what it decays to
+    fn check_va_list(&mut self, ap: Expression) -> Result<Expression, ASTError> {
+        let ap = self.check_expression(ap)?;
+        if !ap.ty().is_va_list_pointer() {
+            semantic_error!(INCOMPATIBLE_TYPES, "Expected a `va_list`, but got `{}`", ap.ty());
+        }
+
+        return Ok(ap);
+    }
+
+    // Type of a member of a structure, which must be complete, with the qualifiers of both
+    fn check_member(&self, ty: &Type, name: &str) -> Result<Type, ASTError> {
+        if !self.symbols.is_complete(ty) {
+            semantic_error!(INCOMPLETE_TYPE, "Member access into incomplete type `{}`", ty);
+        }
+
+        match self.symbols.member(ty, name) {
+            Some(member) => Ok(member.ty.clone().qualified(ty.qualifiers())),
+            None => {
+                semantic_error!(UNKNOWN_MEMBER, "No member named `{}` in `{}`", name, ty);
+            }
+        }
+    }
+
+    // The bit-field a checked member access designates, if any
+    fn bit_field(&self, e: &Expression) -> Option<BitField> {
+        let member = match &e.kind {
+            ExpressionKind::Dot(inner, name) => self.symbols.member(inner.ty(), name),
+            ExpressionKind::Arrow(inner, name) => self.symbols.member(inner.ty().pointee()?, name),
+            _ => None
+        };
+        return member?.bit_field;
+    }
+
+    // Pointer arithmetic is only defined between a pointer and an integer, which is
+    // converted to `long` so it can be scaled by the size of the pointee; the difference
+    // of two pointers is a `long` as well. Pointers can also be compared with each other
+    fn check_pointer_binary(&self, op: BinaryExpressionType, left: Expression, right: Expression) -> Result<Expression, ASTError> {
+        use BinaryExpressionType as B;
+        let span = left.span.to(right.span);
+        let (left_type, right_type) = (left.ty().clone(), right.ty().clone());
+
+        match op {
+            B::Add | B::Subtract if left_type.is_pointer() != right_type.is_pointer() => {
+                // The pointer always ends up on the left of the expression
+                let (pointer, integer) = if left_type.is_pointer() {
+                    (left, right)
+                } else if op == B::Add {
+                    (right, left)
+                } else {
+                    semantic_error!(INVALID_OPERANDS at span, "Invalid operands to binary expression (`{}` and `{}`)", left_type, right_type);
+                };
+
+                if !integer.ty().is_integer() {
+                    semantic_error!(INVALID_OPERANDS at span, "Invalid operands to binary expression (`{}` and `{}`)", pointer.ty(), integer.ty());
+                }
+                self.check_arithmetic_pointee(pointer.ty())?;
+
+                let pointer_type = pointer.ty().clone();
+                let integer = Self::convert(integer, &Type::Long)?;
+                Ok(Expression::typed(ExpressionKind::Binary(op, Box::new(pointer), Box::new(integer)), pointer_type, span))
+            },
+
+            B::Subtract => {
+                if !Self::same_pointee(&left_type, &right_type) {
+                    semantic_error!(INCOMPATIBLE_TYPES, "`{}` and `{}` are not pointers to compatible types", left_type, right_type);
+                }
+                self.check_arithmetic_pointee(&left_type)?;
+
+                Ok(Expression::typed(ExpressionKind::Binary(op, Box::new(left), Box::new(right)), Type::Long, span))
+            },
+
+            B::Equal | B::NotEqual => {
+                let common = Self::common_pointer_type(&left, &right)?;
+                let left = Self::convert(left, &common)?;
+                let right = Self::convert(right, &common)?;
+
+                Ok(Expression::typed(ExpressionKind::Binary(op, Box::new(left), Box::new(right)), Type::Int, span))
+            },
+
+            B::Less | B::LessEqual | B::Greater | B::GreaterEqual => {
+                if !Self::same_pointee(&left_type, &right_type) {
+                    semantic_error!(INCOMPATIBLE_TYPES, "Comparison of distinct pointer types (`{}` and `{}`)", left_type, right_type);
+                }
+
+                Ok(Expression::typed(ExpressionKind::Binary(op, Box::new(left), Box::new(right)), Type::Int, span))
+            },
+
+            _ => {
+                semantic_error!(INVALID_OPERANDS at span, "Invalid operands to binary expression (`{}` and `{}`)", left_type, right_type);
+            }
+        }
+    }
+
+    // Arithmetic on `void *` would need the size of `void`, and
+    // likewise for pointers to other incomplete types
+    fn check_arithmetic_pointee(&self, pointer_type: &Type) -> Result<(), ASTError> {
+        match pointer_type.pointee().map(Type::unqualified) {
+            Some(Type::Void) => {
+                semantic_error!(INVALID_OPERANDS, "Arithmetic on a pointer to void");
+            },
+            Some(Type::Function(_)) => {
+                semantic_error!(INVALID_OPERANDS, "Arithmetic on a pointer to function type `{}`", pointer_type);
+            },
+            Some(pointee) if !self.symbols.is_complete(pointee) => {
+                semantic_error!(INVALID_OPERANDS, "Arithmetic on a pointer to incomplete type `{}`", pointee);
+            },
+            _ => {}
+        }
+
+        return Ok(());
+    }
+
+    // Type two pointers are converted to before comparing them for equality
+    fn common_pointer_type(left: &Expression, right: &Expression) -> Result<Type, ASTError> {
+        let (left_type, right_type) = (left.ty(), right.ty());
+
+        if left_type == right_type {
+            return Ok(left_type.clone());
+        }
+        if consteval::is_null_pointer_constant(left) {
+            return Ok(right_type.clone());
+        }
+        if consteval::is_null_pointer_constant(right) {
+            return Ok(left_type.clone());
+        }
+        // Either pointee is `void`, or both are the same type, which has the qualifiers of both sides
+        if Type::pointer_conversion(left_type, right_type).is_some() {
+            let (left_pointee, right_pointee) = (left_type.pointee().expect("Pointer"), right_type.pointee().expect("Pointer"));
+            let pointee = match left_pointee.unqualified() {
+                Type::Void => Type::Void,
+                _ => right_pointee.unqualified().clone()
+            };
+            let qualifiers = left_pointee.qualifiers().union(right_pointee.qualifiers());
+            return Ok(Type::Pointer(Box::new(pointee.qualified(qualifiers))));
+        }
+
+        semantic_error!(INCOMPATIBLE_TYPES, "Comparison of distinct pointer types (`{}` and `{}`)", left_type, right_type);
+    }
+
+    // Pointers to the same type, regardless of its qualifiers
+    fn same_pointee(left: &Type, right: &Type) -> bool {
+        match (left.pointee(), right.pointee()) {
+            (Some(left), Some(right)) => Type::compatible(left, right),
+            _ => false
+        }
+    }
+
+    // Conversions as if by assignment, which are only implicit between
+    // arithmetic types, from null pointer constants to pointers, from
+    // pointers to `_Bool`, and between `void *` and other pointers, as long as the type pointed to
+    // keeps its qualifiers. The context describes where the conversion
+    // happens, for diagnostics
+    fn convert_by_assignment(&mut self, e: Expression, to: &Type, context: &str) -> Result<Expression, ASTError> {
+        let from = e.ty().clone();
+        let to = to.unqualified();
+
+        if from == *to {
+            return Ok(e);
+        }
+        if from.is_arithmetic() && to.is_arithmetic() {
+            self.check_narrowing(&e, to, context);
+            return Self::convert(e, to);
+        }
+        if to.is_pointer() && consteval::is_null_pointer_constant(&e) {
+            return Self::convert(e, to);
+        }
+        // Pointers convert to `_Bool` by comparison with the null pointer
+        if from.is_pointer() && *to == Type::Bool {
+            return Self::convert(e, to);
+        }
+        match Type::pointer_conversion(&from, to) {
+            Some(discarded) if discarded.is_empty() => return Self::convert(e, to),
+            Some(discarded) => {
+                semantic_error!(INCOMPATIBLE_TYPES, "Conversion from `{}` to `{}` in {} discards the `{}` qualifier of the type pointed to", from, to, context, discarded);
+            },
+            None => {}
+        }
+
+        match (&from, to) {
+            (Type::Void, _) => {
+                semantic_error!(VOID_VALUE, "Void value not ignored as it ought to be");
+            },
+            (from, to) if (from.is_integer() && to.is_pointer()) || (from.is_pointer() && to.is_integer()) => {
+                let kinds = if from.is_integer() { "integer to pointer" } else { "pointer to integer" };
+                let message = format!("Incompatible {} conversion from `{}` to `{}` in {}", kinds, from, to, context);
+                // The expression is parenthesized, as the cast would otherwise only apply to its first operand
+                let edits = vec![
+                    Edit { span: Span { start: e.span.start, end: e.span.start }, replacement: format!("({})(", to) },
+                    Edit { span: Span { start: e.span.end, end: e.span.end }, replacement: ")".to_string() }
+                ];
+                let diagnostic = Diagnostic::error(message)
+                    .with_code(diagnostics::INCOMPATIBLE_TYPES)
+                    .with_label(e.span, format!("has type `{}`", from))
+                    .with_fixit_edits(edits, "an explicit cast is required");
+                return Err(ASTError::SemanticError(diagnostic));
+            },
+            _ => {
+                semantic_error!(INCOMPATIBLE_TYPES at e.span, "Incompatible types: cannot convert `{}` to `{}` in {}", from, to, context);
+            }
+        }
+    }
+
+    // Floating constants are shown in their shortest exact form, e.g. `1e300`
+    // rather than with all 301 digits of its integer part
+    fn display_constant(constant: &ConstantValue) -> String {
+        let (scientific, debug) = match constant {
+            ConstantValue::Float(v) => (format!("{:e}", v), format!("{:?}", v)),
+            ConstantValue::Double(v) => (format!("{:e}", v), format!("{:?}", v)),
+            constant => return constant.to_string()
+        };
+        return if scientific.len() < debug.len() { scientific } else { debug };
+    }
+
+    // Warns about implicit arithmetic conversions that may not preserve the value
+    // being converted: constants are only reported if their value changes, and
+    // other expressions if the new type can't represent every value of the old
+    fn check_narrowing(&mut self, e: &Expression, to: &Type, context: &str) {
+        let from = e.ty();
+
+        if let Some(constant) = consteval::evaluate_arithmetic(e) {
+            // Integer targets go through their value, as narrow types have no literals
+            let (converted, exact) = match to.is_integer() {
+                true => {
+                    let value = constant.integer_value(to);
+                    let exact = value.is_some_and(|v| match constant.is_floating() {
+                        true => v as f64 == constant.to_float(),
+                        false => constant.integer_value(from) == Some(v)
+                    });
+                    (value.map(|v| v.to_string()), exact)
+                },
+                false => {
+                    let converted = constant.convert(to);
+                    let exact = converted.as_ref().and_then(|c| c.convert(from)).as_ref() == Some(&constant);
+                    (converted.as_ref().map(Self::display_constant), exact)
+                }
+            };
+            if !exact {
+                let result = converted.unwrap_or("an undefined value".to_string());
+                let message = format!("Implicit conversion from `{}` to `{}` in {} changes value from {} to {}", from, to, context, Self::display_constant(&constant), result);
+                self.warnings.push(Diagnostic::warning(message).with_warning(Warning::ConstantConversion).with_label(e.span, ""));
+            }
+            return;
+        }
+
+        // Integers as wide as a floating type have more digits than its significand,
+        // while converting to `_Bool` only tests whether the value is 0
+        let narrowing = *to != Type::Bool && match (from.is_floating(), to.is_floating()) {
+            (true, true) | (false, false) => to.size() < from.size(),
+            (true, false) => true,
+            (false, true) => from.size() >= to.size()
+        };
+        if narrowing {
+            let message = format!("Implicit conversion from `{}` to `{}` in {} may change its value", from, to, context);
+            self.warnings.push(Diagnostic::warning(message).with_warning(Warning::Conversion).with_label(e.span, ""));
+        }
+    }
+
+    // Comparing a signed value with an unsigned one converts it to unsigned, so
+    // negative values compare greater than every positive one, as in `-1 < 1u`.
+    // Signed constants which aren't negative keep their value, so are fine
+    fn check_sign_compare(&mut self, left: &Expression, right: &Expression, common: &Type, span: Span) {
+        if !common.is_integer() || common.is_signed() {
+            return;
+        }
+
+        let is_signed = |e: &Expression| e.ty().is_integer() && e.ty().is_signed() && consteval::evaluate(e).is_none_or(|(value, _)| value < 0);
+        if is_signed(left) || is_signed(right) {
+            let message = format!("Comparison of integers of different signs: `{}` and `{}`", left.ty(), right.ty());
+            self.warnings.push(Diagnostic::warning(message).with_warning(Warning::SignCompare).with_label(span, ""));
+        }
+    }
+
+    // Converts an expression to the given type, folding the conversion
+    // of constants so they don't need to be converted at runtime
+    fn convert(e: Expression, to: &Type) -> Result<Expression, ASTError> {
+        let from = e.ty().clone();
+        let to = to.unqualified();
+        if from == *to {
+            return Ok(e);
+        }
+        if from == Type::Void {
+            semantic_error!(VOID_VALUE, "Void value not ignored as it ought to be");
+        }
+
+        if let ExpressionKind::Constant(c) = &e.kind {
+            if let Some(folded) = c.convert(to) {
+                return Ok(Expression::typed(ExpressionKind::Constant(folded), to.clone(), e.span));
+            }
+        }
+
+        let span = e.span;
+        return Ok(Expression::typed(ExpressionKind::Cast(to.clone(), Box::new(e)), to.clone(), span));
+    }
+}
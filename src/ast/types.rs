@@ -0,0 +1,461 @@
+// Types of declarations and expressions //
+#![allow(dead_code)]
+use std::fmt;
+use super::symbols::Expression;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Type {
+    Void,
+    // `_Bool` only holds 0 and 1: converting any other value to it yields 1
+    Bool,
+    Char,
+    SChar,
+    UChar,
+    Short,
+    UShort,
+    Int,
+    UInt,
+    Long,
+    ULong,
+    LongLong,
+    ULongLong,
+    Float,
+    Double,
+    Pointer(Box<Type>),
+    Array(Box<Type>, usize),
+    // Arrays whose size the parser can't evaluate, as it depends on the layout
+    // of a structure or the type of an expression, e.g. `int a[sizeof(struct s)]`.
+    // The resolver replaces them with an `Array` once their size is known
+    DeferredArray(Box<Type>, Box<Expression>),
+    // Variable-length arrays, as in `int a[n]` within a block, whose number of elements
+    // is only known at runtime. The resolver evaluates it once, where the array type
+    // appears, into the `unsigned long` local named here
+    VariableArray(Box<Type>, String),
+    // Structures and unions are referred to by their tag; their members
+    // and layout are kept in the symbol table, as they may be incomplete
+    Structure(StructKind, String),
+    Function(FunctionType),
+    // Qualified types only appear where an object is declared or pointed to: the
+    // qualifiers of an array apply to its elements, and values have no qualifiers
+    Qualified(Qualifiers, Box<Type>)
+}
+
+// Type qualifiers restrict how an object may be accessed: `const` objects may not be
+// modified, accesses to `volatile` objects are side effects that can't be removed or
+// merged, and `restrict` pointers are the only way to access the object they point to
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Qualifiers {
+    pub constant: bool,
+    pub volatile: bool,
+    pub restrict: bool
+}
+
+impl Qualifiers {
+    pub fn is_empty(&self) -> bool {
+        !self.constant && !self.volatile && !self.restrict
+    }
+
+    pub fn union(self, other: Qualifiers) -> Qualifiers {
+        Qualifiers {
+            constant: self.constant || other.constant,
+            volatile: self.volatile || other.volatile,
+            restrict: self.restrict || other.restrict
+        }
+    }
+
+    // Whether every qualifier of `other` is also in these
+    pub fn contains(&self, other: Qualifiers) -> bool {
+        self.union(other) == *self
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StructKind {
+    Struct,
+    Union
+}
+
+// Variadic functions take any number of arguments after their parameters, as in `int f(int n, ...)`
+#[derive(Debug, Clone, PartialEq)]
+pub struct FunctionType {
+    pub params: Vec<Type>,
+    pub ret: Box<Type>,
+    pub variadic: bool
+}
+
+impl FunctionType {
+    // Functions declared with `()`, as in `int f();`, or called without being declared have no
+    // prototype, and take any arguments. As `(...)` can't be written in C, they are marked as
+    // variadic functions without parameters
+    pub fn has_prototype(&self) -> bool {
+        return !self.variadic || !self.params.is_empty();
+    }
+
+    // Whether calls without a prototype, which only promote their arguments, can call
+    // a function of this type: it is not variadic, and its parameters are left alone
+    // by the default argument promotions (C11 6.7.6.3)
+    fn takes_promoted_arguments(&self) -> bool {
+        return !self.variadic && self.params.iter().all(|p| p.promote_argument() == *p);
+    }
+}
+
+// The `va_list` of the System V ABI is an array of one `struct __va_list_tag`,
+// which the resolver predefines, so it decays to a pointer when passed around
+pub const VA_LIST_TAG: &str = "__va_list_tag";
+
+impl Type {
+    // Adds qualifiers to a type; those of an array go to its elements
+    pub fn qualified(self, qualifiers: Qualifiers) -> Type {
+        if qualifiers.is_empty() {
+            return self;
+        }
+
+        match self {
+            Type::Array(element, count) => Type::Array(Box::new(element.qualified(qualifiers)), count),
+            Type::DeferredArray(element, size) => Type::DeferredArray(Box::new(element.qualified(qualifiers)), size),
+            Type::VariableArray(element, count) => Type::VariableArray(Box::new(element.qualified(qualifiers)), count),
+            Type::Qualified(existing, inner) => Type::Qualified(existing.union(qualifiers), inner),
+            ty => Type::Qualified(qualifiers, Box::new(ty))
+        }
+    }
+
+    // The type without its own qualifiers, e.g. the type of the value of a `const int`
+    pub fn unqualified(&self) -> &Type {
+        match self {
+            Type::Qualified(_, inner) => inner,
+            ty => ty
+        }
+    }
+
+    // Qualifiers of an object of this type, including those of the elements of an array
+    pub fn qualifiers(&self) -> Qualifiers {
+        match self {
+            Type::Qualified(qualifiers, _) => *qualifiers,
+            Type::Array(element, _) | Type::DeferredArray(element, _) | Type::VariableArray(element, _) => element.qualifiers(),
+            _ => Qualifiers::default()
+        }
+    }
+
+    // Pointers convert implicitly to pointers to the same type, and between `void *` and
+    // other pointers, but only if the type pointed to keeps its qualifiers, as from `int *`
+    // to `const int *`. Returns the qualifiers the conversion would discard, or None if the
+    // types pointed to are incompatible
+    pub fn pointer_conversion(from: &Type, to: &Type) -> Option<Qualifiers> {
+        let (from, to) = (from.pointee()?, to.pointee()?);
+        let compatible = Type::compatible(from, to) || *from.unqualified() == Type::Void || *to.unqualified() == Type::Void;
+        if !compatible {
+            return None;
+        }
+
+        let (from, to) = (from.qualifiers(), to.qualifiers());
+        return Some(Qualifiers {
+            constant: from.constant && !to.constant,
+            volatile: from.volatile && !to.volatile,
+            restrict: from.restrict && !to.restrict
+        });
+    }
+
+    // Whether two types are the same, ignoring their own qualifiers. The number of elements
+    // of a variable-length array is only known at runtime, so it is compatible with any
+    // array of a compatible element type, as `int (*)[n]` is with `int (*)[m]` or `int (*)[3]`,
+    // and so is an array declared without a size, as `int (*)[]` is
+    pub fn compatible(a: &Type, b: &Type) -> bool {
+        match (a.unqualified(), b.unqualified()) {
+            (Type::VariableArray(a, _), Type::VariableArray(b, _) | Type::Array(b, _)) |
+            (Type::Array(a, _), Type::VariableArray(b, _)) => a == b || Type::compatible(a, b) && a.qualifiers() == b.qualifiers(),
+            (Type::Array(a, m), Type::Array(b, n)) => (m == n || *m == 0 || *n == 0) && (a == b || Type::compatible(a, b) && a.qualifiers() == b.qualifiers()),
+            (Type::Pointer(a), Type::Pointer(b)) => a == b || Type::compatible(a, b) && a.qualifiers() == b.qualifiers(),
+            (Type::Function(f), Type::Function(g)) if f.ret == g.ret && f.has_prototype() != g.has_prototype() => {
+                f.takes_promoted_arguments() || g.takes_promoted_arguments()
+            },
+            (a, b) => a == b
+        }
+    }
+
+    // The type of an object or function declared twice with these types, if they are
+    // compatible (C11 6.2.7): an array declared without a size, as in `extern int a[];`,
+    // takes it from the other declaration, and so does a function without a prototype
+    // take the parameters of the other declaration
+    pub fn composite(a: &Type, b: &Type) -> Option<Type> {
+        match (a, b) {
+            (Type::Array(x, 0), Type::Array(y, n)) | (Type::Array(y, n), Type::Array(x, 0)) if x == y => Some(Type::Array(x.clone(), *n)),
+            (Type::Function(f), Type::Function(_)) if Type::compatible(a, b) => Some(if f.has_prototype() { a.clone() } else { b.clone() }),
+            _ => (a == b).then(|| a.clone())
+        }
+    }
+
+    // Whether the size of an object of this type is only known at runtime, because it
+    // is (or points to) a variable-length array, like `int (*)[n]`
+    pub fn is_variably_modified(&self) -> bool {
+        match self {
+            Type::VariableArray(_, _) => true,
+            Type::Pointer(inner) | Type::Array(inner, _) | Type::Qualified(_, inner) => inner.is_variably_modified(),
+            Type::Function(f) => f.ret.is_variably_modified() || f.params.iter().any(|p| p.is_variably_modified()),
+            _ => false
+        }
+    }
+
+    // Whether the size of an object of this type is only known at runtime, as for
+    // `int[n]` or `int[3][n]`, unlike `int (*)[n]`, which is merely variably modified
+    pub fn is_variable_length(&self) -> bool {
+        match self.unqualified() {
+            Type::VariableArray(_, _) => true,
+            Type::Array(element, _) => element.is_variable_length(),
+            _ => false
+        }
+    }
+
+    pub fn va_list() -> Type {
+        Type::Array(Box::new(Type::Structure(StructKind::Struct, VA_LIST_TAG.to_string())), 1)
+    }
+
+    // Pointer to a `va_list`, which is what a `va_list` decays to
+    pub fn is_va_list_pointer(&self) -> bool {
+        matches!(self.pointee(), Some(Type::Structure(StructKind::Struct, tag)) if tag == VA_LIST_TAG)
+    }
+
+    pub fn is_integer(&self) -> bool {
+        !matches!(self.unqualified(), Type::Void | Type::Float | Type::Double | Type::Pointer(_) | Type::Array(_, _) | Type::DeferredArray(_, _) | Type::VariableArray(_, _) | Type::Structure(_, _) | Type::Function(_))
+    }
+
+    pub fn is_floating(&self) -> bool {
+        matches!(self.unqualified(), Type::Float | Type::Double)
+    }
+
+    pub fn is_arithmetic(&self) -> bool {
+        self.is_integer() || self.is_floating()
+    }
+
+    // Types of objects that hold a single value
+    pub fn is_scalar(&self) -> bool {
+        self.is_arithmetic() || self.is_pointer()
+    }
+
+    pub fn is_pointer(&self) -> bool {
+        matches!(self.unqualified(), Type::Pointer(_))
+    }
+
+    // Type of the object a pointer points to
+    pub fn pointee(&self) -> Option<&Type> {
+        match self.unqualified() {
+            Type::Pointer(inner) => Some(inner),
+            _ => None
+        }
+    }
+
+    // Plain `char` is signed in the System V ABI
+    pub fn is_signed(&self) -> bool {
+        matches!(self.unqualified(), Type::Char | Type::SChar | Type::Short | Type::Int | Type::Long | Type::LongLong)
+    }
+
+    // Size of an object of this type, in bytes. The size of structures
+    // depends on their layout, see `SymbolTable::size_of`
+    pub fn size(&self) -> usize {
+        match self {
+            Type::Bool | Type::Char | Type::SChar | Type::UChar => 1,
+            Type::Short | Type::UShort => 2,
+            Type::Int | Type::UInt | Type::Float => 4,
+            Type::Long | Type::ULong | Type::LongLong | Type::ULongLong | Type::Double => 8,
+            Type::Pointer(_) => 8,
+            Type::Array(_, _) => self.checked_size().expect("Internal type error -- array is too large"),
+            Type::Qualified(_, inner) => inner.size(),
+            Type::Void | Type::DeferredArray(_, _) | Type::VariableArray(_, _) | Type::Structure(_, _) | Type::Function(_) => panic!("Internal type error -- `{}` has no size", self)
+        }
+    }
+
+    // Size of an object of this type, unless it is an array too large for its size to be counted
+    pub fn checked_size(&self) -> Option<usize> {
+        match self {
+            Type::Array(element, count) => element.checked_size()?.checked_mul(*count),
+            Type::Qualified(_, inner) => inner.checked_size(),
+            _ => Some(self.size())
+        }
+    }
+
+    pub fn alignment(&self) -> usize {
+        match self {
+            Type::Array(element, _) | Type::VariableArray(element, _) => element.alignment(),
+            Type::Qualified(_, inner) => inner.alignment(),
+            _ => self.size()
+        }
+    }
+
+    // The default argument promotions, which apply to variable arguments and the arguments
+    // of functions without a prototype: the integer promotions, and `float` to `double`
+    pub fn promote_argument(&self) -> Type {
+        match self {
+            Type::Float => Type::Double,
+            ty => ty.promote()
+        }
+    }
+
+    // Integer conversion rank (C11 6.3.1.1)
+    fn rank(&self) -> u8 {
+        match self {
+            Type::Bool => 1,
+            Type::Char | Type::SChar | Type::UChar => 2,
+            Type::Short | Type::UShort => 3,
+            Type::Int | Type::UInt => 4,
+            Type::Long | Type::ULong => 5,
+            Type::LongLong | Type::ULongLong => 6,
+            Type::Qualified(_, inner) => inner.rank(),
+            Type::Void | Type::Float | Type::Double | Type::Pointer(_) | Type::Array(_, _) | Type::DeferredArray(_, _) | Type::VariableArray(_, _) | Type::Structure(_, _) | Type::Function(_) => 0
+        }
+    }
+
+    fn to_unsigned(&self) -> Type {
+        match self.unqualified() {
+            Type::Char | Type::SChar => Type::UChar,
+            Type::Short => Type::UShort,
+            Type::Int => Type::UInt,
+            Type::Long => Type::ULong,
+            Type::LongLong => Type::ULongLong,
+            ty => ty.clone()
+        }
+    }
+
+    // Integer promotions: every type narrower than `int` is
+    // promoted to `int`, which can represent all of its values
+    pub fn promote(&self) -> Type {
+        if self.is_integer() && self.rank() < Type::Int.rank() {
+            return Type::Int;
+        }
+
+        return self.unqualified().clone();
+    }
+
+    // The usual arithmetic conversions (C11 6.3.1.8), which determine
+    // the type both operands of a binary operator are converted to
+    pub fn common(a: &Type, b: &Type) -> Type {
+        if *a == Type::Double || *b == Type::Double {
+            return Type::Double;
+        }
+        if *a == Type::Float || *b == Type::Float {
+            return Type::Float;
+        }
+
+        let a = a.promote();
+        let b = b.promote();
+
+        if a == b {
+            return a;
+        }
+        if a.is_signed() == b.is_signed() {
+            return if a.rank() >= b.rank() { a } else { b };
+        }
+
+        let (signed, unsigned) = if a.is_signed() { (a, b) } else { (b, a) };
+        if unsigned.rank() >= signed.rank() {
+            return unsigned;
+        }
+        if signed.size() > unsigned.size() {
+            return signed;
+        }
+
+        return signed.to_unsigned();
+    }
+
+    // Converts a value to this type, wrapping it modulo 2^N
+    // and reinterpreting the sign bit for signed types.
+    // `_Bool` instead compares the value with 0
+    pub fn wrap(&self, value: i128) -> i128 {
+        if *self.unqualified() == Type::Bool {
+            return (value != 0) as i128;
+        }
+
+        let bits = 8 * self.size() as u32;
+        let modulus: i128 = 1 << bits;
+
+        let value = value.rem_euclid(modulus);
+        if self.is_signed() && value >= modulus / 2 {
+            return value - modulus;
+        }
+
+        return value;
+    }
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.with_declarator(String::new()))
+    }
+}
+
+impl Type {
+    // Types are shown as in C, around the (abstract) declarator of what has the type:
+    // the type of `int (*p)[3]` is `int (*)[3]`, and that of `int *(*f)(int)` is `int *(*)(int)`
+    fn with_declarator(&self, declarator: String) -> String {
+        let name = match self {
+            Type::Void => "void",
+            Type::Bool => "_Bool",
+            Type::Char => "char",
+            Type::SChar => "signed char",
+            Type::UChar => "unsigned char",
+            Type::Short => "short",
+            Type::UShort => "unsigned short",
+            Type::Int => "int",
+            Type::UInt => "unsigned int",
+            Type::Long => "long",
+            Type::ULong => "unsigned long",
+            Type::LongLong => "long long",
+            Type::ULongLong => "unsigned long long",
+            Type::Float => "float",
+            Type::Double => "double",
+            Type::Pointer(inner) => return Self::pointer_declarator(inner, Qualifiers::default(), declarator),
+            Type::Array(element, 0) => return element.with_declarator(format!("{}[]", declarator)),
+            Type::Array(element, count) => return element.with_declarator(format!("{}[{}]", declarator, count)),
+            Type::DeferredArray(element, _) => return element.with_declarator(format!("{}[]", declarator)),
+            Type::VariableArray(element, _) => return element.with_declarator(format!("{}[*]", declarator)),
+            // Tags are renamed to be unique, so only print the name they had in the source
+            Type::Structure(kind, tag) => {
+                let keyword = match kind {
+                    StructKind::Struct => "struct",
+                    StructKind::Union => "union"
+                };
+                &format!("{} {}", keyword, super::table::source_name(tag))
+            },
+            // `int *const` is a constant pointer, while `const int *` points to constants
+            Type::Qualified(qualifiers, inner) => match inner.as_ref() {
+                Type::Pointer(pointee) => return Self::pointer_declarator(pointee, *qualifiers, declarator),
+                inner => return format!("{} {}", qualifiers, inner.with_declarator(declarator))
+            },
+            // Functions declared with `()` take any arguments, unlike those declared with `(void)`
+            Type::Function(t) => {
+                let mut params: Vec<String> = t.params.iter().map(|p| p.to_string()).collect();
+                match (params.is_empty(), t.variadic) {
+                    (true, false) => params.push("void".to_string()),
+                    (false, true) => params.push("...".to_string()),
+                    _ => {}
+                }
+                return t.ret.with_declarator(format!("{}({})", declarator, params.join(", ")));
+            }
+        };
+
+        match declarator.starts_with('[') || declarator.is_empty() {
+            true => format!("{}{}", name, declarator),
+            false => format!("{} {}", name, declarator)
+        }
+    }
+
+    // Pointers to arrays and functions are parenthesized, as in `int (*)(int)`
+    fn pointer_declarator(pointee: &Type, qualifiers: Qualifiers, declarator: String) -> String {
+        let mut pointer = format!("*{}", qualifiers);
+        if !qualifiers.is_empty() && !declarator.is_empty() {
+            pointer += " ";
+        }
+        pointer += declarator.as_str();
+
+        match pointee.unqualified() {
+            Type::Array(..) | Type::DeferredArray(..) | Type::VariableArray(..) | Type::Function(_) => pointee.with_declarator(format!("({})", pointer)),
+            _ => pointee.with_declarator(pointer)
+        }
+    }
+}
+
+impl fmt::Display for Qualifiers {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names = [(self.constant, "const"), (self.volatile, "volatile"), (self.restrict, "restrict")];
+        let names: Vec<&str> = names.iter().filter(|(present, _)| *present).map(|(_, name)| *name).collect();
+        write!(f, "{}", names.join(" "))
+    }
+}
@@ -0,0 +1,442 @@
+// Diagnostics: errors and warnings reported to the user //
+// Every problem found in a program is described by a `Diagnostic`, which points
+// at the source it is about with labels, and may suggest how to fix it. Diagnostics
+// are rendered as snippets of the source, with the labelled code underlined:
+//
+//     error[E0002]: Expected `;` after return value, found `2`
+//      --> main.c:3:12
+//       |
+//     3 |   return 1 2;
+//       |            ^ expected `;`
+//       |
+//     help: insert `;`
+//       |
+//     3 |   return 1; 2;
+//       |           +
+use std::fmt;
+use crate::lex::source::{SourceMap, Span};
+use warnings::{Warning, WarningOptions};
+
+pub mod json;
+pub mod warnings;
+
+// Codes of the errors common enough to be told apart (and searched for)
+// without depending on the wording of their message
+pub const INVALID_TOKEN: &str = "E0001";
+pub const UNEXPECTED_TOKEN: &str = "E0002";
+pub const UNEXPECTED_EOF: &str = "E0003";
+pub const INVALID_LITERAL: &str = "E0004";
+pub const LITERAL_OUT_OF_RANGE: &str = "E0005";
+pub const INVALID_SPECIFIERS: &str = "E0006";
+pub const INVALID_DECLARATOR: &str = "E0007";
+pub const INVALID_CONSTANT: &str = "E0008";
+pub const UNDECLARED_IDENTIFIER: &str = "E0101";
+pub const REDECLARATION: &str = "E0102";
+pub const CONFLICTING_TYPES: &str = "E0103";
+pub const STATIC_ASSERTION_FAILED: &str = "E0104";
+pub const INCOMPLETE_TYPE: &str = "E0105";
+pub const INVALID_STORAGE_CLASS: &str = "E0106";
+pub const INVALID_INLINE: &str = "E0107";
+pub const VARIABLY_MODIFIED_TYPE: &str = "E0108";
+pub const INVALID_ARRAY_SIZE: &str = "E0109";
+pub const INVALID_BIT_FIELD: &str = "E0110";
+pub const INVALID_INITIALIZER: &str = "E0111";
+pub const INVALID_OPERANDS: &str = "E0201";
+pub const INCOMPATIBLE_TYPES: &str = "E0202";
+pub const ARGUMENT_COUNT: &str = "E0203";
+pub const NOT_ASSIGNABLE: &str = "E0204";
+pub const INVALID_LAYOUT_OPERAND: &str = "E0205";
+pub const NOT_ADDRESSABLE: &str = "E0206";
+pub const INVALID_RETURN: &str = "E0207";
+pub const VOID_VALUE: &str = "E0208";
+pub const INVALID_CAST: &str = "E0209";
+pub const UNKNOWN_MEMBER: &str = "E0210";
+pub const NOT_VARIADIC: &str = "E0211";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Warning,
+    Error,
+    // An error after which compilation stops, e.g. once too many errors were reported
+    Fatal
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+            Severity::Fatal => "fatal error"
+        };
+        return write!(f, "{}", name);
+    }
+}
+
+// Source a diagnostic is about. The primary label says where the problem is, while
+// secondary labels point at related code, e.g. an earlier declaration
+#[derive(Debug, Clone, PartialEq)]
+pub struct Label {
+    pub span: Span,
+    pub message: String,
+    pub primary: bool
+}
+
+// A suggested fix, made of edits applied together, as when wrapping an expression in parentheses
+#[derive(Debug, Clone, PartialEq)]
+pub struct FixIt {
+    pub edits: Vec<Edit>,
+    pub message: String
+}
+
+// Replaces the source in `span`, which is empty for insertions
+#[derive(Debug, Clone, PartialEq)]
+pub struct Edit {
+    pub span: Span,
+    pub replacement: String
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub code: Option<&'static str>,
+    // The named warning this is, which decides how (and whether) it is reported
+    pub warning: Option<Warning>,
+    pub message: String,
+    pub labels: Vec<Label>,
+    pub notes: Vec<String>,
+    pub fixits: Vec<FixIt>
+}
+
+impl Diagnostic {
+    pub fn new(severity: Severity, message: impl Into<String>) -> Self {
+        return Self { severity, code: None, warning: None, message: message.into(), labels: vec![], notes: vec![], fixits: vec![] };
+    }
+
+    pub fn error(message: impl Into<String>) -> Self {
+        return Self::new(Severity::Error, message);
+    }
+
+    pub fn warning(message: impl Into<String>) -> Self {
+        return Self::new(Severity::Warning, message);
+    }
+
+    pub fn with_code(mut self, code: &'static str) -> Self {
+        self.code = Some(code);
+        return self;
+    }
+
+    // Named warnings use their option as their code, e.g. `warning[-Wshadow]`
+    pub fn with_warning(mut self, warning: Warning) -> Self {
+        self.code = Some(warning.flag());
+        self.warning = Some(warning);
+        return self;
+    }
+
+    pub fn with_label(mut self, span: Span, message: impl Into<String>) -> Self {
+        self.labels.push(Label { span, message: message.into(), primary: true });
+        return self;
+    }
+
+    pub fn with_secondary_label(mut self, span: Span, message: impl Into<String>) -> Self {
+        self.labels.push(Label { span, message: message.into(), primary: false });
+        return self;
+    }
+
+    pub fn with_note(mut self, note: impl Into<String>) -> Self {
+        self.notes.push(note.into());
+        return self;
+    }
+
+    pub fn with_fixit(self, span: Span, replacement: impl Into<String>, message: impl Into<String>) -> Self {
+        return self.with_fixit_edits(vec![Edit { span, replacement: replacement.into() }], message);
+    }
+
+    pub fn with_fixit_edits(mut self, edits: Vec<Edit>, message: impl Into<String>) -> Self {
+        self.fixits.push(FixIt { edits, message: message.into() });
+        return self;
+    }
+
+    pub fn primary_span(&self) -> Option<Span> {
+        return self.labels.iter().find(|label| label.primary).map(|label| label.span);
+    }
+}
+
+// How diagnostics are reported: as source snippets for users (`text`), or as
+// a JSON array (`json`) or SARIF log (`sarif`) for tools
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Text,
+    Json,
+    Sarif
+}
+
+impl Format {
+    pub const NAMES: [&'static str; 3] = ["text", "json", "sarif"];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "text" => Some(Format::Text),
+            "json" => Some(Format::Json),
+            "sarif" => Some(Format::Sarif),
+            _ => None
+        }
+    }
+}
+
+// Reports diagnostics on the standard error, counting them for the summary printed at the
+// end of compilation. Machine-readable formats are a single document, written at the end
+pub struct DiagnosticEngine {
+    format: Format,
+    color: bool,
+    options: WarningOptions,
+    errors: usize,
+    warnings: usize,
+    // A fatal error only tells why compilation stopped, so it fails the
+    // compilation without counting as one more error in the summary
+    fatal: bool,
+    // Diagnostics encoded in a machine-readable format, with the codes they use
+    encoded: Vec<String>,
+    codes: Vec<&'static str>
+}
+
+impl DiagnosticEngine {
+    pub fn new(format: Format, color: bool, options: WarningOptions) -> Self {
+        return Self { format, color, options, errors: 0, warnings: 0, fatal: false, encoded: vec![], codes: vec![] };
+    }
+
+    // Diagnostics found before the source is loaded, e.g. about command line options, have no snippet
+    pub fn emit(&mut self, diagnostic: &Diagnostic, source: Option<&SourceMap>) {
+        let promoted;
+        let diagnostic = match diagnostic.warning.map(|warning| self.options.severity(warning)) {
+            Some(None) => return,
+            Some(Some(severity)) if severity != diagnostic.severity => {
+                promoted = Diagnostic { severity, ..diagnostic.clone() };
+                &promoted
+            },
+            _ => diagnostic
+        };
+
+        match diagnostic.severity {
+            Severity::Error => self.errors += 1,
+            Severity::Fatal => self.fatal = true,
+            Severity::Warning => self.warnings += 1
+        }
+        if let Some(code) = diagnostic.code {
+            if !self.codes.contains(&code) {
+                self.codes.push(code);
+            }
+        }
+
+        match self.format {
+            Format::Text => eprint!("{}", render(diagnostic, source, self.color)),
+            Format::Json => self.encoded.push(json::encode(diagnostic, source)),
+            Format::Sarif => self.encoded.push(json::encode_sarif(diagnostic, source))
+        }
+    }
+
+    pub fn has_errors(&self) -> bool {
+        return self.errors > 0 || self.fatal;
+    }
+
+    // Ends the report of the compilation, with the summary of its diagnostics or their document
+    pub fn finish(&self) {
+        match self.format {
+            Format::Text => {
+                if let Some(summary) = self.summary() {
+                    eprintln!("{}", summary);
+                }
+            },
+            Format::Json => eprintln!("{}", json::json_log(&self.encoded)),
+            Format::Sarif => eprintln!("{}", json::sarif_log(&self.encoded, &self.codes))
+        }
+    }
+
+    // As clang does, e.g. "1 warning and 2 errors generated."
+    fn summary(&self) -> Option<String> {
+        let count = |n: usize, what: &str| format!("{} {}{}", n, what, if n == 1 { "" } else { "s" });
+        let summary = match (self.warnings, self.errors) {
+            (0, 0) => return None,
+            (warnings, 0) => count(warnings, "warning"),
+            (0, errors) => count(errors, "error"),
+            (warnings, errors) => format!("{} and {}", count(warnings, "warning"), count(errors, "error"))
+        };
+        return Some(format!("{} generated.", summary));
+    }
+}
+
+// ANSI colors, as used by rustc
+const RED: &str = "1;31";
+const YELLOW: &str = "1;33";
+const GREEN: &str = "1;32";
+const BLUE: &str = "1;34";
+const CYAN: &str = "1;36";
+const BOLD: &str = "1";
+
+// Tabs are rendered as this many spaces, so that labels line up with the source
+const TAB_WIDTH: usize = 4;
+
+struct Renderer<'a> {
+    source: &'a SourceMap,
+    color: bool,
+    // Width of the line numbers in the gutter
+    gutter: usize
+}
+
+pub fn render(diagnostic: &Diagnostic, source: Option<&SourceMap>, color: bool) -> String {
+    let paint = |text: &str, style: &str| paint(text, style, color);
+    let severity_color = match diagnostic.severity {
+        Severity::Warning => YELLOW,
+        Severity::Error | Severity::Fatal => RED
+    };
+
+    let mut result = match diagnostic.code {
+        Some(code) => paint(&format!("{}[{}]", diagnostic.severity, code), severity_color),
+        None => paint(&diagnostic.severity.to_string(), severity_color)
+    };
+    result += &paint(&format!(": {}", diagnostic.message), BOLD);
+    result += "\n";
+
+    let Some(source) = source else {
+        for note in &diagnostic.notes {
+            result += &format!("{}: {}\n", paint("note", GREEN), note);
+        }
+        for fixit in &diagnostic.fixits {
+            result += &format!("{}: {}\n", paint("help", CYAN), fixit.message);
+        }
+        return result;
+    };
+
+    let spans = diagnostic.labels.iter().map(|label| label.span).chain(diagnostic.fixits.iter().flat_map(|fixit| fixit.edits.iter().map(|edit| edit.span)));
+    let gutter = spans.map(|span| source.locate(span.start).line.to_string().len()).max().unwrap_or(0);
+    let renderer = Renderer { source, color, gutter };
+
+    result += &renderer.render_labels(&diagnostic.labels, severity_color);
+    let has_help = !diagnostic.notes.is_empty() || !diagnostic.fixits.is_empty();
+    if !diagnostic.labels.is_empty() && has_help {
+        result += &renderer.empty_gutter();
+    }
+    for note in &diagnostic.notes {
+        result += &format!("{} {} {}: {}\n", " ".repeat(gutter), paint("=", BLUE), paint("note", BOLD), note);
+    }
+    for fixit in &diagnostic.fixits {
+        result += &renderer.render_fixit(fixit);
+    }
+
+    return result;
+}
+
+fn paint(text: &str, style: &str, color: bool) -> String {
+    if color {
+        return format!("\x1b[{}m{}\x1b[0m", style, text);
+    }
+    return text.to_string();
+}
+
+// Width of the text once its tabs are expanded
+fn display_width(text: &str) -> usize {
+    return text.chars().map(|c| if c == '\t' { TAB_WIDTH } else { 1 }).sum();
+}
+
+impl Renderer<'_> {
+    fn paint(&self, text: &str, style: &str) -> String {
+        return paint(text, style, self.color);
+    }
+
+    fn empty_gutter(&self) -> String {
+        return format!("{} {}\n", " ".repeat(self.gutter), self.paint("|", BLUE));
+    }
+
+    fn source_line(&self, number: usize, text: &str) -> String {
+        let number = format!("{:>width$} |", number, width = self.gutter);
+        return format!("{} {}\n", self.paint(&number, BLUE), text.replace('\t', &" ".repeat(TAB_WIDTH)));
+    }
+
+    // Labels are grouped by file, starting with the file of the primary label, and every
+    // labelled line is shown once, followed by the labels on it in the order of their columns
+    fn render_labels(&self, labels: &[Label], severity_color: &str) -> String {
+        let mut labels: Vec<&Label> = labels.iter().collect();
+        labels.sort_by_key(|label| (!label.primary, label.span.start));
+        let mut files: Vec<&str> = vec![];
+        for label in &labels {
+            let file = self.source.locate(label.span.start).file;
+            if !files.contains(&file) {
+                files.push(file);
+            }
+        }
+
+        let mut result = String::new();
+        for (index, file) in files.iter().enumerate() {
+            let mut in_file: Vec<&Label> = labels.iter().copied().filter(|label| self.source.locate(label.span.start).file == *file).collect();
+            let location = self.source.locate(in_file[0].span.start);
+            let arrow = if index == 0 { "-->" } else { ":::" };
+            result += &format!("{}{} {}:{}:{}\n", " ".repeat(self.gutter), self.paint(arrow, BLUE), location.file, location.line, location.column);
+            result += &self.empty_gutter();
+
+            in_file.sort_by_key(|label| label.span.start);
+            let mut previous_line: Option<usize> = None;
+            for (line_start, line) in self.labelled_lines(&in_file) {
+                let number = self.source.locate(line_start).line;
+                if previous_line.is_some_and(|previous| number > previous + 1) {
+                    result += &format!("{}\n", self.paint("...", BLUE));
+                }
+                previous_line = Some(number);
+                result += &self.source_line(number, line);
+
+                for label in in_file.iter().filter(|label| self.source.line(label.span.start).0 == line_start) {
+                    let (marker, style) = if label.primary { ("^", severity_color) } else { ("-", BLUE) };
+                    let column = label.span.start - line_start;
+                    // Labels spanning several lines are underlined up to the end of their first one
+                    let end = label.span.end.min(line_start + line.len()).max(label.span.start);
+                    let indent = display_width(&line[..column]);
+                    let width = display_width(&line[column..end - line_start]).max(1);
+                    let underline = self.paint(format!("{} {}", marker.repeat(width), label.message).trim_end(), style);
+                    result += &format!("{} {} {}{}\n", " ".repeat(self.gutter), self.paint("|", BLUE), " ".repeat(indent), underline);
+                }
+            }
+        }
+
+        return result;
+    }
+
+    // The distinct lines the labels start on, in order, as their offset and text
+    fn labelled_lines<'b>(&'b self, labels: &[&Label]) -> Vec<(usize, &'b str)> {
+        let mut lines: Vec<(usize, &str)> = labels.iter().map(|label| self.source.line(label.span.start)).collect();
+        lines.dedup_by_key(|(start, _)| *start);
+        return lines;
+    }
+
+    // Shows the lines of the fix-it with its edits applied, marking inserted text
+    // with `+` and replacements with `~`
+    fn render_fixit(&self, fixit: &FixIt) -> String {
+        let mut result = format!("{}: {}\n", self.paint("help", CYAN), fixit.message);
+        result += &self.empty_gutter();
+
+        let mut edits: Vec<&Edit> = fixit.edits.iter().collect();
+        edits.sort_by_key(|edit| edit.span.start);
+        let mut lines: Vec<(usize, &str)> = edits.iter().map(|edit| self.source.line(edit.span.start)).collect();
+        lines.dedup_by_key(|(start, _)| *start);
+
+        for (line_start, line) in lines {
+            let mut edited = String::new();
+            let mut underline = String::new();
+            let mut cursor = 0;
+            for edit in edits.iter().filter(|edit| self.source.line(edit.span.start).0 == line_start) {
+                let start = edit.span.start - line_start;
+                let end = (edit.span.end - line_start).min(line.len()).max(start);
+                edited += &line[cursor..start];
+                underline += &" ".repeat(display_width(&edited).saturating_sub(display_width(&underline)));
+
+                let marker = if edit.span.start == edit.span.end { "+" } else { "~" };
+                edited += &edit.replacement;
+                underline += &marker.repeat(display_width(&edit.replacement).max(1));
+                cursor = end;
+            }
+            edited += &line[cursor..];
+
+            result += &self.source_line(self.source.locate(line_start).line, &edited);
+            let indent = underline.len() - underline.trim_start().len();
+            result += &format!("{} {} {}{}\n", " ".repeat(self.gutter), self.paint("|", BLUE), &underline[..indent], self.paint(&underline[indent..], GREEN));
+        }
+        return result;
+    }
+}
@@ -0,0 +1,146 @@
+// Machine-readable diagnostics, for tools consuming the compiler's output: a JSON
+// array of diagnostics, or a SARIF 2.1.0 log (https://sarifweb.azurewebsites.net),
+// both encoded from the same diagnostics as the snippets shown to users
+use crate::lex::source::{SourceMap, Span};
+use super::{Diagnostic, Severity};
+
+pub fn string(text: &str) -> String {
+    let mut result = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => result += "\\\"",
+            '\\' => result += "\\\\",
+            '\n' => result += "\\n",
+            '\r' => result += "\\r",
+            '\t' => result += "\\t",
+            c if (c as u32) < 0x20 => result += &format!("\\u{:04x}", c as u32),
+            c => result.push(c)
+        }
+    }
+    result.push('"');
+    return result;
+}
+
+fn array(items: impl IntoIterator<Item = String>) -> String {
+    return format!("[{}]", items.into_iter().collect::<Vec<_>>().join(","));
+}
+
+fn severity(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Warning => "warning",
+        Severity::Error => "error",
+        Severity::Fatal => "fatal"
+    }
+}
+
+// Spans are given by the line and column (starting at 1) of their start and of their
+// (exclusive) end, as offsets are those of the preprocessed source, not of the file
+fn location(span: Span, source: &SourceMap) -> String {
+    let start = source.locate(span.start);
+    let end = source.locate(span.end);
+    return format!(
+        "{{\"file\":{},\"line\":{},\"column\":{},\"span\":{{\"start\":{{\"line\":{},\"column\":{}}},\"end\":{{\"line\":{},\"column\":{}}}}}}}",
+        string(start.file), start.line, start.column, start.line, start.column, end.line, end.column
+    );
+}
+
+// A diagnostic as a JSON object, whose `location` is that of its primary label (if any)
+pub fn encode(diagnostic: &Diagnostic, source: Option<&SourceMap>) -> String {
+    let locate = |span: Span| source.map_or("null".to_string(), |source| location(span, source));
+
+    let labels = diagnostic.labels.iter().map(|label| format!(
+        "{{\"location\":{},\"message\":{},\"primary\":{}}}",
+        locate(label.span), string(&label.message), label.primary
+    ));
+    let fixits = diagnostic.fixits.iter().map(|fixit| format!(
+        "{{\"edits\":{},\"message\":{}}}",
+        array(fixit.edits.iter().map(|edit| format!("{{\"location\":{},\"replacement\":{}}}", locate(edit.span), string(&edit.replacement)))),
+        string(&fixit.message)
+    ));
+
+    return format!(
+        "{{\"severity\":\"{}\",\"code\":{},\"message\":{},\"location\":{},\"labels\":{},\"notes\":{},\"fixits\":{}}}",
+        severity(diagnostic.severity),
+        diagnostic.code.map_or("null".to_string(), string),
+        string(&diagnostic.message),
+        diagnostic.primary_span().map_or("null".to_string(), locate),
+        array(labels),
+        array(diagnostic.notes.iter().map(|note| string(note))),
+        array(fixits)
+    );
+}
+
+fn region(span: Span, source: &SourceMap) -> String {
+    let start = source.locate(span.start);
+    let end = source.locate(span.end);
+    return format!(
+        "{{\"startLine\":{},\"startColumn\":{},\"endLine\":{},\"endColumn\":{}}}",
+        start.line, start.column, end.line, end.column
+    );
+}
+
+fn physical_location(span: Span, source: &SourceMap) -> String {
+    let file = source.locate(span.start).file;
+    return format!("{{\"artifactLocation\":{{\"uri\":{}}},\"region\":{}}}", string(file), region(span, source));
+}
+
+// A diagnostic as a SARIF result: its primary label is its location, other labels are
+// related locations, fix-its are fixes, and notes (which SARIF has no place for) are
+// kept in its property bag
+pub fn encode_sarif(diagnostic: &Diagnostic, source: Option<&SourceMap>) -> String {
+    let mut fields = vec![];
+    if let Some(code) = diagnostic.code {
+        fields.push(format!("\"ruleId\":{}", string(code)));
+    }
+    let level = match diagnostic.severity {
+        Severity::Warning => "warning",
+        Severity::Error | Severity::Fatal => "error"
+    };
+    fields.push(format!("\"level\":\"{}\"", level));
+    fields.push(format!("\"message\":{{\"text\":{}}}", string(&diagnostic.message)));
+
+    if let Some(source) = source {
+        let message = |text: &str| match text.is_empty() {
+            true => String::new(),
+            false => format!(",\"message\":{{\"text\":{}}}", string(text))
+        };
+        let (primary, related): (Vec<_>, Vec<_>) = diagnostic.labels.iter().partition(|label| label.primary);
+        fields.push(format!("\"locations\":{}", array(primary.iter().map(|label| format!(
+            "{{\"physicalLocation\":{}{}}}", physical_location(label.span, source), message(&label.message)
+        )))));
+        if !related.is_empty() {
+            fields.push(format!("\"relatedLocations\":{}", array(related.iter().enumerate().map(|(id, label)| format!(
+                "{{\"id\":{},\"physicalLocation\":{}{}}}", id, physical_location(label.span, source), message(&label.message)
+            )))));
+        }
+        if !diagnostic.fixits.is_empty() {
+            // The edits of a fix are all in the file of the first one
+            fields.push(format!("\"fixes\":{}", array(diagnostic.fixits.iter().map(|fixit| format!(
+                "{{\"description\":{{\"text\":{}}},\"artifactChanges\":[{{\"artifactLocation\":{{\"uri\":{}}},\"replacements\":{}}}]}}",
+                string(&fixit.message),
+                string(fixit.edits.first().map_or("", |edit| source.locate(edit.span.start).file)),
+                array(fixit.edits.iter().map(|edit| format!(
+                    "{{\"deletedRegion\":{},\"insertedContent\":{{\"text\":{}}}}}", region(edit.span, source), string(&edit.replacement)
+                )))
+            )))));
+        }
+    }
+    if !diagnostic.notes.is_empty() {
+        fields.push(format!("\"properties\":{{\"notes\":{}}}", array(diagnostic.notes.iter().map(|note| string(note)))));
+    }
+
+    return format!("{{{}}}", fields.join(","));
+}
+
+// The SARIF log of a compilation, given its results and the codes they use
+pub fn sarif_log(results: &[String], codes: &[&str]) -> String {
+    let rules = array(codes.iter().map(|code| format!("{{\"id\":{}}}", string(code))));
+    return format!(
+        "{{\"$schema\":\"https://json.schemastore.org/sarif-2.1.0.json\",\"version\":\"2.1.0\",\"runs\":[{{\"tool\":{{\"driver\":{{\"name\":\"zcc\",\"version\":{},\"rules\":{}}}}},\"results\":{}}}]}}",
+        string(env!("CARGO_PKG_VERSION")), rules, array(results.iter().cloned())
+    );
+}
+
+pub fn json_log(results: &[String]) -> String {
+    return array(results.iter().cloned());
+}
@@ -0,0 +1,131 @@
+// Named warnings, which can be enabled and disabled one by one (`-Wshadow`,
+// `-Wno-shadow`) or by group (`-Wall`, `-Wextra`), and made errors (`-Werror`,
+// `-Werror=shadow`), as with GCC and Clang. Every warning is looked for, and
+// these options decide, as they are reported, whether and how they are shown
+use std::collections::HashMap;
+use super::Severity;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Warning {
+    UnusedVariable,
+    UnusedParameter,
+    ReturnType,
+    ImplicitFunctionDeclaration,
+    Shadow,
+    SignCompare,
+    ConstantConversion,
+    Conversion,
+    IntToPointerCast,
+    PointerToIntCast,
+    Varargs
+}
+
+struct Entry {
+    warning: Warning,
+    // The option naming the warning, e.g. `-Wshadow`
+    flag: &'static str,
+    enabled: bool,
+    error: bool,
+    groups: &'static [&'static str]
+}
+
+const GROUPS: [&str; 2] = ["all", "extra"];
+
+// Warnings enabled by default are the ones which are almost always bugs, and
+// calling an undeclared function is an error since C99, as in Clang
+const WARNINGS: [Entry; 11] = [
+    Entry { warning: Warning::UnusedVariable, flag: "-Wunused-variable", enabled: false, error: false, groups: &["all"] },
+    Entry { warning: Warning::UnusedParameter, flag: "-Wunused-parameter", enabled: false, error: false, groups: &["extra"] },
+    Entry { warning: Warning::ReturnType, flag: "-Wreturn-type", enabled: true, error: false, groups: &["all"] },
+    Entry { warning: Warning::ImplicitFunctionDeclaration, flag: "-Wimplicit-function-declaration", enabled: true, error: true, groups: &["all"] },
+    Entry { warning: Warning::Shadow, flag: "-Wshadow", enabled: false, error: false, groups: &[] },
+    Entry { warning: Warning::SignCompare, flag: "-Wsign-compare", enabled: false, error: false, groups: &["extra"] },
+    Entry { warning: Warning::ConstantConversion, flag: "-Wconstant-conversion", enabled: true, error: false, groups: &["all"] },
+    Entry { warning: Warning::Conversion, flag: "-Wconversion", enabled: true, error: false, groups: &[] },
+    Entry { warning: Warning::IntToPointerCast, flag: "-Wint-to-pointer-cast", enabled: true, error: false, groups: &["all"] },
+    Entry { warning: Warning::PointerToIntCast, flag: "-Wpointer-to-int-cast", enabled: true, error: false, groups: &["all"] },
+    Entry { warning: Warning::Varargs, flag: "-Wvarargs", enabled: true, error: false, groups: &["all"] }
+];
+
+impl Warning {
+    fn entry(self) -> &'static Entry {
+        return WARNINGS.iter().find(|entry| entry.warning == self).expect("Every warning has an entry");
+    }
+
+    pub fn flag(self) -> &'static str {
+        return self.entry().flag;
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        return WARNINGS.iter().find(|entry| &entry.flag[2..] == name).map(|entry| entry.warning);
+    }
+}
+
+// The `-W` and `-w` options given. Options about a single warning take
+// precedence over those about its groups, whatever their order, so that
+// `-Wno-unused-parameter -Wextra` enables every warning of `-Wextra` but one
+#[derive(Debug, Clone, Default)]
+pub struct WarningOptions {
+    enabled: HashMap<Warning, bool>,
+    groups: HashMap<&'static str, bool>,
+    errors: HashMap<Warning, bool>,
+    all_errors: bool,
+    // `-w`, which drops every warning that isn't an error
+    suppressed: bool
+}
+
+impl WarningOptions {
+    // Applies a `-W` option, given without its `-W`, returning whether it is one
+    pub fn apply(&mut self, option: &str) -> bool {
+        let (name, enable) = match option.strip_prefix("no-") {
+            Some(name) => (name, false),
+            None => (option, true)
+        };
+
+        if name == "error" {
+            self.all_errors = enable;
+            return true;
+        }
+        if let Some(name) = name.strip_prefix("error=") {
+            let Some(warning) = Warning::from_name(name) else {
+                return false;
+            };
+            // `-Werror=name` also enables the warning, while `-Wno-error=name` leaves it as is
+            if enable {
+                self.enabled.insert(warning, true);
+            }
+            self.errors.insert(warning, enable);
+            return true;
+        }
+        if let Some(group) = GROUPS.iter().find(|group| **group == name) {
+            self.groups.insert(group, enable);
+            return true;
+        }
+        if let Some(warning) = Warning::from_name(name) {
+            self.enabled.insert(warning, enable);
+            return true;
+        }
+        return false;
+    }
+
+    pub fn suppress(&mut self) {
+        self.suppressed = true;
+    }
+
+    // How a warning is reported, if it is at all
+    pub fn severity(&self, warning: Warning) -> Option<Severity> {
+        let entry = warning.entry();
+        let group = entry.groups.iter().find_map(|group| self.groups.get(group));
+        let enabled = self.enabled.get(&warning).or(group).copied().unwrap_or(entry.enabled);
+        if !enabled {
+            return None;
+        }
+
+        let error = self.errors.get(&warning).copied().unwrap_or(self.all_errors || entry.error);
+        return match (error, self.suppressed) {
+            (true, _) => Some(Severity::Error),
+            (false, true) => None,
+            (false, false) => Some(Severity::Warning)
+        };
+    }
+}
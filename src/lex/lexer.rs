@@ -12,24 +12,130 @@ pub enum Tag {
     RParen,
     LBrace,
     RBrace,
+    LBracket,
+    RBracket,
 
     Semicolon,
+    Colon,
+    Comma,
+    Ellipsis,
     NumberLiteral,
+    StringLiteral,
 
     // Keywords:
     KInt,
     KVoid,
     KReturn,
+    KStatic,
+    KExtern,
+    KChar,
+    KShort,
+    KLong,
+    KSigned,
+    KUnsigned,
+    KStruct,
+    KUnion,
+    KEnum,
+    KTypedef,
+    KFloat,
+    KDouble,
+    KSizeof,
+    KAlignof,
+    KBool,
+    KStaticAssert,
+    // Function specifier
+    KInline,
+    // Type qualifiers
+    KConst,
+    KVolatile,
+    KRestrict,
+    // Builtins of GCC's <stdarg.h>
+    KBuiltinVaList,
+    KBuiltinVaStart,
+    KBuiltinVaArg,
+    KBuiltinVaEnd,
+    KBuiltinVaCopy,
 
     // Operations
     OpNegation,
-    OpComplement
+    OpComplement,
+    OpAssign,
+    OpAddition,
+    OpMultiplication,
+    OpDivision,
+    OpRemainder,
+    OpBitAnd,
+    OpBitOr,
+    OpBitXor,
+    OpShiftLeft,
+    OpShiftRight,
+    OpEqual,
+    OpNotEqual,
+    OpLess,
+    OpLessEqual,
+    OpGreater,
+    OpGreaterEqual,
+    // Member access
+    OpDot,
+    OpArrow
+}
+
+impl Tag {
+    // How punctuators and operators are spelled, for diagnostics
+    pub fn spelling(&self) -> Option<&'static str> {
+        let spelling = match self {
+            Tag::LParen => "(",
+            Tag::RParen => ")",
+            Tag::LBrace => "{",
+            Tag::RBrace => "}",
+            Tag::LBracket => "[",
+            Tag::RBracket => "]",
+            Tag::Semicolon => ";",
+            Tag::Colon => ":",
+            Tag::Comma => ",",
+            Tag::Ellipsis => "...",
+            Tag::OpAssign => "=",
+            _ => return None
+        };
+        return Some(spelling);
+    }
 }
 
 static TOKEN_KEYWORDS: phf::Map<&'static str, Tag> = phf_map! {
     "int" => Tag::KInt,
     "void" => Tag::KVoid,
     "return" => Tag::KReturn,
+    "static" => Tag::KStatic,
+    "extern" => Tag::KExtern,
+    "char" => Tag::KChar,
+    "short" => Tag::KShort,
+    "long" => Tag::KLong,
+    "signed" => Tag::KSigned,
+    "unsigned" => Tag::KUnsigned,
+    "struct" => Tag::KStruct,
+    "union" => Tag::KUnion,
+    "enum" => Tag::KEnum,
+    "typedef" => Tag::KTypedef,
+    "float" => Tag::KFloat,
+    "double" => Tag::KDouble,
+    "sizeof" => Tag::KSizeof,
+    "_Alignof" => Tag::KAlignof,
+    "_Bool" => Tag::KBool,
+    "_Static_assert" => Tag::KStaticAssert,
+    "const" => Tag::KConst,
+    "volatile" => Tag::KVolatile,
+    "restrict" => Tag::KRestrict,
+    "inline" => Tag::KInline,
+    // Spellings used by the C library's headers, which are also valid before C99
+    "__restrict" => Tag::KRestrict,
+    "__restrict__" => Tag::KRestrict,
+    "__inline" => Tag::KInline,
+    "__inline__" => Tag::KInline,
+    "__builtin_va_list" => Tag::KBuiltinVaList,
+    "__builtin_va_start" => Tag::KBuiltinVaStart,
+    "__builtin_va_arg" => Tag::KBuiltinVaArg,
+    "__builtin_va_end" => Tag::KBuiltinVaEnd,
+    "__builtin_va_copy" => Tag::KBuiltinVaCopy,
 };
 impl Tag {
     fn get_keyword(key: &str) -> Option<Tag> {
@@ -51,7 +157,8 @@ pub struct Lexer {
 enum TokenizerState {
     Start,
     Identifier,
-    Int
+    Int,
+    String
 }
 
 impl Lexer {
@@ -71,6 +178,16 @@ impl Lexer {
         }
     }
 
+    // Looks ahead of the current character without consuming anything
+    fn peek_char(&self, offset: usize) -> Option<char> {
+        self.buffer.chars().nth(self.index + offset)
+    }
+
+    fn is_hex_literal(&self, start: usize) -> bool {
+        let prefix = self.buffer.as_bytes().get(start..start + 2);
+        matches!(prefix, Some(b"0x" | b"0X"))
+    }
+
     pub fn next(&mut self) -> Token {
         use TokenizerState as S;
         use Tag as T;
@@ -96,6 +213,14 @@ impl Lexer {
                         result.range.start += 1;
                     },
 
+                    // skip the lines the preprocessor leaves for the compiler, such as
+                    // linemarkers (which the source map reads) and `#pragma` directives
+                    '#' if self.index == 0 || self.buffer.as_bytes()[self.index - 1] == b'\n' => {
+                        let end = self.buffer[self.index..].find('\n').map_or(max_length - 1, |i| self.index + i);
+                        result.range.start += end - self.index + 1;
+                        self.index = end;
+                    },
+
                     // match an identifier and enter that state
                     'a'..='z' | 'A'..='Z' | '_' => {
                         state = S::Identifier;
@@ -103,7 +228,7 @@ impl Lexer {
                     },
 
 
-                    // match a parenthesis/brace and return that token
+                    // match a parenthesis/brace/bracket and return that token
                     '(' => {
                         result.tag = T::LParen;
                         self.index += 1;
@@ -124,6 +249,16 @@ impl Lexer {
                         self.index += 1;
                         break;
                     },
+                    '[' => {
+                        result.tag = T::LBracket;
+                        self.index += 1;
+                        break;
+                    },
+                    ']' => {
+                        result.tag = T::RBracket;
+                        self.index += 1;
+                        break;
+                    },
 
                     // match semicolon and return that token directly
                     ';' => {
@@ -132,14 +267,42 @@ impl Lexer {
                         break;
                     },
 
+                    ',' => {
+                        result.tag = T::Comma;
+                        self.index += 1;
+                        break;
+                    },
+
+                    ':' => {
+                        result.tag = T::Colon;
+                        self.index += 1;
+                        break;
+                    },
+
+                    // enter string matching mode, until the closing quote
+                    '"' => {
+                        state = S::String;
+                        result.tag = T::StringLiteral;
+                    },
+
                     // enter integer matching mode
                     '0'..='9' => {
                         state = S::Int;
                         result.tag = T::NumberLiteral;
                     },
 
-                    '-' => {
-                        result.tag = T::OpNegation;
+                    // a floating literal may start with its decimal point, e.g. `.5`
+                    '.' if self.peek_char(1).is_some_and(|c| c.is_ascii_digit()) => {
+                        state = S::Int;
+                        result.tag = T::NumberLiteral;
+                    },
+                    '.' if self.peek_char(1) == Some('.') && self.peek_char(2) == Some('.') => {
+                        result.tag = T::Ellipsis;
+                        self.index += 3;
+                        break;
+                    },
+                    '.' => {
+                        result.tag = T::OpDot;
                         self.index += 1;
                         break;
                     },
@@ -150,6 +313,87 @@ impl Lexer {
                         break;
                     },
 
+                    '=' => {
+                        result.tag = match self.peek_char(1) {
+                            Some('=') => { self.index += 1; T::OpEqual },
+                            _ => T::OpAssign
+                        };
+                        self.index += 1;
+                        break;
+                    },
+
+                    '+' => {
+                        result.tag = T::OpAddition;
+                        self.index += 1;
+                        break;
+                    },
+                    '*' => {
+                        result.tag = T::OpMultiplication;
+                        self.index += 1;
+                        break;
+                    },
+                    '/' => {
+                        result.tag = T::OpDivision;
+                        self.index += 1;
+                        break;
+                    },
+                    '%' => {
+                        result.tag = T::OpRemainder;
+                        self.index += 1;
+                        break;
+                    },
+                    '&' => {
+                        result.tag = T::OpBitAnd;
+                        self.index += 1;
+                        break;
+                    },
+                    '|' => {
+                        result.tag = T::OpBitOr;
+                        self.index += 1;
+                        break;
+                    },
+                    '^' => {
+                        result.tag = T::OpBitXor;
+                        self.index += 1;
+                        break;
+                    },
+
+                    // operators which may be followed by a second character
+                    '-' => {
+                        result.tag = match self.peek_char(1) {
+                            Some('>') => { self.index += 1; T::OpArrow },
+                            _ => T::OpNegation
+                        };
+                        self.index += 1;
+                        break;
+                    },
+                    '!' => {
+                        result.tag = match self.peek_char(1) {
+                            Some('=') => { self.index += 1; T::OpNotEqual },
+                            _ => T::Invalid
+                        };
+                        self.index += 1;
+                        break;
+                    },
+                    '<' => {
+                        result.tag = match self.peek_char(1) {
+                            Some('<') => { self.index += 1; T::OpShiftLeft },
+                            Some('=') => { self.index += 1; T::OpLessEqual },
+                            _ => T::OpLess
+                        };
+                        self.index += 1;
+                        break;
+                    },
+                    '>' => {
+                        result.tag = match self.peek_char(1) {
+                            Some('>') => { self.index += 1; T::OpShiftRight },
+                            Some('=') => { self.index += 1; T::OpGreaterEqual },
+                            _ => T::OpGreater
+                        };
+                        self.index += 1;
+                        break;
+                    },
+
                     // we encountered an invalid token -- return it directly
                     _ => {
                         result.tag = T::Invalid;
@@ -174,16 +418,43 @@ impl Lexer {
                 },
 
                 S::Int => match c {
-                    // TODO: add hexadecimal support here
-                    // TODO: add support for floats
-                    '0'..='9' => {}
+                    '0'..='9' => {},
+                    // hexadecimal literals start with `0x`, and have digits up to `f`
+                    'x' | 'X' if self.index == result.range.start + 1 && self.buffer.as_bytes()[result.range.start] == b'0' => {},
+                    'a'..='f' | 'A'..='F' if self.is_hex_literal(result.range.start) => {},
+                    // fractions, exponents and their sign, of floating literals
+                    '.' | 'e' | 'E' => {},
+                    '+' | '-' if matches!(self.buffer.as_bytes()[self.index - 1], b'e' | b'E') => {},
+                    // integer and floating suffixes (validated by the parser)
+                    'u' | 'U' | 'l' | 'L' | 'f' | 'F' => {},
                     _ => break
+                },
+
+                // escape sequences are decoded by the parser, so only
+                // skip the character after a backslash here
+                S::String => match c {
+                    '"' => {
+                        self.index += 1;
+                        break;
+                    },
+                    '\\' if self.index + 1 < max_length => self.index += 1,
+                    // strings can't span multiple lines
+                    '\n' => {
+                        result.tag = T::Invalid;
+                        break;
+                    },
+                    _ => {}
                 }
             }
 
             self.index += 1;
         }
 
+        // a string that reached the end of the file was never closed
+        if matches!(state, S::String) && self.index > max_length {
+            result.tag = T::Invalid;
+        }
+
         if matches!(result.tag, T::Eof) {
             result.range.end = result.range.start;
         } else {
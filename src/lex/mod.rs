@@ -1 +1,2 @@
 pub mod lexer;
+pub mod source;
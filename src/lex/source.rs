@@ -0,0 +1,93 @@
+use std::ops::Range;
+
+// A range of bytes of the preprocessed source, such as the tokens an AST node was parsed from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize
+}
+
+impl Span {
+    // The smallest span covering both spans, e.g. both operands of a binary expression
+    pub fn to(self, other: Span) -> Span {
+        return Span { start: self.start.min(other.start), end: self.end.max(other.end) };
+    }
+}
+
+impl From<Range<usize>> for Span {
+    fn from(range: Range<usize>) -> Self {
+        return Span { start: range.start, end: range.end };
+    }
+}
+
+// Where a byte of the preprocessed source comes from. Lines and columns start at 1
+#[derive(Debug, Clone, PartialEq)]
+pub struct Location<'a> {
+    pub file: &'a str,
+    pub line: usize,
+    pub column: usize
+}
+
+// Maps offsets in the preprocessed source back to the file and line they come from, following
+// the linemarkers the preprocessor leaves, e.g. `# 12 "file.c" 2` when the next line is line 12
+// of `file.c`. Without any linemarker, lines are those of the preprocessed source itself
+#[derive(Debug, Clone)]
+pub struct SourceMap {
+    buffer: String,
+    // Offset of the start of every line
+    line_starts: Vec<usize>,
+    // The (0-based) line of every linemarker, with the file and line of the line after it
+    markers: Vec<(usize, String, usize)>,
+    default_file: String
+}
+
+impl SourceMap {
+    pub fn new(buffer: &str, default_file: &str) -> Self {
+        let mut line_starts = vec![0];
+        let mut markers = vec![];
+
+        for (index, line) in buffer.split('\n').enumerate() {
+            if let Some((file, line)) = Self::parse_linemarker(line) {
+                markers.push((index, file, line));
+            }
+            let start = line_starts[index] + line.len() + 1;
+            line_starts.push(start);
+        }
+        line_starts.pop();
+
+        return Self { buffer: buffer.to_string(), line_starts, markers, default_file: default_file.to_string() };
+    }
+
+    // Linemarkers are `# <line> "<file>"`, followed by flags we don't need
+    fn parse_linemarker(line: &str) -> Option<(String, usize)> {
+        let rest = line.strip_prefix('#')?.trim_start();
+        let (number, rest) = rest.split_once(' ')?;
+        let line = number.parse().ok()?;
+        let file = rest.trim_start().strip_prefix('"')?;
+        let (file, _) = file.split_once('"')?;
+        return Some((file.to_string(), line));
+    }
+
+    fn line_index(&self, offset: usize) -> usize {
+        return self.line_starts.partition_point(|start| *start <= offset) - 1;
+    }
+
+    pub fn locate(&self, offset: usize) -> Location<'_> {
+        let index = self.line_index(offset);
+        let column = offset - self.line_starts[index] + 1;
+
+        let marker = self.markers.partition_point(|(line, _, _)| *line < index);
+        match marker.checked_sub(1).map(|marker| &self.markers[marker]) {
+            Some((marker_index, file, line)) => Location { file, line: line + index - marker_index - 1, column },
+            None => Location { file: &self.default_file, line: index + 1, column }
+        }
+    }
+
+    // The offset and text (without its newline) of the line of the preprocessed source
+    // containing `offset`. Macros are expanded in it, as the original source isn't kept
+    pub fn line(&self, offset: usize) -> (usize, &str) {
+        let start = self.line_starts[self.line_index(offset)];
+        let end = self.buffer[start..].find('\n').map_or(self.buffer.len(), |end| start + end);
+        return (start, &self.buffer[start..end]);
+    }
+}
@@ -43,4 +43,191 @@ mod lexer_tests {
         assert_eq!(lexer.next(), Token { tag: Tag::Invalid, range: 4..5 });
         assert_eq!(lexer.next(), Token { tag: Tag::KVoid, range: 6..10 });
     }
+
+    #[test]
+    fn test_storage_class_declaration() {
+        let mut lexer = Lexer::load_test_str("static int x = f(a, b);");
+        assert_eq!(lexer.next(), Token { tag: Tag::KStatic, range: 0..6 });
+        assert_eq!(lexer.next(), Token { tag: Tag::KInt, range: 7..10 });
+        assert_eq!(lexer.next(), Token { tag: Tag::Identifier, range: 11..12 });
+        assert_eq!(lexer.next(), Token { tag: Tag::OpAssign, range: 13..14 });
+        assert_eq!(lexer.next(), Token { tag: Tag::Identifier, range: 15..16 });
+        assert_eq!(lexer.next(), Token { tag: Tag::LParen, range: 16..17 });
+        assert_eq!(lexer.next(), Token { tag: Tag::Identifier, range: 17..18 });
+        assert_eq!(lexer.next(), Token { tag: Tag::Comma, range: 18..19 });
+        assert_eq!(lexer.next(), Token { tag: Tag::Identifier, range: 20..21 });
+        assert_eq!(lexer.next(), Token { tag: Tag::RParen, range: 21..22 });
+        assert_eq!(lexer.next(), Token { tag: Tag::Semicolon, range: 22..23 });
+    }
+
+    #[test]
+    fn test_integer_operators() {
+        let mut lexer = Lexer::load_test_str("unsigned long x = 1ul << 2 >= y != z;");
+        assert_eq!(lexer.next(), Token { tag: Tag::KUnsigned, range: 0..8 });
+        assert_eq!(lexer.next(), Token { tag: Tag::KLong, range: 9..13 });
+        assert_eq!(lexer.next(), Token { tag: Tag::Identifier, range: 14..15 });
+        assert_eq!(lexer.next(), Token { tag: Tag::OpAssign, range: 16..17 });
+        assert_eq!(lexer.next(), Token { tag: Tag::NumberLiteral, range: 18..21 });
+        assert_eq!(lexer.next(), Token { tag: Tag::OpShiftLeft, range: 22..24 });
+        assert_eq!(lexer.next(), Token { tag: Tag::NumberLiteral, range: 25..26 });
+        assert_eq!(lexer.next(), Token { tag: Tag::OpGreaterEqual, range: 27..29 });
+        assert_eq!(lexer.next(), Token { tag: Tag::Identifier, range: 30..31 });
+        assert_eq!(lexer.next(), Token { tag: Tag::OpNotEqual, range: 32..34 });
+        assert_eq!(lexer.next(), Token { tag: Tag::Identifier, range: 35..36 });
+        assert_eq!(lexer.next(), Token { tag: Tag::Semicolon, range: 36..37 });
+    }
+
+    #[test]
+    fn test_array_brackets() {
+        let mut lexer = Lexer::load_test_str("a[1][2]");
+        assert_eq!(lexer.next(), Token { tag: Tag::Identifier, range: 0..1 });
+        assert_eq!(lexer.next(), Token { tag: Tag::LBracket, range: 1..2 });
+        assert_eq!(lexer.next(), Token { tag: Tag::NumberLiteral, range: 2..3 });
+        assert_eq!(lexer.next(), Token { tag: Tag::RBracket, range: 3..4 });
+        assert_eq!(lexer.next(), Token { tag: Tag::LBracket, range: 4..5 });
+        assert_eq!(lexer.next(), Token { tag: Tag::NumberLiteral, range: 5..6 });
+        assert_eq!(lexer.next(), Token { tag: Tag::RBracket, range: 6..7 });
+    }
+
+    #[test]
+    fn test_member_access() {
+        let mut lexer = Lexer::load_test_str("struct s; p->x.y - q;");
+        assert_eq!(lexer.next(), Token { tag: Tag::KStruct, range: 0..6 });
+        assert_eq!(lexer.next(), Token { tag: Tag::Identifier, range: 7..8 });
+        assert_eq!(lexer.next(), Token { tag: Tag::Semicolon, range: 8..9 });
+        assert_eq!(lexer.next(), Token { tag: Tag::Identifier, range: 10..11 });
+        assert_eq!(lexer.next(), Token { tag: Tag::OpArrow, range: 11..13 });
+        assert_eq!(lexer.next(), Token { tag: Tag::Identifier, range: 13..14 });
+        assert_eq!(lexer.next(), Token { tag: Tag::OpDot, range: 14..15 });
+        assert_eq!(lexer.next(), Token { tag: Tag::Identifier, range: 15..16 });
+        assert_eq!(lexer.next(), Token { tag: Tag::OpNegation, range: 17..18 });
+        assert_eq!(lexer.next(), Token { tag: Tag::Identifier, range: 19..20 });
+        assert_eq!(lexer.next(), Token { tag: Tag::Semicolon, range: 20..21 });
+    }
+
+    #[test]
+    fn test_floating_literals() {
+        let mut lexer = Lexer::load_test_str("double x = 1.5e-3 - .25f+2.;");
+        assert_eq!(lexer.next(), Token { tag: Tag::KDouble, range: 0..6 });
+        assert_eq!(lexer.next(), Token { tag: Tag::Identifier, range: 7..8 });
+        assert_eq!(lexer.next(), Token { tag: Tag::OpAssign, range: 9..10 });
+        assert_eq!(lexer.next(), Token { tag: Tag::NumberLiteral, range: 11..17 });
+        assert_eq!(lexer.next(), Token { tag: Tag::OpNegation, range: 18..19 });
+        assert_eq!(lexer.next(), Token { tag: Tag::NumberLiteral, range: 20..24 });
+        assert_eq!(lexer.next(), Token { tag: Tag::OpAddition, range: 24..25 });
+        assert_eq!(lexer.next(), Token { tag: Tag::NumberLiteral, range: 25..27 });
+        assert_eq!(lexer.next(), Token { tag: Tag::Semicolon, range: 27..28 });
+    }
+
+    #[test]
+    fn test_hexadecimal_literals() {
+        let mut lexer = Lexer::load_test_str("x = 0x1eUL+0XaB-0755-1e;");
+        assert_eq!(lexer.next(), Token { tag: Tag::Identifier, range: 0..1 });
+        assert_eq!(lexer.next(), Token { tag: Tag::OpAssign, range: 2..3 });
+        assert_eq!(lexer.next(), Token { tag: Tag::NumberLiteral, range: 4..10 });
+        assert_eq!(lexer.next(), Token { tag: Tag::OpAddition, range: 10..11 });
+        assert_eq!(lexer.next(), Token { tag: Tag::NumberLiteral, range: 11..15 });
+        assert_eq!(lexer.next(), Token { tag: Tag::OpNegation, range: 15..16 });
+        assert_eq!(lexer.next(), Token { tag: Tag::NumberLiteral, range: 16..20 });
+        assert_eq!(lexer.next(), Token { tag: Tag::OpNegation, range: 20..21 });
+        assert_eq!(lexer.next(), Token { tag: Tag::NumberLiteral, range: 21..23 });
+        assert_eq!(lexer.next(), Token { tag: Tag::Semicolon, range: 23..24 });
+    }
+
+    #[test]
+    fn test_string_literals() {
+        let mut lexer = Lexer::load_test_str("s = \"a\\\"b\" \"\";\n\"open\n\"x");
+        assert_eq!(lexer.next(), Token { tag: Tag::Identifier, range: 0..1 });
+        assert_eq!(lexer.next(), Token { tag: Tag::OpAssign, range: 2..3 });
+        assert_eq!(lexer.next(), Token { tag: Tag::StringLiteral, range: 4..10 });
+        assert_eq!(lexer.next(), Token { tag: Tag::StringLiteral, range: 11..13 });
+        assert_eq!(lexer.next(), Token { tag: Tag::Semicolon, range: 13..14 });
+        assert_eq!(lexer.next().tag, Tag::Invalid);
+        assert_eq!(lexer.next().tag, Tag::Invalid);
+    }
+
+    #[test]
+    fn test_variadic_tokens() {
+        let mut lexer = Lexer::load_test_str("f(int n, ...) __builtin_va_list ap; __builtin_va_arg(ap, int)..");
+        assert_eq!(lexer.next(), Token { tag: Tag::Identifier, range: 0..1 });
+        assert_eq!(lexer.next(), Token { tag: Tag::LParen, range: 1..2 });
+        assert_eq!(lexer.next(), Token { tag: Tag::KInt, range: 2..5 });
+        assert_eq!(lexer.next(), Token { tag: Tag::Identifier, range: 6..7 });
+        assert_eq!(lexer.next(), Token { tag: Tag::Comma, range: 7..8 });
+        assert_eq!(lexer.next(), Token { tag: Tag::Ellipsis, range: 9..12 });
+        assert_eq!(lexer.next(), Token { tag: Tag::RParen, range: 12..13 });
+        assert_eq!(lexer.next(), Token { tag: Tag::KBuiltinVaList, range: 14..31 });
+        assert_eq!(lexer.next(), Token { tag: Tag::Identifier, range: 32..34 });
+        assert_eq!(lexer.next(), Token { tag: Tag::Semicolon, range: 34..35 });
+        assert_eq!(lexer.next(), Token { tag: Tag::KBuiltinVaArg, range: 36..52 });
+        assert_eq!(lexer.next(), Token { tag: Tag::LParen, range: 52..53 });
+        assert_eq!(lexer.next(), Token { tag: Tag::Identifier, range: 53..55 });
+        assert_eq!(lexer.next(), Token { tag: Tag::Comma, range: 55..56 });
+        assert_eq!(lexer.next(), Token { tag: Tag::KInt, range: 57..60 });
+        assert_eq!(lexer.next(), Token { tag: Tag::RParen, range: 60..61 });
+        assert_eq!(lexer.next(), Token { tag: Tag::OpDot, range: 61..62 });
+        assert_eq!(lexer.next(), Token { tag: Tag::OpDot, range: 62..63 });
+    }
+
+    #[test]
+    fn test_type_qualifiers() {
+        let mut lexer = Lexer::load_test_str("const char *restrict s; volatile int __restrict constant;");
+        assert_eq!(lexer.next(), Token { tag: Tag::KConst, range: 0..5 });
+        assert_eq!(lexer.next(), Token { tag: Tag::KChar, range: 6..10 });
+        assert_eq!(lexer.next(), Token { tag: Tag::OpMultiplication, range: 11..12 });
+        assert_eq!(lexer.next(), Token { tag: Tag::KRestrict, range: 12..20 });
+        assert_eq!(lexer.next(), Token { tag: Tag::Identifier, range: 21..22 });
+        assert_eq!(lexer.next(), Token { tag: Tag::Semicolon, range: 22..23 });
+        assert_eq!(lexer.next(), Token { tag: Tag::KVolatile, range: 24..32 });
+        assert_eq!(lexer.next(), Token { tag: Tag::KInt, range: 33..36 });
+        assert_eq!(lexer.next(), Token { tag: Tag::KRestrict, range: 37..47 });
+        assert_eq!(lexer.next(), Token { tag: Tag::Identifier, range: 48..56 });
+    }
+
+    #[test]
+    fn test_bool_keyword() {
+        let mut lexer = Lexer::load_test_str("_Bool b; _Boolean;");
+        assert_eq!(lexer.next(), Token { tag: Tag::KBool, range: 0..5 });
+        assert_eq!(lexer.next(), Token { tag: Tag::Identifier, range: 6..7 });
+        assert_eq!(lexer.next(), Token { tag: Tag::Semicolon, range: 7..8 });
+        assert_eq!(lexer.next(), Token { tag: Tag::Identifier, range: 9..17 });
+    }
+
+    #[test]
+    fn test_static_assert_keyword() {
+        let mut lexer = Lexer::load_test_str("_Static_assert(1, \"ok\");");
+        assert_eq!(lexer.next(), Token { tag: Tag::KStaticAssert, range: 0..14 });
+        assert_eq!(lexer.next(), Token { tag: Tag::LParen, range: 14..15 });
+        assert_eq!(lexer.next(), Token { tag: Tag::NumberLiteral, range: 15..16 });
+        assert_eq!(lexer.next(), Token { tag: Tag::Comma, range: 16..17 });
+        assert_eq!(lexer.next(), Token { tag: Tag::StringLiteral, range: 18..22 });
+    }
+
+    #[test]
+    fn test_bit_field_colon() {
+        let mut lexer = Lexer::load_test_str("unsigned x : 3;");
+        assert_eq!(lexer.next(), Token { tag: Tag::KUnsigned, range: 0..8 });
+        assert_eq!(lexer.next(), Token { tag: Tag::Identifier, range: 9..10 });
+        assert_eq!(lexer.next(), Token { tag: Tag::Colon, range: 11..12 });
+        assert_eq!(lexer.next(), Token { tag: Tag::NumberLiteral, range: 13..14 });
+        assert_eq!(lexer.next(), Token { tag: Tag::Semicolon, range: 14..15 });
+    }
+
+    #[test]
+    fn test_inline_keyword() {
+        let mut lexer = Lexer::load_test_str("static inline __inline__ int;");
+        assert_eq!(lexer.next(), Token { tag: Tag::KStatic, range: 0..6 });
+        assert_eq!(lexer.next(), Token { tag: Tag::KInline, range: 7..13 });
+        assert_eq!(lexer.next(), Token { tag: Tag::KInline, range: 14..24 });
+        assert_eq!(lexer.next(), Token { tag: Tag::KInt, range: 25..28 });
+    }
+
+    #[test]
+    fn test_linemarkers() {
+        let mut lexer = Lexer::load_test_str("# 1 \"a.c\"\nint x;\n#pragma once\nint;");
+        assert_eq!(lexer.next(), Token { tag: Tag::KInt, range: 10..13 });
+        assert_eq!(lexer.next(), Token { tag: Tag::Identifier, range: 14..15 });
+        assert_eq!(lexer.next(), Token { tag: Tag::Semicolon, range: 15..16 });
+        assert_eq!(lexer.next(), Token { tag: Tag::KInt, range: 30..33 });
+    }
 }
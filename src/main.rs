@@ -1,5 +1,6 @@
-use core::panic;
-use std::{fs, io::Write, path::PathBuf, process};
+// The codebase spells out its returns explicitly
+#![allow(clippy::needless_return)]
+use std::{fs, io::{IsTerminal, Write}, path::PathBuf, process};
 use clap::{arg, command, ArgAction, ArgGroup};
 mod debug;
 use debug::dprintln;
@@ -10,6 +11,16 @@ mod ast;
 use ast::parser;
 mod zil;
 mod assembly;
+mod diagnostics;
+use diagnostics::{Diagnostic, DiagnosticEngine, Format, Severity};
+use diagnostics::warnings::WarningOptions;
+use lex::source::SourceMap;
+
+// Stops compiling, with a summary of the diagnostics reported, failing if any was an error
+fn finish(engine: &DiagnosticEngine) -> ! {
+    engine.finish();
+    process::exit(if engine.has_errors() { 1 } else { 0 });
+}
 
 fn main() {
     let matches = command!()
@@ -22,6 +33,14 @@ fn main() {
         .arg(arg!(tacky: --tacky "Runs the compiler through the ZIL generation stage, stopping before assembly generation").action(ArgAction::SetTrue))
         .arg(arg!(codegen: --codegen "Runs the lexer, parser and assembly generation, but stops before code emission").action(ArgAction::SetTrue))
         .arg(arg!(assemble: -S --assemble "Emits an assembly file (if generated), but does not link it").action(ArgAction::SetTrue))
+        .arg(arg!(debug: -g "Emits line tables, which map the generated code back to the source").action(ArgAction::SetTrue))
+        .arg(arg!(diagnostics_format: --"diagnostics-format" <FORMAT> "Reports diagnostics as source snippets (`text`), or for tools as JSON (`json`) or SARIF (`sarif`)")
+                    .value_parser(Format::NAMES)
+                    .default_value("text")
+                )
+        .arg(arg!(options: -f <option> "Code generation and diagnostic options, e.g. `-ferror-limit=20` or `-fno-color-diagnostics`").action(ArgAction::Append))
+        .arg(arg!(warnings: -W <warning> "Enables (`-Wshadow`) or disables (`-Wno-shadow`) a warning or group of warnings (`-Wall`, `-Wextra`), or makes warnings errors (`-Werror`, `-Werror=shadow`)").action(ArgAction::Append))
+        .arg(arg!(no_warnings: -w "Suppresses all warnings, except those made errors").action(ArgAction::SetTrue))
         .group(ArgGroup::new("directives")
                             .args(["lex", "parse", "codegen"])
                             .multiple(false)
@@ -29,26 +48,76 @@ fn main() {
         )
         .get_matches();
 
+    // Stop reporting syntax errors after this many (0 for no limit), as after the
+    // first few, errors are likely to be caused by recovering from earlier ones
+    let mut error_limit: usize = 20;
+    // Diagnostics are colored when shown in a terminal, unless told otherwise
+    let mut color = std::io::stderr().is_terminal();
+    let mut unsupported = vec![];
+    for option in matches.get_many::<String>("options").into_iter().flatten() {
+        match option.as_str() {
+            "color-diagnostics" => color = true,
+            "no-color-diagnostics" => color = false,
+            option => match option.strip_prefix("error-limit=").map(str::parse) {
+                Some(Ok(limit)) => error_limit = limit,
+                _ => unsupported.push(option)
+            }
+        }
+    }
+
+    let mut warnings = WarningOptions::default();
+    let mut unknown_warnings = vec![];
+    for option in matches.get_many::<String>("warnings").into_iter().flatten() {
+        if !warnings.apply(option) {
+            unknown_warnings.push(option);
+        }
+    }
+    if matches.get_flag("no_warnings") {
+        warnings.suppress();
+    }
+
+    let format = matches.get_one::<String>("diagnostics_format").and_then(|name| Format::from_name(name)).unwrap_or(Format::Text);
+    let mut engine = DiagnosticEngine::new(format, color, warnings);
+    for option in unsupported {
+        engine.emit(&Diagnostic::error(format!("Unsupported option `-f{}`", option)), None);
+    }
+    for option in unknown_warnings {
+        engine.emit(&Diagnostic::warning(format!("Unknown warning option `-W{}`", option)), None);
+    }
     let path: &PathBuf = matches.get_one("path").expect("Path to operate on is required!");
     if !path.exists() {
-        eprintln!("Error: invalid input file path provided");
-        process::exit(128);
+        engine.emit(&Diagnostic::error(format!("No such file: `{}`", path.display())), None);
+    }
+    if engine.has_errors() {
+        finish(&engine);
     }
 
     // Preprocess the files using GCC (as zcc only acts as a compiler)
     let preprocessed_path = path.clone().with_extension("i"); // output to same file with `.i` extension
     let mut preprocess = process::Command::new("gcc");
-    preprocess.arg("-E") // run only the preprocessor
-              .arg("-P") // don't emit linemarkers
+    preprocess.arg("-E") // run only the preprocessor (whose linemarkers the source map follows)
+              .arg("-I").arg(concat!(env!("CARGO_MANIFEST_DIR"), "/include")) // zcc's own headers, e.g. <stdbool.h>
               .arg(path.clone().into_os_string())
               .arg("-o")
               .arg(preprocessed_path.clone().into_os_string());
-    preprocess.status().expect("GCC Error: failed to preprocess the given input!");
+    // GCC reports the errors it finds itself
+    match preprocess.status() {
+        Ok(status) if status.success() => {},
+        Ok(_) => {
+            engine.emit(&Diagnostic::error("Preprocessing failed"), None);
+            finish(&engine);
+        },
+        Err(e) => {
+            engine.emit(&Diagnostic::error(format!("Unable to run the preprocessor (gcc): {}", e)), None);
+            finish(&engine);
+        }
+    }
     drop(preprocess);
 
 
     // - 1. Run the lexer
     let mut lexer = lexer::Lexer::load(&preprocessed_path);
+    let source_map = SourceMap::new(&lexer.buffer, &path.to_string_lossy());
     let mut tokens = vec![];
     loop {
         let token = lexer.next();
@@ -57,9 +126,11 @@ fn main() {
             break;
         }
         if matches!(token.tag, lexer::Tag::Invalid) {
-            let str = &lexer.buffer[token.range.clone()];
-            println!("Lexer error: encountered invalid tag `{}` in range {}-{}", str, token.range.start, token.range.end);
-            process::exit(2);
+            let diagnostic = Diagnostic::error(format!("Invalid token `{}`", &lexer.buffer[token.range.clone()]))
+                .with_code(diagnostics::INVALID_TOKEN)
+                .with_label(token.range.clone().into(), "");
+            engine.emit(&diagnostic, Some(&source_map));
+            continue;
         }
 
         dprintln!("Found tag of type {:?}; value: '{}'", token.tag, &lexer.buffer[token.range.clone()]);
@@ -68,80 +139,135 @@ fn main() {
     dprintln!("Lexed file successfully.\n");
 
     // Erase the preprocessed file, as it is no longer necessary
-    if fs::remove_file(preprocessed_path).is_err() {
-        println!("Failed to remove preprocessed intermediate file.");
+    if let Err(e) = fs::remove_file(&preprocessed_path) {
+        engine.emit(&Diagnostic::warning(format!("Unable to remove `{}`: {}", preprocessed_path.display(), e)), None);
+    }
+    if engine.has_errors() {
+        finish(&engine);
     }
     // If we are just lexing, exit gracefully if succeeded
     if matches.get_flag("lex") {
-        process::exit(0);
+        finish(&engine);
     }
 
 
     // - 2. Parse the tokens
     let mut t = parser::ASTParser::new(lexer.buffer, tokens);
+    t.set_error_limit(error_limit);
 
     let result = t.parse();
-    let ast_tree: ast::symbols::Program;
-    match result {
+    let ast_tree: ast::symbols::Program = match result {
         Ok(program_tree) => {
             dprintln!("Built AST successfully.");
             dprintln!("{:#?}", &program_tree);
 
-            if matches.get_flag("parse") {
-                process::exit(0);
+            program_tree
+        },
+        Err(errors) => {
+            for e in errors {
+                engine.emit(&e.into_diagnostic(), Some(&source_map));
+            }
+            if t.stopped_early() {
+                let diagnostic = Diagnostic::new(Severity::Fatal, "Too many errors emitted, stopping now")
+                    .with_note("use `-ferror-limit=0` for no limit");
+                engine.emit(&diagnostic, None);
             }
-            
-            ast_tree = program_tree;
+            finish(&engine);
+        }
+    };
+
+    // Resolve identifiers and their linkage, then check types, before accepting the program
+    // Each pass finds its own warnings, which are reported in the order of the source
+    let semantic = ast::resolve::resolve(ast_tree).and_then(|(tree, symbols, mut warnings)| {
+        let (tree, mut type_warnings) = ast::typecheck::typecheck(tree, &symbols)?;
+        warnings.append(&mut type_warnings);
+        warnings.sort_by_key(|warning| warning.primary_span().map(|span| span.start));
+        Ok((tree, symbols, warnings))
+    });
+    let (ast_tree, mut symbols) = match semantic {
+        Ok((tree, symbols, warnings)) => {
+            dprintln!("Resolved identifiers and types successfully.");
+            dprintln!("{:#?}", &symbols);
+
+            // Warnings may have been made errors
+            for warning in &warnings {
+                engine.emit(warning, Some(&source_map));
+            }
+            if engine.has_errors() || matches.get_flag("parse") {
+                finish(&engine);
+            }
+
+            (tree, symbols)
         },
         Err(e) => {
-            panic!("{}", e);
+            engine.emit(&e.into_diagnostic(), Some(&source_map));
+            finish(&engine);
         }
-    }
+    };
 
     // - 3. Convert the Tree to Z intermediate language
-    let intermediate: zil::symbols::Program = zil::transpile::parse(ast_tree.clone());
+    let intermediate: zil::symbols::Program = zil::transpile::parse(ast_tree, &mut symbols);
     dprintln!("\nTranspiled to ZIL successfully.");
     dprintln!("{:#?}", intermediate);
     if matches.get_flag("tacky") {
-        process::exit(0);
+        finish(&engine);
     }
 
     // - 4. Compile ZIL into Assembly
-    let mut transpiler = assembly::transpile::STranspiler::new();
+    let mut transpiler = assembly::transpile::STranspiler::new(symbols);
     let assembled = transpiler.parse(intermediate);
     dprintln!("\nTranspiled to Assembly successfully.");
     dprintln!("{:#?}", assembled);
 
 
     // - 5. Codegen from assembly output
-    let code = assembly::agen::codegen(&assembled);
+    let code = assembly::agen::codegen(&assembled, matches.get_flag("debug").then_some(&source_map));
     let should_output = matches.get_flag("assemble");
     dprintln!("\nCodegen successful!");
     dprintln!("{}", code);
 
     // - 6. Assemble and link
     if matches.get_flag("codegen") && !should_output {
-        process::exit(0);
+        finish(&engine);
     }
 
     let assembly_path = path.clone().with_extension("s");
     let output_path = path.clone().with_extension("");
 
-    let mut file_handle = fs::File::create(&assembly_path).expect("IOError: Unable to create output file");
-    write!(file_handle, "{}", code).expect("IOError: Unable to write to output file");
+    let written = fs::File::create(&assembly_path).and_then(|mut file| write!(file, "{}", code));
+    if let Err(e) = written {
+        engine.emit(&Diagnostic::error(format!("Unable to write `{}`: {}", assembly_path.display(), e)), None);
+        finish(&engine);
+    }
 
     if matches.get_flag("codegen") {
-        process::exit(0);
+        finish(&engine);
     }
 
     let mut assemble = process::Command::new("gcc");
     assemble.arg(assembly_path.clone().into_os_string())
               .arg("-o")
-              .arg(&output_path.into_os_string());
-    assemble.status().expect("GCC Error: failed to assemble the output!");
+              .arg(output_path.into_os_string());
+    let assembled = assemble.status();
     drop(assemble);
 
     if !should_output {
-        fs::remove_file(&assembly_path).expect("IOError: Unable to delete assembly intermediate");
+        if let Err(e) = fs::remove_file(&assembly_path) {
+            engine.emit(&Diagnostic::warning(format!("Unable to remove `{}`: {}", assembly_path.display(), e)), None);
+        }
+    }
+    // GCC reports the errors it finds itself
+    match assembled {
+        Ok(status) if status.success() => {},
+        Ok(_) => {
+            engine.emit(&Diagnostic::error("Assembling or linking failed"), None);
+            finish(&engine);
+        },
+        Err(e) => {
+            engine.emit(&Diagnostic::error(format!("Unable to run the assembler (gcc): {}", e)), None);
+            finish(&engine);
+        }
     }
+
+    finish(&engine);
 }
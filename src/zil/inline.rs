@@ -0,0 +1,179 @@
+use std::collections::{HashMap, HashSet};
+use crate::ast::symbols::Type;
+use crate::ast::table::{InitialValue, StaticInit, Symbol, SymbolAttributes, SymbolTable};
+use super::symbols as Z;
+
+// Functions declared `inline` are inlined into their callers when their body is at most
+// this many instructions long. Bodies are straight-line code, so this bounds the growth
+// of every caller to a constant per call
+const MAX_INLINED_INSTRUCTIONS: usize = 40;
+
+// Replaces direct calls to small functions declared `inline` by their body, then drops
+// the functions that mustn't be emitted: inline definitions, which are left to another
+// translation unit to define externally, and `static inline` functions that are no longer
+// referenced. Other functions are emitted even if unused, as their address may be taken
+pub fn inline_functions(program: Z::Program, symbols: &mut SymbolTable) -> Z::Program {
+    let bodies: HashMap<String, Z::FunctionDefinition> = program.iter()
+        .filter_map(|construct| match construct {
+            Z::Construct::Function(f) if is_inlinable(f, symbols) => Some((f.identifier.clone(), f.clone())),
+            _ => None
+        })
+        .collect();
+
+    let mut sites = 0;
+    let mut program: Z::Program = program.into_iter()
+        .map(|construct| match construct {
+            Z::Construct::Function(f) => {
+                let mut expanding = vec![f.identifier.clone()];
+                let instructions = inline_calls(f.instructions, &mut expanding, &bodies, &mut sites, symbols);
+                Z::Construct::Function(Z::FunctionDefinition { instructions, ..f })
+            },
+            construct => construct
+        })
+        .collect();
+
+    // Dropping an unreferenced function may leave others unreferenced, e.g. the
+    // `static inline` helpers it was the only caller of, so repeat until none is
+    loop {
+        let referenced = referenced_functions(&mut program, symbols);
+        let count = program.len();
+        program.retain(|construct| match construct {
+            Z::Construct::Function(f) => match &symbols.get(&f.identifier).expect("Internal ZIL error -- function missing from symbol table").attributes {
+                SymbolAttributes::Function { global: true, inline_only: true, .. } => false,
+                SymbolAttributes::Function { global: false, inline: true, .. } => referenced.contains(&f.identifier),
+                _ => true
+            },
+            Z::Construct::StaticVariable(_) => true
+        });
+
+        if program.len() == count {
+            return program;
+        }
+    }
+}
+
+fn is_inlinable(f: &Z::FunctionDefinition, symbols: &SymbolTable) -> bool {
+    let Some(Symbol { ty: Type::Function(function_type), attributes: SymbolAttributes::Function { inline: true, .. } }) = symbols.get(&f.identifier) else {
+        return false;
+    };
+
+    // Variable-length arrays are released by restoring the stack pointer, which assumes
+    // the frame of the function they are allocated in, as do variable arguments
+    let uses_frame = f.instructions.iter().any(|i| matches!(i,
+        Z::Instruction::Allocate(_) | Z::Instruction::SaveStack(_) | Z::Instruction::RestoreStack(_) |
+        Z::Instruction::VaStart(_) | Z::Instruction::VaArg(_)
+    ));
+
+    return !function_type.variadic && !uses_frame && f.instructions.len() <= MAX_INLINED_INSTRUCTIONS;
+}
+
+// Calls within inlined bodies are inlined as well, except for calls to the functions being
+// expanded (`expanding`), so (mutually) recursive functions are only ever expanded once
+fn inline_calls(instructions: Vec<Z::Instruction>, expanding: &mut Vec<String>, bodies: &HashMap<String, Z::FunctionDefinition>, sites: &mut usize, symbols: &mut SymbolTable) -> Vec<Z::Instruction> {
+    let mut result = vec![];
+
+    for instruction in instructions {
+        let call = match instruction {
+            Z::Instruction::FunctionCall(call) => call,
+            instruction => {
+                result.push(instruction);
+                continue;
+            }
+        };
+        let callee = match &call.function {
+            Z::Callee::Direct(name) if !expanding.contains(name) => bodies.get(name),
+            _ => None
+        };
+        let Some(callee) = callee else {
+            result.push(Z::Instruction::FunctionCall(call));
+            continue;
+        };
+
+        *sites += 1;
+        let body = expand_call(callee, call.arguments, call.destination, *sites, symbols);
+        expanding.push(callee.identifier.clone());
+        result.extend(inline_calls(body, expanding, bodies, sites, symbols));
+        expanding.pop();
+    }
+
+    return result;
+}
+
+// The body of `callee`, with its parameters initialized from `arguments`, and its return
+// value copied to `destination`. Every local variable of the callee gets a name unique to
+// the call site, while static variables are shared between the callee and every site
+fn expand_call(callee: &Z::FunctionDefinition, arguments: Vec<Z::Value>, destination: Option<Z::Value>, site: usize, symbols: &mut SymbolTable) -> Vec<Z::Instruction> {
+    let mut rename = |name: &mut String| {
+        let Some(symbol) = symbols.get(name) else {
+            panic!("Internal ZIL error -- variable `{}` missing from symbol table", name)
+        };
+        if !matches!(symbol.attributes, SymbolAttributes::Local) {
+            return;
+        }
+
+        let new_name = format!("{}.inline.{}", name, site);
+        let symbol = Symbol { ty: symbol.ty.clone(), attributes: SymbolAttributes::Local };
+        symbols.insert(new_name.clone(), symbol);
+        *name = new_name;
+    };
+
+    let mut instructions = vec![];
+    for (param, argument) in callee.params.iter().zip(arguments) {
+        let mut param = param.clone();
+        rename(&mut param);
+        instructions.push(Z::Instruction::Copy(Z::CopyInstructionDefinition { source: argument, destination: Z::Value::Variable(param) }));
+    }
+
+    // Without any jumps, the body ends at its first return. Inlined
+    // instructions are attributed to the call in line tables
+    for instruction in &callee.instructions {
+        let mut instruction = instruction.clone();
+        instruction.for_each_variable(&mut rename);
+
+        match instruction {
+            Z::Instruction::Return(value) => {
+                if let (Some(source), Some(destination)) = (value, destination) {
+                    instructions.push(Z::Instruction::Copy(Z::CopyInstructionDefinition { source, destination }));
+                }
+                break;
+            },
+            Z::Instruction::Location(_) => {},
+            instruction => instructions.push(instruction)
+        }
+    }
+
+    return instructions;
+}
+
+// The functions called or whose address is taken by another function, or by a static initializer
+fn referenced_functions(program: &mut Z::Program, symbols: &SymbolTable) -> HashSet<String> {
+    let mut referenced = HashSet::new();
+
+    for construct in program.iter_mut() {
+        let Z::Construct::Function(f) = construct else { continue };
+        let caller = f.identifier.clone();
+
+        for instruction in f.instructions.iter_mut() {
+            if let Z::Instruction::FunctionCall(Z::FunctionCallDefinition { function: Z::Callee::Direct(name), .. }) = instruction {
+                if *name != caller {
+                    referenced.insert(name.clone());
+                }
+            }
+            instruction.for_each_variable(&mut |name| if *name != caller {
+                referenced.insert(name.clone());
+            });
+        }
+    }
+
+    for (_, symbol) in symbols.iter() {
+        if let SymbolAttributes::Static { init: InitialValue::Initial(init), .. } = &symbol.attributes {
+            for init in init {
+                if let StaticInit::Address(name, _) = init {
+                    referenced.insert(name.clone());
+                }
+            }
+        }
+    }
+
+    return referenced;
+}
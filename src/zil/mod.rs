@@ -1,3 +1,4 @@
 #![allow(dead_code)]
 pub mod symbols;
 pub mod transpile;
+pub mod inline;
@@ -1,39 +1,292 @@
 // zil : Z intermediate language
+use crate::ast::symbols::{ConstantValue, Span, Type};
+use crate::ast::table::{StaticInit, SymbolTable};
+
 pub type Program = Vec<Construct>;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum Construct {
-    Function(FunctionDefinition)
+    Function(FunctionDefinition),
+    StaticVariable(StaticVariableDefinition)
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct FunctionDefinition {
     pub identifier: String,
+    pub global: bool,
+    pub params: Vec<String>,
     pub instructions: Vec<Instruction>
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+pub struct StaticVariableDefinition {
+    pub identifier: String,
+    pub global: bool,
+    pub ty: Type,
+    pub init: Vec<StaticInit>
+}
+
+#[derive(Debug, Clone)]
 pub enum Instruction {
-    Return(Value),
-    Unary(UnaryInstructionDefinition)
+    // Functions returning void (or falling off their end) return no value
+    Return(Option<Value>),
+    Unary(UnaryInstructionDefinition),
+    Binary(BinaryInstructionDefinition),
+    Copy(CopyInstructionDefinition),
+    CopyToOffset(CopyToOffsetInstructionDefinition),
+    CopyFromOffset(CopyFromOffsetInstructionDefinition),
+    // Conversions between integer types of different sizes
+    SignExtend(ConvertInstructionDefinition),
+    ZeroExtend(ConvertInstructionDefinition),
+    Truncate(ConvertInstructionDefinition),
+    // Conversions between integer and floating types, and between floating types
+    IntToFloat(ConvertInstructionDefinition),
+    FloatToInt(ConvertInstructionDefinition),
+    FloatToFloat(ConvertInstructionDefinition),
+    FunctionCall(FunctionCallDefinition),
+    // Memory access through pointers
+    GetAddress(GetAddressInstructionDefinition),
+    Load(LoadInstructionDefinition),
+    Store(StoreInstructionDefinition),
+    // Variable arguments, read through a pointer to a `va_list`
+    VaStart(VaStartInstructionDefinition),
+    VaArg(VaArgInstructionDefinition),
+    // Stack allocation of variable-length arrays. The stack pointer is saved before
+    // allocating any in a block, and restored when leaving it to release them
+    Allocate(AllocateInstructionDefinition),
+    SaveStack(Value),
+    RestoreStack(Value),
+    // The span of the statement the instructions after it come from, for line tables
+    Location(Span)
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct UnaryInstructionDefinition {
     pub operator: UnaryInstructionOperator,
     pub source: Value,
     pub destination: Value
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum UnaryInstructionOperator {
     Complement,
     Negate
 }
 
-#[derive(Debug)]
-#[derive(Clone)]
+#[derive(Debug, Clone)]
+pub struct BinaryInstructionDefinition {
+    pub operator: BinaryInstructionOperator,
+    pub left: Value,
+    pub right: Value,
+    pub destination: Value
+}
+
+#[derive(Debug, Clone)]
+pub enum BinaryInstructionOperator {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Remainder,
+    BitAnd,
+    BitOr,
+    BitXor,
+    ShiftLeft,
+    ShiftRight,
+    Equal,
+    NotEqual,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual
+}
+
+#[derive(Debug, Clone)]
+pub struct CopyInstructionDefinition {
+    pub source: Value,
+    pub destination: Value
+}
+
+// Copies `source` into the object `destination` (such as an
+// array) at `offset` bytes from its start
+#[derive(Debug, Clone)]
+pub struct CopyToOffsetInstructionDefinition {
+    pub source: Value,
+    pub destination: String,
+    pub offset: usize
+}
+
+// Copies the part of the object `source` (such as a structure member)
+// at `offset` bytes from its start into `destination`
+#[derive(Debug, Clone)]
+pub struct CopyFromOffsetInstructionDefinition {
+    pub source: String,
+    pub offset: usize,
+    pub destination: Value
+}
+
+#[derive(Debug, Clone)]
+pub struct ConvertInstructionDefinition {
+    pub source: Value,
+    pub destination: Value
+}
+
+// Stores the address of `source`, which must be a variable, in `destination`
+#[derive(Debug, Clone)]
+pub struct GetAddressInstructionDefinition {
+    pub source: Value,
+    pub destination: Value
+}
+
+// Copies the object `source_pointer` points to into `destination`
+#[derive(Debug, Clone)]
+pub struct LoadInstructionDefinition {
+    pub source_pointer: Value,
+    pub destination: Value
+}
+
+// Copies `source` into the object `destination_pointer` points to
+#[derive(Debug, Clone)]
+pub struct StoreInstructionDefinition {
+    pub source: Value,
+    pub destination_pointer: Value
+}
+
+// Reserves `size` bytes on the stack, aligned to 16 bytes, and stores their address in `destination`
+#[derive(Debug, Clone)]
+pub struct AllocateInstructionDefinition {
+    pub size: Value,
+    pub destination: Value
+}
+
+#[derive(Debug, Clone)]
+pub struct VaStartInstructionDefinition {
+    pub va_list: Value
+}
+
+#[derive(Debug, Clone)]
+pub struct VaArgInstructionDefinition {
+    pub va_list: Value,
+    pub destination: Value
+}
+
+#[derive(Debug, Clone)]
+pub struct FunctionCallDefinition {
+    pub function: Callee,
+    pub arguments: Vec<Value>,
+    // Calls to functions returning void have no destination
+    pub destination: Option<Value>
+}
+
+// Functions are called by name, or through a pointer to them
+#[derive(Debug, Clone)]
+pub enum Callee {
+    Direct(String),
+    Indirect(Value)
+}
+
+#[derive(Debug, Clone)]
 pub enum Value {
-    Constant(String),
+    Constant(ConstantValue),
     Variable(String)
 }
+
+impl Value {
+    // Whether the value is a volatile variable, or one with a volatile member
+    pub fn is_volatile(&self, symbols: &SymbolTable) -> bool {
+        match self {
+            Value::Variable(name) => symbols.get(name).is_some_and(|symbol| symbols.is_volatile(&symbol.ty)),
+            Value::Constant(_) => false
+        }
+    }
+
+    // Whether the value is a pointer to a volatile object
+    fn points_to_volatile(&self, symbols: &SymbolTable) -> bool {
+        match self {
+            Value::Variable(name) => symbols.get(name).and_then(|symbol| symbol.ty.pointee()).is_some_and(|pointee| symbols.is_volatile(pointee)),
+            Value::Constant(_) => false
+        }
+    }
+}
+
+impl Instruction {
+    // Whether the instruction accesses a volatile object, directly or through a pointer.
+    // Passes over ZIL must neither remove such instructions nor merge them with other
+    // accesses, and must keep them in order. Taking the address of an object doesn't access it
+    pub fn is_volatile(&self, symbols: &SymbolTable) -> bool {
+        let volatile = |value: &Value| value.is_volatile(symbols);
+        let object = |name: &String| symbols.get(name).is_some_and(|symbol| symbols.is_volatile(&symbol.ty));
+
+        match self {
+            Instruction::Return(value) => value.as_ref().is_some_and(volatile),
+            Instruction::Unary(def) => volatile(&def.source) || volatile(&def.destination),
+            Instruction::Binary(def) => volatile(&def.left) || volatile(&def.right) || volatile(&def.destination),
+            Instruction::Copy(def) => volatile(&def.source) || volatile(&def.destination),
+            Instruction::CopyToOffset(def) => volatile(&def.source) || object(&def.destination),
+            Instruction::CopyFromOffset(def) => object(&def.source) || volatile(&def.destination),
+            Instruction::SignExtend(def) | Instruction::ZeroExtend(def) | Instruction::Truncate(def) |
+            Instruction::IntToFloat(def) | Instruction::FloatToInt(def) | Instruction::FloatToFloat(def) => {
+                volatile(&def.source) || volatile(&def.destination)
+            },
+            Instruction::FunctionCall(def) => {
+                let callee = match &def.function {
+                    Callee::Indirect(pointer) => volatile(pointer),
+                    Callee::Direct(_) => false
+                };
+                callee || def.arguments.iter().any(volatile) || def.destination.as_ref().is_some_and(volatile)
+            },
+            Instruction::GetAddress(def) => volatile(&def.destination),
+            Instruction::Load(def) => def.source_pointer.points_to_volatile(symbols) || volatile(&def.source_pointer) || volatile(&def.destination),
+            Instruction::Store(def) => def.destination_pointer.points_to_volatile(symbols) || volatile(&def.destination_pointer) || volatile(&def.source),
+            Instruction::VaStart(def) => volatile(&def.va_list),
+            Instruction::VaArg(def) => volatile(&def.va_list) || volatile(&def.destination),
+            Instruction::Allocate(def) => volatile(&def.size) || volatile(&def.destination),
+            Instruction::SaveStack(value) | Instruction::RestoreStack(value) => volatile(value),
+            Instruction::Location(_) => false
+        }
+    }
+
+    // Applies `f` to the name of every variable (or function) the instruction refers to,
+    // but not to the functions it calls by name, e.g. to rename the variables it uses
+    pub fn for_each_variable(&mut self, f: &mut impl FnMut(&mut String)) {
+        let values: Vec<&mut Value> = match self {
+            Instruction::Return(value) => value.iter_mut().collect(),
+            Instruction::Unary(def) => vec![&mut def.source, &mut def.destination],
+            Instruction::Binary(def) => vec![&mut def.left, &mut def.right, &mut def.destination],
+            Instruction::Copy(def) => vec![&mut def.source, &mut def.destination],
+            Instruction::CopyToOffset(def) => {
+                f(&mut def.destination);
+                vec![&mut def.source]
+            },
+            Instruction::CopyFromOffset(def) => {
+                f(&mut def.source);
+                vec![&mut def.destination]
+            },
+            Instruction::SignExtend(def) | Instruction::ZeroExtend(def) | Instruction::Truncate(def) |
+            Instruction::IntToFloat(def) | Instruction::FloatToInt(def) | Instruction::FloatToFloat(def) => {
+                vec![&mut def.source, &mut def.destination]
+            },
+            Instruction::FunctionCall(def) => {
+                let callee = match &mut def.function {
+                    Callee::Indirect(pointer) => Some(pointer),
+                    Callee::Direct(_) => None
+                };
+                callee.into_iter().chain(def.arguments.iter_mut()).chain(def.destination.iter_mut()).collect()
+            },
+            Instruction::GetAddress(def) => vec![&mut def.source, &mut def.destination],
+            Instruction::Load(def) => vec![&mut def.source_pointer, &mut def.destination],
+            Instruction::Store(def) => vec![&mut def.source, &mut def.destination_pointer],
+            Instruction::VaStart(def) => vec![&mut def.va_list],
+            Instruction::VaArg(def) => vec![&mut def.va_list, &mut def.destination],
+            Instruction::Allocate(def) => vec![&mut def.size, &mut def.destination],
+            Instruction::SaveStack(value) | Instruction::RestoreStack(value) => vec![value],
+            Instruction::Location(_) => vec![]
+        };
+
+        for value in values {
+            if let Value::Variable(name) = value {
+                f(name);
+            }
+        }
+    }
+}
@@ -1,64 +1,530 @@
 use crate::ast::symbols as A;
+use crate::ast::symbols::{Type, StructKind};
+use crate::ast::table::{SymbolTable, Symbol, SymbolAttributes, InitialValue, StaticInit, Member, BitField};
 use super::symbols as Z;
 
-pub fn parse(program: A::Program) -> Z::Program {
+pub fn parse(program: A::Program, symbols: &mut SymbolTable) -> Z::Program {
     let mut result = Z::Program::new();
 
     for dec in program {
         match dec {
             A::Declaration::Function(def) => {
-                let mut t = FunctionTranspiler::new(def.name);
+                // Declarations without a body do not generate any code
+                if !def.has_body {
+                    continue;
+                }
+
+                let global = match symbols.get(&def.name).map(|s| &s.attributes) {
+                    Some(SymbolAttributes::Function { global, .. }) => *global,
+                    _ => panic!("Internal ZIL error -- function `{}` missing from symbol table", def.name)
+                };
+
+                let return_type = match symbols.type_of(&def.name) {
+                    Type::Function(f) => *f.ret.clone(),
+                    _ => panic!("Internal ZIL error -- function `{}` without function type", def.name)
+                };
+
+                let mut t = FunctionTranspiler::new(def.name, global, def.params, symbols);
                 for s in def.statements {
                     t.parse_statement(s);
                 }
 
+                // Falling off the end of a function returns 0 (which is only
+                // well-defined for `main`, but a safe default for any function)
+                // at its closing brace
+                let zero = A::ConstantValue::Int(0);
+                let value = return_type.is_scalar().then(|| Z::Value::Constant(zero.convert(&return_type).unwrap_or(zero)));
+                t.instructions.push(Z::Instruction::Location(A::Span { start: def.span.end - 1, end: def.span.end }));
+                t.instructions.push(Z::Instruction::Return(value));
+
                 result.push(Z::Construct::from_transpiler(t));
-            }
+            },
+
+            // File-scope variables are emitted from the symbol table
+            // below, once every tentative definition has been seen
+            A::Declaration::Variable(_) => {},
+
+            // Structure layouts are only kept in the symbol table, enumerators
+            // were replaced by their value, and static assertions checked
+            A::Declaration::Struct(_) | A::Declaration::Enum(_) | A::Declaration::StaticAssert(_) => {}
         }
     }
 
-    return result;
+    for (name, symbol) in symbols.iter() {
+        if let SymbolAttributes::Static { init, global } = &symbol.attributes {
+            let init = match init {
+                InitialValue::Initial(value) => value.clone(),
+                InitialValue::Tentative => vec![StaticInit::Zero(symbols.size_of(&symbol.ty))],
+                InitialValue::NoInitializer => continue
+            };
+
+            result.push(Z::Construct::StaticVariable(Z::StaticVariableDefinition {
+                identifier: name.clone(),
+                global: *global,
+                ty: symbol.ty.clone(),
+                init
+            }));
+        }
+    }
+
+    return super::inline::inline_functions(result, symbols);
 }
 
 
-struct FunctionTranspiler {
+// The result of an expression is either a plain value, an object reached
+// through a pointer, a member at some offset within a variable, or a bit-field
+// within the storage unit another operand designates. All but the first are
+// only loaded if their value is needed (the operand of `&` or the left side
+// of an assignment is not loaded)
+#[derive(Clone)]
+enum Operand {
+    Plain(Z::Value),
+    Dereferenced(Z::Value),
+    SubObject(String, usize, Type),
+    BitField(Box<Operand>, BitField)
+}
+
+struct FunctionTranspiler<'a> {
     instructions: Vec<Z::Instruction>,
     f_name: String,
-    tmp_count: usize
+    global: bool,
+    params: Vec<String>,
+    tmp_count: usize,
+    symbols: &'a mut SymbolTable
 }
 
-impl FunctionTranspiler {
-    fn new(name: String) -> Self {
+impl<'a> FunctionTranspiler<'a> {
+    fn new(name: String, global: bool, params: Vec<String>, symbols: &'a mut SymbolTable) -> Self {
         Self {
             instructions: vec![],
             f_name: name,
-            tmp_count: 0
+            global,
+            params,
+            tmp_count: 0,
+            symbols
         }
     }
 
-    fn make_temporary(&mut self) -> String {
+    // Temporaries are added to the symbol table, so that the assembly generation
+    // knows the size of each of them. They hold values, which are never qualified
+    fn make_temporary(&mut self, ty: Type) -> Z::Value {
         let name = format!("fn.{}.{}", self.f_name, self.tmp_count);
         self.tmp_count += 1;
-        return name;
+
+        self.symbols.insert(name.clone(), Symbol { ty: ty.unqualified().clone(), attributes: SymbolAttributes::Local });
+        return Z::Value::Variable(name);
+    }
+
+    fn type_of(&self, value: &Z::Value) -> Type {
+        match value {
+            Z::Value::Constant(c) => c.ty(),
+            Z::Value::Variable(name) => self.symbols.type_of(name).unqualified().clone()
+        }
+    }
+
+    // A volatile variable is read even if its value is discarded, as in `x;` or `(void) x`,
+    // where it is otherwise only named. Other objects are loaded to get their value anyway
+    fn read_volatile(&mut self, value: Z::Value) {
+        if let Z::Value::Variable(name) = &value {
+            if self.symbols.is_volatile(self.symbols.type_of(name)) {
+                let ty = self.type_of(&value);
+                let dst = self.make_temporary(ty);
+                self.instructions.push(Z::Instruction::Copy(Z::CopyInstructionDefinition { source: value, destination: dst }));
+            }
+        }
     }
 
     fn parse_statement(&mut self, s: A::Statement){
-        match s {
-            A::Statement::Return(exp) => {
+        if !matches!(s.kind, A::StatementKind::Compound(_) | A::StatementKind::Null) {
+            self.instructions.push(Z::Instruction::Location(s.span));
+        }
+
+        match s.kind {
+            A::StatementKind::Return(exp) => {
                 let value = self.parse_value(exp);
-                self.instructions.push(Z::Instruction::Return(value));
-            }
+                self.instructions.push(Z::Instruction::Return(Some(value)));
+            },
+
+            A::StatementKind::Expression(exp) => {
+                let is_lvalue = exp.is_lvalue();
+                let value = self.parse_value(exp);
+                if is_lvalue {
+                    self.read_volatile(value);
+                }
+            },
+
+            // Only automatic variables are initialized when their declaration is
+            // reached; static ones are initialized before the program starts
+            A::StatementKind::Declaration(A::Declaration::Variable(var)) => {
+                if var.storage_class.is_some() {
+                    return;
+                }
+                if var.var_type.is_variable_length() {
+                    self.allocate_variable_length_array(var);
+                    return;
+                }
+                self.initialize_variable(var);
+            },
+            A::StatementKind::Declaration(A::Declaration::Function(_) | A::Declaration::Struct(_) | A::Declaration::Enum(_) | A::Declaration::StaticAssert(_)) => {},
+
+            // The variable-length arrays of a block are released when leaving it
+            A::StatementKind::Compound(statements) => {
+                let saved = match statements.iter().any(Self::declares_variable_length_array) {
+                    true => {
+                        let saved = self.make_temporary(Type::Pointer(Box::new(Type::Void)));
+                        self.instructions.push(Z::Instruction::SaveStack(saved.clone()));
+                        Some(saved)
+                    },
+                    false => None
+                };
+
+                for s in statements {
+                    self.parse_statement(s);
+                }
+
+                if let Some(saved) = saved {
+                    self.instructions.push(Z::Instruction::RestoreStack(saved));
+                }
+            },
+
+            A::StatementKind::Null => {}
+        }
+    }
+
+    fn declares_variable_length_array(s: &A::Statement) -> bool {
+        matches!(&s.kind, A::StatementKind::Declaration(A::Declaration::Variable(var)) if var.storage_class.is_none() && var.var_type.is_variable_length())
+    }
+
+    // A variable-length array is allocated on the stack each time its declaration is
+    // reached, and its variable becomes a pointer to it, through which it is accessed
+    fn allocate_variable_length_array(&mut self, var: A::VariableDeclaration) {
+        let size = self.size_value(&var.var_type);
+        let pointer = Type::Pointer(Box::new(var.var_type));
+        self.symbols.insert(var.name.clone(), Symbol { ty: pointer, attributes: SymbolAttributes::Local });
+
+        self.instructions.push(Z::Instruction::Allocate(Z::AllocateInstructionDefinition {
+            size,
+            destination: Z::Value::Variable(var.name)
+        }));
+    }
+
+    // Size of an object of the given type, as a `long`. The size of a variable-length
+    // array is computed from the number of elements of each of its dimensions
+    fn size_value(&mut self, ty: &Type) -> Z::Value {
+        let (count, element) = match ty.unqualified() {
+            Type::VariableArray(element, count) => (Z::Value::Variable(count.clone()), element.clone()),
+            Type::Array(element, count) if element.is_variable_length() => {
+                (Z::Value::Constant(A::ConstantValue::Long(*count as i64)), element.clone())
+            },
+            ty => return Z::Value::Constant(A::ConstantValue::Long(self.symbols.size_of(ty) as i64))
+        };
+
+        let element = self.size_value(&element);
+        return self.emit_binary(Z::BinaryInstructionOperator::Multiply, count, element, Type::Long);
+    }
+
+    fn initialize_variable(&mut self, var: A::VariableDeclaration) {
+        match var.initializer {
+            Some(A::Initializer::Single(e)) => {
+                let value = self.parse_value(e);
+                self.instructions.push(Z::Instruction::Copy(Z::CopyInstructionDefinition {
+                    source: value,
+                    destination: Z::Value::Variable(var.name)
+                }));
+            },
+            Some(init) => self.parse_initializer(init, &var.var_type, &var.name, 0),
+            None => {}
         }
     }
 
+    // Initializes an array or structure element by element; the type
+    // checker made sure there is an initializer for every one of them
+    fn parse_initializer(&mut self, init: A::Initializer, ty: &Type, name: &str, offset: usize) {
+        match (init, ty.unqualified()) {
+            (A::Initializer::Compound(list, _), Type::Array(element, _)) => {
+                let size = self.symbols.size_of(element);
+                for (idx, init) in list.into_iter().enumerate() {
+                    self.parse_initializer(init, element, name, offset + idx * size);
+                }
+            },
+            (A::Initializer::Compound(list, _), Type::Structure(kind, tag)) => {
+                let members = match self.symbols.get_struct(tag) {
+                    Some(definition) => definition.members.clone(),
+                    None => panic!("Internal ZIL error -- initializer for incomplete structure")
+                };
+                // A union is initialized through a single member, which may be designated
+                if *kind == StructKind::Union {
+                    if let Some(init) = list.first() {
+                        let (designated, init) = init.union_member();
+                        let member = match designated {
+                            Some(member) => members.iter().find(|m| m.name == member).expect("Internal ZIL error -- unknown union member"),
+                            None => &members[0]
+                        };
+                        self.initialize_member(init.clone(), member, name, offset);
+                    }
+                    return;
+                }
+                for (member, init) in members.iter().zip(list) {
+                    self.initialize_member(init, member, name, offset);
+                }
+            },
+            (A::Initializer::Single(e), _) => {
+                let value = self.parse_value(e);
+                self.instructions.push(Z::Instruction::CopyToOffset(Z::CopyToOffsetInstructionDefinition {
+                    source: value,
+                    destination: name.to_string(),
+                    offset
+                }));
+            },
+            _ => panic!("Internal ZIL error -- initializer list for a scalar")
+        }
+    }
+
+    // Bit-fields share their storage unit with other members, so they are stored into it
+    fn initialize_member(&mut self, init: A::Initializer, member: &Member, name: &str, offset: usize) {
+        let Some(bit_field) = member.bit_field else {
+            self.parse_initializer(init, &member.ty, name, offset + member.offset);
+            return;
+        };
+
+        let A::Initializer::Single(e) = init else {
+            panic!("Internal ZIL error -- initializer list for a bit-field");
+        };
+        let value = self.parse_value(e);
+        let unit = Operand::SubObject(name.to_string(), offset + member.offset, member.ty.clone());
+        self.store(Operand::BitField(Box::new(unit), bit_field), value);
+    }
+
+    // Evaluates an expression to a value, loading it if it is behind a pointer
     fn parse_value(&mut self, e: A::Expression) -> Z::Value {
-        match e {
-            A::Expression::Constant(c) => Z::Value::Constant(c.inner().clone()),
-            A::Expression::Unary(op, inner) => {
+        let operand = self.parse_operand(e);
+        return self.load(operand);
+    }
+
+    fn load(&mut self, operand: Operand) -> Z::Value {
+        match operand {
+            Operand::Plain(value) => value,
+            Operand::Dereferenced(pointer) => {
+                let pointee = match self.type_of(&pointer) {
+                    Type::Pointer(pointee) => *pointee,
+                    _ => panic!("Internal ZIL error -- dereference of a non-pointer")
+                };
+
+                let dst = self.make_temporary(pointee);
+                self.instructions.push(Z::Instruction::Load(Z::LoadInstructionDefinition {
+                    source_pointer: pointer,
+                    destination: dst.clone()
+                }));
+
+                return dst;
+            },
+            Operand::SubObject(base, offset, ty) => {
+                let dst = self.make_temporary(ty);
+                self.instructions.push(Z::Instruction::CopyFromOffset(Z::CopyFromOffsetInstructionDefinition {
+                    source: base,
+                    offset,
+                    destination: dst.clone()
+                }));
+
+                return dst;
+            },
+            Operand::BitField(unit, bit_field) => {
+                let ty = self.operand_type(&unit);
+                let unit = self.load(*unit);
+                return self.extract_bit_field(unit, &ty, bit_field);
+            }
+        }
+    }
+
+    // Stores a value into the object an operand designates, returning the value it then
+    // has. Storing into a bit-field reads its storage unit, replaces the bits of the
+    // bit-field and writes the unit back, and the value may have been truncated
+    fn store(&mut self, operand: Operand, src: Z::Value) -> Z::Value {
+        match operand {
+            Operand::Plain(dst) => {
+                self.instructions.push(Z::Instruction::Copy(Z::CopyInstructionDefinition {
+                    source: src,
+                    destination: dst.clone()
+                }));
+
+                return dst;
+            },
+            Operand::Dereferenced(pointer) => {
+                self.instructions.push(Z::Instruction::Store(Z::StoreInstructionDefinition {
+                    source: src.clone(),
+                    destination_pointer: pointer
+                }));
+
+                return src;
+            },
+            Operand::SubObject(base, offset, _) => {
+                self.instructions.push(Z::Instruction::CopyToOffset(Z::CopyToOffsetInstructionDefinition {
+                    source: src.clone(),
+                    destination: base,
+                    offset
+                }));
+
+                return src;
+            },
+            Operand::BitField(unit, bit_field) => {
+                let ty = self.operand_type(&unit).unqualified().clone();
+                let work = Self::bit_field_work_type(&ty, false);
+                let mask = (1u128 << bit_field.width) - 1;
+                let constant = |value: u128| Z::Value::Constant(A::ConstantValue::from_value(value as i128, &work).expect("Work type has literals"));
+                let shift = Z::Value::Constant(A::ConstantValue::Int(bit_field.offset as i32));
+
+                let src = self.emit_conversion(src, &work);
+                let bits = self.emit_binary(Z::BinaryInstructionOperator::BitAnd, src, constant(mask), work.clone());
+                let bits = self.emit_binary(Z::BinaryInstructionOperator::ShiftLeft, bits, shift, work.clone());
+
+                let old = self.load((*unit).clone());
+                let old = self.emit_conversion(old, &work);
+                let kept = !(mask << bit_field.offset) & ((1u128 << (8 * work.size())) - 1);
+                let old = self.emit_binary(Z::BinaryInstructionOperator::BitAnd, old, constant(kept), work.clone());
+                let new = self.emit_binary(Z::BinaryInstructionOperator::BitOr, old, bits, work);
+
+                let new = self.emit_conversion(new, &ty);
+                self.store(*unit, new.clone());
+                return self.extract_bit_field(new, &ty, bit_field);
+            }
+        }
+    }
+
+    // Type in which the bits of a storage unit of type `ty` are manipulated: a signed
+    // bit-field is extracted with an arithmetic shift, which copies its sign bit
+    fn bit_field_work_type(ty: &Type, signed: bool) -> Type {
+        match (ty.size() <= 4, signed) {
+            (true, true) => Type::Int,
+            (true, false) => Type::UInt,
+            (false, true) => Type::Long,
+            (false, false) => Type::ULong
+        }
+    }
+
+    // Value of a bit-field, given the value of its storage unit of type `ty`. Signed
+    // bit-fields are shifted to the top of the work type, then back down with their sign
+    fn extract_bit_field(&mut self, unit: Z::Value, ty: &Type, bit_field: BitField) -> Z::Value {
+        let ty = ty.unqualified();
+        let work = Self::bit_field_work_type(ty, ty.is_signed());
+        let bits = 8 * work.size();
+        let shift = |count: usize| Z::Value::Constant(A::ConstantValue::Int(count as i32));
+
+        let unit = self.emit_conversion(unit, &work);
+        let value = match ty.is_signed() {
+            true => {
+                let value = self.emit_binary(Z::BinaryInstructionOperator::ShiftLeft, unit, shift(bits - bit_field.offset - bit_field.width), work.clone());
+                self.emit_binary(Z::BinaryInstructionOperator::ShiftRight, value, shift(bits - bit_field.width), work)
+            },
+            false => {
+                let mask = A::ConstantValue::from_value((1i128 << bit_field.width) - 1, &work).expect("Work type has literals");
+                let value = self.emit_binary(Z::BinaryInstructionOperator::ShiftRight, unit, shift(bit_field.offset), work.clone());
+                self.emit_binary(Z::BinaryInstructionOperator::BitAnd, value, Z::Value::Constant(mask), work)
+            }
+        };
+
+        return self.emit_conversion(value, ty);
+    }
+
+    // Converts a value to another scalar type
+    fn emit_conversion(&mut self, src: Z::Value, ty: &Type) -> Z::Value {
+        let src_type = self.type_of(&src);
+        if src_type == *ty {
+            return src;
+        }
+        if *ty == Type::Bool {
+            return self.convert_to_bool(src, &src_type);
+        }
+
+        let dst = self.make_temporary(ty.clone());
+        let def = Z::ConvertInstructionDefinition { source: src, destination: dst.clone() };
+        let im = if ty.is_floating() && src_type.is_floating() {
+            Z::Instruction::FloatToFloat(def)
+        } else if ty.is_floating() {
+            Z::Instruction::IntToFloat(def)
+        } else if src_type.is_floating() {
+            Z::Instruction::FloatToInt(def)
+        } else if ty.size() == src_type.size() {
+            Z::Instruction::Copy(Z::CopyInstructionDefinition { source: def.source, destination: def.destination })
+        } else if ty.size() < src_type.size() {
+            Z::Instruction::Truncate(def)
+        } else if src_type.is_signed() {
+            Z::Instruction::SignExtend(def)
+        } else {
+            Z::Instruction::ZeroExtend(def)
+        };
+        self.instructions.push(im);
+
+        return dst;
+    }
+
+    // Type of the object an operand designates
+    fn operand_type(&self, operand: &Operand) -> Type {
+        match operand {
+            Operand::Plain(value) => self.type_of(value),
+            Operand::Dereferenced(pointer) => match self.type_of(pointer) {
+                Type::Pointer(pointee) => *pointee,
+                _ => panic!("Internal ZIL error -- dereference of a non-pointer")
+            },
+            Operand::SubObject(_, _, ty) => ty.clone(),
+            Operand::BitField(unit, _) => self.operand_type(unit)
+        }
+    }
+
+    fn member(&self, ty: &Type, name: &str) -> Member {
+        match self.symbols.member(ty, name) {
+            Some(member) => member.clone(),
+            None => panic!("Internal ZIL error -- `{}` has no member `{}`", ty, name)
+        }
+    }
+
+    // A member that is a bit-field designates part of its storage unit
+    fn with_bit_field(unit: Operand, bit_field: Option<BitField>) -> Operand {
+        match bit_field {
+            Some(bit_field) => Operand::BitField(Box::new(unit), bit_field),
+            None => unit
+        }
+    }
+
+    // Pointer to the member at `offset` bytes from the object `pointer` points to
+    fn member_pointer(&mut self, pointer: Z::Value, offset: usize, member_type: Type) -> Z::Value {
+        let offset = Z::Value::Constant(A::ConstantValue::Long(offset as i64));
+        return self.emit_binary(Z::BinaryInstructionOperator::Add, pointer, offset, Type::Pointer(Box::new(member_type)));
+    }
+
+    fn parse_operand(&mut self, e: A::Expression) -> Operand {
+        let ty = e.ty().clone();
+        match e.kind {
+            A::ExpressionKind::Constant(c) => Operand::Plain(Z::Value::Constant(c)),
+            A::ExpressionKind::Variable(name) if ty.is_variable_length() => Operand::Dereferenced(Z::Value::Variable(name)),
+            A::ExpressionKind::Variable(name) => Operand::Plain(Z::Value::Variable(name)),
+            // The object of a compound literal is initialized each time it is evaluated
+            A::ExpressionKind::CompoundLiteral(literal) => {
+                let name = literal.name.clone();
+                self.initialize_variable(*literal);
+                return Operand::Plain(Z::Value::Variable(name));
+            },
+            A::ExpressionKind::String(_) => panic!("Internal ZIL error -- string literal was not replaced by its array"),
+            A::ExpressionKind::VaStart(ap) => {
+                let va_list = self.parse_value(*ap);
+                self.instructions.push(Z::Instruction::VaStart(Z::VaStartInstructionDefinition { va_list }));
+
+                return Operand::Plain(Z::Value::Constant(A::ConstantValue::Int(0)));
+            },
+            A::ExpressionKind::VaArg(ap, ty) => {
+                let va_list = self.parse_value(*ap);
+                let dst = self.make_temporary(ty);
+                self.instructions.push(Z::Instruction::VaArg(Z::VaArgInstructionDefinition {
+                    va_list,
+                    destination: dst.clone()
+                }));
+
+                return Operand::Plain(dst);
+            },
+            A::ExpressionKind::Unary(op, inner) => {
                 let src = self.parse_value(*inner);
-                let dst = Z::Value::Variable(self.make_temporary());
+                let dst = self.make_temporary(ty);
                 let op = Self::convert_unop(op);
-                
+
                 let im = Z::Instruction::Unary(Z::UnaryInstructionDefinition {
                     operator: op,
                     source: src,
@@ -66,17 +532,231 @@ impl FunctionTranspiler {
                 });
                 self.instructions.push(im);
 
-                return dst;
+                return Operand::Plain(dst);
+            },
+
+            // The type checker converted both operands to the same type, and
+            // determined the type of the result. Pointer arithmetic is scaled here
+            A::ExpressionKind::Binary(op, left, right) => {
+                let left = self.parse_value(*left);
+                let right = self.parse_value(*right);
+                let left_type = self.type_of(&left);
+
+                if let (Type::Pointer(pointee), A::BinaryExpressionType::Add | A::BinaryExpressionType::Subtract) = (&left_type, op) {
+                    let scale = self.size_value(pointee);
+                    return Operand::Plain(self.parse_pointer_arithmetic(op, left, right, scale));
+                }
+
+                return Operand::Plain(self.emit_binary(Self::convert_binop(op), left, right, ty));
+            },
+
+            A::ExpressionKind::Cast(_, inner) => {
+                let is_lvalue = inner.is_lvalue();
+                let src = self.parse_value(*inner);
+                let src_type = self.type_of(&src);
+                if src_type == ty {
+                    return Operand::Plain(src);
+                }
+
+                // The value of a cast to void is never used, so any value will do
+                if ty == Type::Void {
+                    if is_lvalue {
+                        self.read_volatile(src);
+                    }
+                    return Operand::Plain(Z::Value::Constant(A::ConstantValue::Int(0)));
+                }
+
+                return Operand::Plain(self.emit_conversion(src, &ty));
+            },
+
+            A::ExpressionKind::Assignment(left, right) => {
+                let left = self.parse_operand(*left);
+                let src = self.parse_value(*right);
+                return Operand::Plain(self.store(left, src));
+            },
+
+            A::ExpressionKind::FunctionCall(function, arguments) => {
+                let function = match function.kind {
+                    A::ExpressionKind::Variable(name) if matches!(function.ty(), Type::Function(_)) => Z::Callee::Direct(name),
+                    _ => Z::Callee::Indirect(self.parse_value(*function))
+                };
+                let arguments = arguments.into_iter().map(|a| self.parse_value(a)).collect();
+                // The type checker rejects any use of a void result, so any value will do
+                let dst = match ty {
+                    Type::Void => None,
+                    ty => Some(self.make_temporary(ty))
+                };
+
+                self.instructions.push(Z::Instruction::FunctionCall(Z::FunctionCallDefinition {
+                    function,
+                    arguments,
+                    destination: dst.clone()
+                }));
+
+                return Operand::Plain(dst.unwrap_or(Z::Value::Constant(A::ConstantValue::Int(0))));
+            },
+
+            A::ExpressionKind::AddressOf(inner) => {
+                match self.parse_operand(*inner) {
+                    Operand::Plain(value) => {
+                        let dst = self.make_temporary(Type::Pointer(Box::new(self.type_of(&value))));
+                        self.instructions.push(Z::Instruction::GetAddress(Z::GetAddressInstructionDefinition {
+                            source: value,
+                            destination: dst.clone()
+                        }));
+
+                        return Operand::Plain(dst);
+                    },
+                    // `&*p` is just `p`
+                    Operand::Dereferenced(pointer) => Operand::Plain(pointer),
+                    Operand::SubObject(base, offset, ty) => {
+                        let base_type = self.symbols.type_of(&base).clone();
+                        let address = self.make_temporary(Type::Pointer(Box::new(base_type)));
+                        self.instructions.push(Z::Instruction::GetAddress(Z::GetAddressInstructionDefinition {
+                            source: Z::Value::Variable(base),
+                            destination: address.clone()
+                        }));
+
+                        return Operand::Plain(self.member_pointer(address, offset, ty));
+                    },
+                    Operand::BitField(_, _) => panic!("Internal ZIL error -- address of a bit-field")
+                }
+            },
+
+            A::ExpressionKind::Dereference(inner) => {
+                let pointer = self.parse_value(*inner);
+                return Operand::Dereferenced(pointer);
+            },
+
+            // The type checker placed the pointer on the left
+            A::ExpressionKind::Subscript(pointer, index) => {
+                let pointer = self.parse_value(*pointer);
+                let index = self.parse_value(*index);
+                let scale = match self.type_of(&pointer) {
+                    Type::Pointer(pointee) => self.size_value(&pointee),
+                    _ => panic!("Internal ZIL error -- subscript of a non-pointer")
+                };
+
+                let address = self.parse_pointer_arithmetic(A::BinaryExpressionType::Add, pointer, index, scale);
+                return Operand::Dereferenced(address);
+            },
+
+            // Members of variables are accessed at an offset within them,
+            // members of other objects through a pointer to the member
+            A::ExpressionKind::Dot(inner, name) => {
+                let inner = self.parse_operand(*inner);
+                let member = self.member(&self.operand_type(&inner), &name);
+                let (offset, member_type) = (member.offset, member.ty);
+
+                let operand = match inner {
+                    Operand::Plain(Z::Value::Variable(base)) => Operand::SubObject(base, offset, member_type),
+                    Operand::Plain(Z::Value::Constant(_)) => panic!("Internal ZIL error -- member of a constant"),
+                    Operand::SubObject(base, base_offset, _) => Operand::SubObject(base, base_offset + offset, member_type),
+                    Operand::Dereferenced(pointer) => Operand::Dereferenced(self.member_pointer(pointer, offset, member_type)),
+                    Operand::BitField(_, _) => panic!("Internal ZIL error -- member of a bit-field")
+                };
+                return Self::with_bit_field(operand, member.bit_field);
+            },
+
+            A::ExpressionKind::Arrow(inner, name) => {
+                let pointer = self.parse_value(*inner);
+                let structure = match self.type_of(&pointer) {
+                    Type::Pointer(pointee) => *pointee,
+                    _ => panic!("Internal ZIL error -- member access through a non-pointer")
+                };
+
+                let member = self.member(&structure, &name);
+                let operand = Operand::Dereferenced(self.member_pointer(pointer, member.offset, member.ty));
+                return Self::with_bit_field(operand, member.bit_field);
+            },
+
+            A::ExpressionKind::SizeOf(_) | A::ExpressionKind::SizeOfType(_) | A::ExpressionKind::AlignOf(_) => {
+                panic!("Internal ZIL error -- `sizeof` should have been folded by the resolver")
             }
         }
     }
 
+    // Adds an integer to (or subtracts it from) a pointer, after scaling it by
+    // the size of the pointee, or divides the difference of two pointers by it
+    fn parse_pointer_arithmetic(&mut self, op: A::BinaryExpressionType, pointer: Z::Value, other: Z::Value, scale: Z::Value) -> Z::Value {
+        use Z::BinaryInstructionOperator as O;
+
+        if self.type_of(&other).is_pointer() {
+            let difference = self.emit_binary(O::Subtract, pointer, other, Type::Long);
+            return self.emit_binary(O::Divide, difference, scale, Type::Long);
+        }
+
+        let pointer_type = self.type_of(&pointer);
+        let offset = self.emit_binary(O::Multiply, other, scale, Type::Long);
+        let operator = match op {
+            A::BinaryExpressionType::Add => O::Add,
+            _ => O::Subtract
+        };
+
+        return self.emit_binary(operator, pointer, offset, pointer_type);
+    }
+
+    // Converting to `_Bool` compares the value with 0 rather than truncating it, so
+    // that e.g. `(_Bool)256` is 1. The comparison yields an int, which is then
+    // truncated to the single byte of the `_Bool`
+    fn convert_to_bool(&mut self, src: Z::Value, src_type: &Type) -> Z::Value {
+        let zero = A::ConstantValue::Int(0);
+        let zero = match src_type {
+            Type::Pointer(_) => A::ConstantValue::ULong(0),
+            ty => zero.convert(&ty.promote()).expect("Zero converts to any arithmetic type")
+        };
+        let comparison = self.emit_binary(Z::BinaryInstructionOperator::NotEqual, src, Z::Value::Constant(zero), Type::Int);
+
+        let dst = self.make_temporary(Type::Bool);
+        self.instructions.push(Z::Instruction::Truncate(Z::ConvertInstructionDefinition {
+            source: comparison,
+            destination: dst.clone()
+        }));
+
+        return dst;
+    }
+
+    fn emit_binary(&mut self, operator: Z::BinaryInstructionOperator, left: Z::Value, right: Z::Value, ty: Type) -> Z::Value {
+        let dst = self.make_temporary(ty);
+        self.instructions.push(Z::Instruction::Binary(Z::BinaryInstructionDefinition {
+            operator,
+            left,
+            right,
+            destination: dst.clone()
+        }));
+
+        return dst;
+    }
+
     fn convert_unop(op: A::UnaryExpressionType) -> Z::UnaryInstructionOperator {
         match op {
             A::UnaryExpressionType::Complement => Z::UnaryInstructionOperator::Complement,
             A::UnaryExpressionType::Negation => Z::UnaryInstructionOperator::Negate,
         }
     }
+
+    fn convert_binop(op: A::BinaryExpressionType) -> Z::BinaryInstructionOperator {
+        use A::BinaryExpressionType as B;
+        use Z::BinaryInstructionOperator as O;
+        match op {
+            B::Add => O::Add,
+            B::Subtract => O::Subtract,
+            B::Multiply => O::Multiply,
+            B::Divide => O::Divide,
+            B::Remainder => O::Remainder,
+            B::BitAnd => O::BitAnd,
+            B::BitOr => O::BitOr,
+            B::BitXor => O::BitXor,
+            B::ShiftLeft => O::ShiftLeft,
+            B::ShiftRight => O::ShiftRight,
+            B::Equal => O::Equal,
+            B::NotEqual => O::NotEqual,
+            B::Less => O::Less,
+            B::LessEqual => O::LessEqual,
+            B::Greater => O::Greater,
+            B::GreaterEqual => O::GreaterEqual,
+        }
+    }
 }
 
 impl Z::Construct {
@@ -84,6 +764,8 @@ impl Z::Construct {
         Self::Function(
             Z::FunctionDefinition {
                 identifier: t.f_name,
+                global: t.global,
+                params: t.params,
                 instructions: t.instructions
             }
         )